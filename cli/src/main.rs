@@ -0,0 +1,1098 @@
+use std::error::Error;
+
+use clap::{ArgEnum, Parser, Subcommand};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{read_keypair_file, Keypair, Signer},
+    transaction::Transaction,
+};
+use spl_associated_token_account::{
+    get_associated_token_address, instruction::create_associated_token_account_idempotent,
+};
+use tokentracing::{
+    find_booth_address, find_metadata_address, find_multisig_address, find_registry_address,
+    find_rewards_address, instruction,
+    state::{
+        AccessMode, AccountState, BoothMetadata, BoothRegistry, CurveType, ExchangeAccount,
+        FeeTier, LimitOrder, OrderSide, RewardPool,
+    },
+};
+
+type CliResult<T> = Result<T, Box<dyn Error>>;
+
+/// Program deployed on testnet, see the README.
+const DEFAULT_PROGRAM_ID: &str = "HEukgy1GRqu8G7H7K19AtLSzDRKq7eB5PwAYUNY22LqM";
+
+/// Operate token tracing exchange booths.
+#[derive(Parser)]
+#[clap(name = "tokentracing-cli", version)]
+struct Cli {
+    /// JSON-RPC URL, defaults to the Solana CLI config
+    #[clap(long, short = 'u', global = true)]
+    url: Option<String>,
+    /// Keypair file paying for and signing transactions, defaults to the Solana CLI config
+    #[clap(long, short = 'k', global = true)]
+    keypair: Option<String>,
+    /// Exchange booth program id
+    #[clap(long, global = true, default_value = DEFAULT_PROGRAM_ID)]
+    program_id: Pubkey,
+    /// Keypair file of a booth multisig signer, repeat for each; admin
+    /// commands then act through the booth multisig
+    #[clap(long = "signer", global = true)]
+    signers: Vec<String>,
+    /// Booth of the mint to act on
+    #[clap(long, global = true, default_value_t = 0)]
+    booth_id: u64,
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create a booth for a mint, signer becomes the admin
+    Init {
+        #[clap(long)]
+        mint: Pubkey,
+    },
+    /// Swap against a booth
+    Swap {
+        #[clap(long)]
+        mint: Pubkey,
+        #[clap(arg_enum)]
+        direction: Direction,
+        /// Lamports for sol-to-token, token base units for token-to-sol
+        amount: u64,
+        /// Wallet that referred this swap, paid the booth referral share of the fee
+        #[clap(long)]
+        referrer: Option<Pubkey>,
+    },
+    /// Buy tokens for several wallets in one all-or-nothing swap
+    BatchSwap {
+        #[clap(long)]
+        mint: Pubkey,
+        /// `<WALLET>=<LAMPORTS>`, paying the tokens to the wallet's associated token account
+        #[clap(required = true, value_parser = parse_payment)]
+        payments: Vec<(Pubkey, u64)>,
+    },
+    /// Print what a swap would pay out at the current rate
+    Quote {
+        #[clap(long)]
+        mint: Pubkey,
+        #[clap(arg_enum)]
+        direction: Direction,
+        amount: u64,
+    },
+    /// Add liquidity to a booth
+    Deposit {
+        #[clap(long)]
+        mint: Pubkey,
+        #[clap(long, default_value_t = 0)]
+        lamports: u64,
+        #[clap(long, default_value_t = 0)]
+        tokens: u64,
+    },
+    /// Burn LP tokens for their share of the booth reserves
+    RemoveLiquidity {
+        #[clap(long)]
+        mint: Pubkey,
+        /// LP tokens to burn
+        #[clap(long)]
+        lp: u64,
+    },
+    /// Pay out booth reserves once no LP tokens are left (admin)
+    Withdraw {
+        #[clap(long)]
+        mint: Pubkey,
+        #[clap(long, default_value_t = 0)]
+        lamports: u64,
+        #[clap(long, default_value_t = 0)]
+        tokens: u64,
+        /// Wallet receiving the liquidity, defaults to the payer
+        #[clap(long)]
+        receiver: Option<Pubkey>,
+    },
+    /// Queue a new rate of token base units paid per lamport (admin)
+    SetRate {
+        #[clap(long)]
+        mint: Pubkey,
+        rate: u64,
+    },
+    /// Pause swaps, or resume them with --resume (admin)
+    Pause {
+        #[clap(long)]
+        mint: Pubkey,
+        #[clap(long)]
+        resume: bool,
+    },
+    /// Cap the lamports of swap value per wallet per window, 0 to remove (admin)
+    SetLimit {
+        #[clap(long)]
+        mint: Pubkey,
+        lamports: u64,
+        /// Window length in seconds
+        #[clap(long, default_value_t = 86_400)]
+        window: i64,
+    },
+    /// Pay swap referrers a share of the booth fee in bps, 0 to stop (admin)
+    SetReferralShare {
+        #[clap(long)]
+        mint: Pubkey,
+        share_bps: u16,
+    },
+    /// Replace the volume fee tiers, none to remove them (admin)
+    SetFeeTiers {
+        #[clap(long)]
+        mint: Pubkey,
+        /// `<LAMPORTS>=<BPS>`: wallets that have swapped at least LAMPORTS pay BPS
+        #[clap(value_parser = parse_fee_tier)]
+        tiers: Vec<FeeTier>,
+    },
+    /// Pause the booth when payouts per window exceed a cap, 0 disables a side (admin)
+    SetBreaker {
+        #[clap(long)]
+        mint: Pubkey,
+        #[clap(long, default_value_t = 0)]
+        lamports: u64,
+        #[clap(long, default_value_t = 0)]
+        tokens: u64,
+        /// Window length in seconds
+        #[clap(long, default_value_t = 3_600)]
+        window: i64,
+    },
+    /// Choose which wallets may swap (admin)
+    SetAccess {
+        #[clap(long)]
+        mint: Pubkey,
+        #[clap(arg_enum)]
+        mode: Access,
+    },
+    /// Add a wallet to the booth allow- or denylist (admin)
+    Allow {
+        #[clap(long)]
+        mint: Pubkey,
+        wallet: Pubkey,
+    },
+    /// Remove a wallet from the booth allow- or denylist (admin)
+    Disallow {
+        #[clap(long)]
+        mint: Pubkey,
+        wallet: Pubkey,
+    },
+    /// Require swaps to carry an attestation, omit --attestor to lift it (admin)
+    SetAttestor {
+        #[clap(long)]
+        mint: Pubkey,
+        #[clap(long)]
+        attestor: Option<Pubkey>,
+    },
+    /// Vouch for a wallet as the signing attestor
+    Attest {
+        wallet: Pubkey,
+        /// Unix timestamp the attestation expires at, 0 for never
+        #[clap(long, default_value_t = 0)]
+        expires_at: i64,
+    },
+    /// Withdraw an attestation as the signing attestor
+    Revoke { wallet: Pubkey },
+    /// Hand the booth to an M-of-N multisig of the given keys (admin)
+    SetMultisig {
+        #[clap(long)]
+        mint: Pubkey,
+        #[clap(long)]
+        threshold: u8,
+        #[clap(required = true)]
+        keys: Vec<Pubkey>,
+    },
+    /// Hand the booth to a new admin key (admin)
+    SetAdmin {
+        #[clap(long)]
+        mint: Pubkey,
+        admin: Pubkey,
+    },
+    /// Pay out all liquidity and close the booth (admin)
+    Close {
+        #[clap(long)]
+        mint: Pubkey,
+        /// Wallet receiving the liquidity, defaults to the payer
+        #[clap(long)]
+        receiver: Option<Pubkey>,
+    },
+    /// Queue new pricing, unset options keep the current value (admin)
+    Propose {
+        #[clap(long)]
+        mint: Pubkey,
+        #[clap(long)]
+        rate: Option<u64>,
+        #[clap(long)]
+        fee_bps: Option<u16>,
+        #[clap(long)]
+        timelock_slots: Option<u64>,
+    },
+    /// Drop the queued pricing change (admin)
+    CancelChange {
+        #[clap(long)]
+        mint: Pubkey,
+    },
+    /// Apply the queued pricing change once its timelock has passed
+    ExecuteChange {
+        #[clap(long)]
+        mint: Pubkey,
+    },
+    /// Rest an order against the booth until its quote pays at least --min-out
+    PlaceOrder {
+        #[clap(long)]
+        mint: Pubkey,
+        /// Any number distinguishing this order from the payer's others
+        #[clap(long)]
+        id: u64,
+        #[clap(arg_enum)]
+        direction: Direction,
+        /// Lamports for sol-to-token, token base units for token-to-sol
+        amount: u64,
+        #[clap(long)]
+        min_out: u64,
+    },
+    /// Withdraw a resting order
+    CancelOrder {
+        #[clap(long)]
+        mint: Pubkey,
+        #[clap(long)]
+        id: u64,
+    },
+    /// Fill every resting order the booth can fill now, collecting crank rewards
+    FillOrders {
+        #[clap(long)]
+        mint: Pubkey,
+    },
+    /// Escrow lamports that any cranker swaps for tokens one period at a time
+    Dca {
+        #[clap(long)]
+        mint: Pubkey,
+        /// Any number distinguishing this order from the payer's others
+        #[clap(long)]
+        id: u64,
+        /// Lamports swapped each period
+        #[clap(long)]
+        amount: u64,
+        /// Seconds between swaps
+        #[clap(long)]
+        interval: i64,
+        /// Lamports escrowed up front
+        #[clap(long)]
+        deposit: u64,
+    },
+    /// Withdraw a DCA order and its remaining escrow
+    CloseDca {
+        #[clap(long)]
+        mint: Pubkey,
+        #[clap(long)]
+        id: u64,
+    },
+    /// Swap the due period of a DCA order
+    ExecuteDca {
+        #[clap(long)]
+        mint: Pubkey,
+        #[clap(long)]
+        owner: Pubkey,
+        #[clap(long)]
+        id: u64,
+    },
+    /// Create the booth reward pool paying LP token stakers in another mint (admin)
+    InitRewards {
+        #[clap(long)]
+        mint: Pubkey,
+        /// Mint rewards are paid in
+        #[clap(long)]
+        reward_mint: Pubkey,
+    },
+    /// Add reward tokens from the signer's account and set the emission rate (admin)
+    FundRewards {
+        #[clap(long)]
+        mint: Pubkey,
+        /// Reward tokens added to the pool
+        #[clap(long)]
+        amount: u64,
+        /// Reward tokens emitted per second across all stakers
+        #[clap(long)]
+        rate: u64,
+    },
+    /// Stake tokens in the booth reward pool
+    Stake {
+        #[clap(long)]
+        mint: Pubkey,
+        amount: u64,
+    },
+    /// Withdraw staked tokens from the booth reward pool
+    Unstake {
+        #[clap(long)]
+        mint: Pubkey,
+        amount: u64,
+    },
+    /// Claim the rewards earned by the signer's stake
+    ClaimRewards {
+        #[clap(long)]
+        mint: Pubkey,
+    },
+    /// Upgrade a booth created by an older program version (admin)
+    Migrate {
+        #[clap(long)]
+        mint: Pubkey,
+    },
+    /// Set the booth name, symbol, description and URI (admin)
+    SetMetadata {
+        #[clap(long)]
+        mint: Pubkey,
+        #[clap(long)]
+        name: String,
+        #[clap(long, default_value = "")]
+        symbol: String,
+        #[clap(long, default_value = "")]
+        description: String,
+        #[clap(long, default_value = "")]
+        uri: String,
+    },
+    /// Print the decoded booth state
+    Show {
+        #[clap(long)]
+        mint: Pubkey,
+    },
+    /// List the booths in the on-chain registry
+    List {
+        /// Zero-based page to print
+        #[clap(long, default_value_t = 0)]
+        page: u32,
+        #[clap(long, default_value_t = 20)]
+        page_size: u32,
+    },
+}
+
+#[derive(ArgEnum, Clone, Copy)]
+enum Direction {
+    SolToToken,
+    TokenToSol,
+}
+
+#[derive(ArgEnum, Clone, Copy)]
+enum Access {
+    Open,
+    Allowlist,
+    Denylist,
+}
+
+impl From<Direction> for OrderSide {
+    fn from(direction: Direction) -> Self {
+        match direction {
+            Direction::SolToToken => OrderSide::SolToToken,
+            Direction::TokenToSol => OrderSide::TokenToSol,
+        }
+    }
+}
+
+impl From<Access> for AccessMode {
+    fn from(access: Access) -> Self {
+        match access {
+            Access::Open => AccessMode::Open,
+            Access::Allowlist => AccessMode::Allowlist,
+            Access::Denylist => AccessMode::Denylist,
+        }
+    }
+}
+
+fn parse_payment(payment: &str) -> Result<(Pubkey, u64), String> {
+    let (wallet, lamports) = payment
+        .split_once('=')
+        .ok_or("expected <WALLET>=<LAMPORTS>")?;
+    Ok((
+        wallet
+            .parse()
+            .map_err(|err| format!("{}: {:?}", wallet, err))?,
+        lamports
+            .parse()
+            .map_err(|err| format!("{}: {}", lamports, err))?,
+    ))
+}
+
+fn parse_fee_tier(tier: &str) -> Result<FeeTier, String> {
+    let (min_volume, fee_bps) = tier.split_once('=').ok_or("expected <LAMPORTS>=<BPS>")?;
+    Ok(FeeTier {
+        min_volume: min_volume
+            .parse()
+            .map_err(|err| format!("{}: {}", min_volume, err))?,
+        fee_bps: fee_bps
+            .parse()
+            .map_err(|err| format!("{}: {}", fee_bps, err))?,
+    })
+}
+
+struct Context {
+    client: RpcClient,
+    payer: Keypair,
+    program_id: Pubkey,
+    /// Booth multisig signers from `--signer`.
+    signers: Vec<Keypair>,
+    booth_id: u64,
+}
+
+impl Context {
+    fn send(&self, instructions: &[Instruction]) -> CliResult<()> {
+        let blockhash = self.client.get_latest_blockhash()?;
+        let mut signers: Vec<&Keypair> = vec![&self.payer];
+        signers.extend(self.signers.iter().filter(|signer| {
+            instructions.iter().any(|instruction| {
+                instruction
+                    .accounts
+                    .iter()
+                    .any(|account| account.is_signer && account.pubkey == signer.pubkey())
+            })
+        }));
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.payer.pubkey()),
+            &signers,
+            blockhash,
+        );
+        let signature = self.client.send_and_confirm_transaction(&transaction)?;
+        println!("Signature: {}", signature);
+        Ok(())
+    }
+
+    fn create_ata(&self, owner: &Pubkey, mint: &Pubkey) -> Instruction {
+        create_associated_token_account_idempotent(
+            &self.payer.pubkey(),
+            owner,
+            mint,
+            &spl_token::id(),
+        )
+    }
+
+    /// The booth admin key to build admin instructions with: the booth
+    /// multisig when `--signer` is given, the payer otherwise.
+    fn admin(&self, mint: &Pubkey) -> Pubkey {
+        if self.signers.is_empty() {
+            return self.payer.pubkey();
+        }
+        let (vault, _) = find_booth_address(&self.program_id, mint, self.booth_id);
+        find_multisig_address(&self.program_id, &vault).0
+    }
+
+    /// Add the `--signer` keys to an instruction built with [`Context::admin`].
+    fn authorize(&self, mint: &Pubkey, instruction: Instruction) -> Instruction {
+        if self.signers.is_empty() {
+            return instruction;
+        }
+        let signers: Vec<Pubkey> = self.signers.iter().map(|signer| signer.pubkey()).collect();
+        instruction::with_multisig_signers(instruction, &self.admin(mint), &signers)
+    }
+
+    fn load_exchange(&self, mint: &Pubkey) -> CliResult<ExchangeAccount> {
+        let (vault, _) = find_booth_address(&self.program_id, mint, self.booth_id);
+        let data = self.client.get_account_data(&vault)?;
+        Ok(ExchangeAccount::unpack(&data)?)
+    }
+
+    fn load_rewards(&self, mint: &Pubkey) -> CliResult<RewardPool> {
+        let (vault, _) = find_booth_address(&self.program_id, mint, self.booth_id);
+        let (rewards, _) = find_rewards_address(&self.program_id, &vault);
+        let data = self.client.get_account_data(&rewards)?;
+        Ok(RewardPool::unpack(&data)?)
+    }
+}
+
+fn main() {
+    if let Err(err) = run(Cli::parse()) {
+        eprintln!("error: {}", err);
+        std::process::exit(1);
+    }
+}
+
+fn run(cli: Cli) -> CliResult<()> {
+    let config = match solana_cli_config::CONFIG_FILE.as_ref() {
+        Some(path) => solana_cli_config::Config::load(path).unwrap_or_default(),
+        None => solana_cli_config::Config::default(),
+    };
+    let url = cli.url.unwrap_or(config.json_rpc_url);
+    let keypair_path = cli.keypair.unwrap_or(config.keypair_path);
+    let read_keypair = |path: &String| {
+        read_keypair_file(path).map_err(|err| format!("failed to read keypair {}: {}", path, err))
+    };
+    let payer = read_keypair(&keypair_path)?;
+    let signers = cli
+        .signers
+        .iter()
+        .map(read_keypair)
+        .collect::<Result<Vec<_>, _>>()?;
+    let ctx = Context {
+        client: RpcClient::new_with_commitment(url, CommitmentConfig::confirmed()),
+        payer,
+        program_id: cli.program_id,
+        signers,
+        booth_id: cli.booth_id,
+    };
+    let payer = ctx.payer.pubkey();
+    let program_id = &ctx.program_id;
+    let booth_id = ctx.booth_id;
+
+    match cli.command {
+        Command::Init { mint } => {
+            let (vault, _) = find_booth_address(program_id, &mint, booth_id);
+            ctx.send(&[
+                instruction::initialize(program_id, &payer, &mint, booth_id),
+                ctx.create_ata(&vault, &mint),
+            ])?;
+            println!("Vault: {}", vault);
+        }
+        Command::Swap {
+            mint,
+            direction,
+            amount,
+            referrer,
+        } => {
+            let exchange = ctx.load_exchange(&mint)?;
+            let swap = match direction {
+                Direction::SolToToken => {
+                    instruction::exchange_sol_to_token(program_id, &payer, &mint, booth_id, amount)
+                }
+                Direction::TokenToSol => {
+                    instruction::exchange_token_to_sol(program_id, &payer, &mint, booth_id, amount)
+                }
+            };
+            let swap = match exchange.attestor() {
+                Some(attestor) => instruction::with_attestation(program_id, swap, &attestor),
+                None => swap,
+            };
+            let mut instructions = vec![ctx.create_ata(&payer, &mint)];
+            let swap = match referrer {
+                Some(referrer) => {
+                    instructions.push(ctx.create_ata(&referrer, &mint));
+                    instruction::with_referrer(program_id, swap, &referrer)
+                }
+                None => swap,
+            };
+            instructions.push(swap);
+            ctx.send(&instructions)?;
+        }
+        Command::BatchSwap { mint, payments } => {
+            let exchange = ctx.load_exchange(&mint)?;
+            let recipients: Vec<(Pubkey, u64)> = payments
+                .iter()
+                .map(|(wallet, lamports)| (get_associated_token_address(wallet, &mint), *lamports))
+                .collect();
+            let mut instructions: Vec<Instruction> = payments
+                .iter()
+                .map(|(wallet, _)| ctx.create_ata(wallet, &mint))
+                .collect();
+            instructions.push(instruction::batch_exchange_sol_to_token(
+                program_id,
+                &payer,
+                &mint,
+                booth_id,
+                &recipients,
+                exchange.attestor().as_ref(),
+            ));
+            ctx.send(&instructions)?;
+        }
+        Command::Quote {
+            mint,
+            direction,
+            amount,
+        } => {
+            let exchange = ctx.load_exchange(&mint)?;
+            match direction {
+                Direction::SolToToken => println!(
+                    "{} lamports -> {} tokens",
+                    amount,
+                    exchange.quote_sol_to_token(amount)?
+                ),
+                Direction::TokenToSol => println!(
+                    "{} tokens -> {} lamports",
+                    amount,
+                    exchange.quote_token_to_sol(amount)?
+                ),
+            }
+        }
+        Command::Deposit {
+            mint,
+            lamports,
+            tokens,
+        } => {
+            ctx.send(&[instruction::deposit(
+                program_id, &payer, &mint, booth_id, lamports, tokens,
+            )])?;
+        }
+        Command::RemoveLiquidity { mint, lp } => {
+            ctx.send(&[
+                ctx.create_ata(&payer, &mint),
+                instruction::remove_liquidity(program_id, &payer, &mint, booth_id, lp),
+            ])?;
+        }
+        Command::Withdraw {
+            mint,
+            lamports,
+            tokens,
+            receiver,
+        } => {
+            let receiver = receiver.unwrap_or(payer);
+            let admin = ctx.admin(&mint);
+            ctx.send(&[
+                ctx.create_ata(&receiver, &mint),
+                ctx.authorize(
+                    &mint,
+                    instruction::withdraw(
+                        program_id, &admin, &receiver, &mint, booth_id, lamports, tokens,
+                    ),
+                ),
+            ])?;
+        }
+        Command::SetRate { mint, rate } => {
+            let exchange = ctx.load_exchange(&mint)?;
+            let admin = ctx.admin(&mint);
+            ctx.send(&[ctx.authorize(
+                &mint,
+                instruction::set_rate(program_id, &admin, &mint, booth_id, rate),
+            )])?;
+            println!(
+                "Executable from slot {}",
+                ctx.client.get_slot()? + exchange.timelock_delay()
+            );
+        }
+        Command::Pause { mint, resume } => {
+            let admin = ctx.admin(&mint);
+            ctx.send(&[ctx.authorize(
+                &mint,
+                instruction::set_paused(program_id, &admin, &mint, booth_id, !resume),
+            )])?;
+        }
+        Command::SetReferralShare { mint, share_bps } => {
+            let admin = ctx.admin(&mint);
+            ctx.send(&[ctx.authorize(
+                &mint,
+                instruction::set_referral_share(program_id, &admin, &mint, booth_id, share_bps),
+            )])?;
+        }
+        Command::SetFeeTiers { mint, tiers } => {
+            let admin = ctx.admin(&mint);
+            ctx.send(&[ctx.authorize(
+                &mint,
+                instruction::set_fee_tiers(program_id, &admin, &mint, booth_id, &tiers),
+            )])?;
+        }
+        Command::SetLimit {
+            mint,
+            lamports,
+            window,
+        } => {
+            let admin = ctx.admin(&mint);
+            ctx.send(&[ctx.authorize(
+                &mint,
+                instruction::set_wallet_limit(
+                    program_id, &admin, &mint, booth_id, lamports, window,
+                ),
+            )])?;
+        }
+        Command::SetBreaker {
+            mint,
+            lamports,
+            tokens,
+            window,
+        } => {
+            let admin = ctx.admin(&mint);
+            ctx.send(&[ctx.authorize(
+                &mint,
+                instruction::set_circuit_breaker(
+                    program_id, &admin, &mint, booth_id, lamports, tokens, window,
+                ),
+            )])?;
+        }
+        Command::SetAccess { mint, mode } => {
+            let admin = ctx.admin(&mint);
+            ctx.send(&[ctx.authorize(
+                &mint,
+                instruction::set_access_mode(program_id, &admin, &mint, booth_id, mode.into()),
+            )])?;
+        }
+        Command::Allow { mint, wallet } => {
+            let admin = ctx.admin(&mint);
+            ctx.send(&[ctx.authorize(
+                &mint,
+                instruction::add_access_entry(program_id, &payer, &admin, &mint, booth_id, &wallet),
+            )])?;
+        }
+        Command::Disallow { mint, wallet } => {
+            let admin = ctx.admin(&mint);
+            ctx.send(&[ctx.authorize(
+                &mint,
+                instruction::remove_access_entry(
+                    program_id, &admin, &mint, booth_id, &wallet, &payer,
+                ),
+            )])?;
+        }
+        Command::SetAttestor { mint, attestor } => {
+            let admin = ctx.admin(&mint);
+            ctx.send(&[ctx.authorize(
+                &mint,
+                instruction::set_attestor(program_id, &admin, &mint, booth_id, attestor),
+            )])?;
+        }
+        Command::Attest { wallet, expires_at } => {
+            ctx.send(&[instruction::attest(
+                program_id, &payer, &payer, &wallet, expires_at,
+            )])?;
+        }
+        Command::Revoke { wallet } => {
+            ctx.send(&[instruction::revoke_attestation(
+                program_id, &payer, &wallet, &payer,
+            )])?;
+        }
+        Command::SetMultisig {
+            mint,
+            threshold,
+            keys,
+        } => {
+            let admin = ctx.admin(&mint);
+            ctx.send(&[ctx.authorize(
+                &mint,
+                instruction::set_multisig(
+                    program_id, &payer, &admin, &mint, booth_id, threshold, keys,
+                ),
+            )])?;
+        }
+        Command::SetAdmin {
+            mint,
+            admin: new_admin,
+        } => {
+            let admin = ctx.admin(&mint);
+            ctx.send(&[ctx.authorize(
+                &mint,
+                instruction::set_admin(program_id, &admin, &mint, booth_id, &new_admin),
+            )])?;
+        }
+        Command::Close { mint, receiver } => {
+            let receiver = receiver.unwrap_or(payer);
+            let admin = ctx.admin(&mint);
+            ctx.send(&[
+                ctx.create_ata(&receiver, &mint),
+                ctx.authorize(
+                    &mint,
+                    instruction::close_exchange(program_id, &admin, &receiver, &mint, booth_id),
+                ),
+            ])?;
+        }
+        Command::Propose {
+            mint,
+            rate,
+            fee_bps,
+            timelock_slots,
+        } => {
+            let exchange = ctx.load_exchange(&mint)?;
+            let admin = ctx.admin(&mint);
+            ctx.send(&[ctx.authorize(
+                &mint,
+                instruction::propose_change(
+                    program_id,
+                    &admin,
+                    &mint,
+                    booth_id,
+                    rate.unwrap_or(exchange.rate),
+                    fee_bps.unwrap_or(exchange.fee_bps),
+                    CurveType::ConstantPrice,
+                    timelock_slots.unwrap_or(exchange.timelock_slots),
+                ),
+            )])?;
+            println!(
+                "Executable from slot {}",
+                ctx.client.get_slot()? + exchange.timelock_delay()
+            );
+        }
+        Command::CancelChange { mint } => {
+            let admin = ctx.admin(&mint);
+            ctx.send(&[ctx.authorize(
+                &mint,
+                instruction::cancel_change(program_id, &admin, &mint, booth_id),
+            )])?;
+        }
+        Command::ExecuteChange { mint } => {
+            ctx.send(&[instruction::execute_change(program_id, &mint, booth_id)])?;
+        }
+        Command::PlaceOrder {
+            mint,
+            id,
+            direction,
+            amount,
+            min_out,
+        } => {
+            let exchange = ctx.load_exchange(&mint)?;
+            let order = instruction::place_limit_order(
+                program_id,
+                &payer,
+                &mint,
+                booth_id,
+                id,
+                direction.into(),
+                amount,
+                min_out,
+            );
+            let order = match exchange.attestor() {
+                Some(attestor) => instruction::with_attestation(program_id, order, &attestor),
+                None => order,
+            };
+            ctx.send(&[ctx.create_ata(&payer, &mint), order])?;
+        }
+        Command::CancelOrder { mint, id } => {
+            ctx.send(&[
+                ctx.create_ata(&payer, &mint),
+                instruction::cancel_limit_order(program_id, &payer, &mint, booth_id, id),
+            ])?;
+        }
+        Command::FillOrders { mint } => {
+            let (vault, _) = find_booth_address(program_id, &mint, booth_id);
+            let exchange = ctx.load_exchange(&mint)?;
+            let fillable: Vec<(Pubkey, u64)> = ctx
+                .client
+                .get_program_accounts(program_id)?
+                .into_iter()
+                .filter_map(|(_, account)| LimitOrder::unpack(&account.data).ok())
+                .filter(|order| order.vault == vault)
+                .filter(|order| {
+                    let quote = match order.side {
+                        OrderSide::SolToToken => exchange.quote_sol_to_token(order.amount),
+                        OrderSide::TokenToSol => exchange.quote_token_to_sol(order.amount),
+                    };
+                    quote.map_or(false, |quote| quote >= order.min_out)
+                })
+                .map(|order| (order.owner, order.order_id))
+                .collect();
+            // seven accounts per order keep a batch of three inside the transaction size limit
+            for orders in fillable.chunks(3) {
+                let mut instructions: Vec<Instruction> = orders
+                    .iter()
+                    .map(|(owner, _)| ctx.create_ata(owner, &mint))
+                    .collect();
+                instructions.push(instruction::fill_limit_orders(
+                    program_id,
+                    &payer,
+                    &mint,
+                    booth_id,
+                    orders,
+                    exchange.attestor().as_ref(),
+                ));
+                ctx.send(&instructions)?;
+            }
+            println!("{} orders filled", fillable.len());
+        }
+        Command::Dca {
+            mint,
+            id,
+            amount,
+            interval,
+            deposit,
+        } => {
+            let exchange = ctx.load_exchange(&mint)?;
+            let dca = instruction::create_dca(
+                program_id, &payer, &mint, booth_id, id, amount, interval, deposit,
+            );
+            let dca = match exchange.attestor() {
+                Some(attestor) => instruction::with_attestation(program_id, dca, &attestor),
+                None => dca,
+            };
+            ctx.send(&[dca])?;
+        }
+        Command::CloseDca { mint, id } => {
+            ctx.send(&[instruction::close_dca(
+                program_id, &payer, &mint, booth_id, id,
+            )])?;
+        }
+        Command::ExecuteDca { mint, owner, id } => {
+            let exchange = ctx.load_exchange(&mint)?;
+            ctx.send(&[
+                ctx.create_ata(&owner, &mint),
+                instruction::execute_dca(
+                    program_id,
+                    &payer,
+                    &owner,
+                    &mint,
+                    booth_id,
+                    id,
+                    exchange.attestor().as_ref(),
+                ),
+            ])?;
+        }
+        Command::InitRewards { mint, reward_mint } => {
+            let admin = ctx.admin(&mint);
+            ctx.send(&[ctx.authorize(
+                &mint,
+                instruction::initialize_rewards(
+                    program_id,
+                    &payer,
+                    &admin,
+                    &mint,
+                    booth_id,
+                    &reward_mint,
+                ),
+            )])?;
+        }
+        Command::FundRewards { mint, amount, rate } => {
+            let admin = ctx.admin(&mint);
+            let pool = ctx.load_rewards(&mint)?;
+            ctx.send(&[ctx.authorize(
+                &mint,
+                instruction::fund_rewards(
+                    program_id,
+                    &payer,
+                    &admin,
+                    &mint,
+                    booth_id,
+                    &pool.reward_mint,
+                    amount,
+                    rate,
+                ),
+            )])?;
+        }
+        Command::Stake { mint, amount } => {
+            ctx.send(&[instruction::stake(
+                program_id, &payer, &mint, booth_id, amount,
+            )])?;
+        }
+        Command::Unstake { mint, amount } => {
+            ctx.send(&[instruction::unstake(
+                program_id, &payer, &mint, booth_id, amount,
+            )])?;
+        }
+        Command::ClaimRewards { mint } => {
+            let pool = ctx.load_rewards(&mint)?;
+            ctx.send(&[
+                ctx.create_ata(&payer, &pool.reward_mint),
+                instruction::claim_rewards(program_id, &payer, &mint, booth_id, &pool.reward_mint),
+            ])?;
+        }
+        Command::Migrate { mint } => {
+            let admin = ctx.admin(&mint);
+            ctx.send(&[ctx.authorize(
+                &mint,
+                instruction::migrate_exchange(program_id, &payer, &admin, &mint, booth_id),
+            )])?;
+        }
+        Command::SetMetadata {
+            mint,
+            name,
+            symbol,
+            description,
+            uri,
+        } => {
+            let admin = ctx.admin(&mint);
+            ctx.send(&[ctx.authorize(
+                &mint,
+                instruction::set_metadata(
+                    program_id,
+                    &payer,
+                    &admin,
+                    &mint,
+                    booth_id,
+                    &name,
+                    &symbol,
+                    &description,
+                    &uri,
+                ),
+            )])?;
+        }
+        Command::Show { mint } => {
+            let (vault, _) = find_booth_address(program_id, &mint, booth_id);
+            let exchange = ctx.load_exchange(&mint)?;
+            let vault_lamports = ctx.client.get_balance(&vault)?;
+            let vault_tokens = ctx
+                .client
+                .get_token_account_balance(&get_associated_token_address(&vault, &mint))
+                .map(|balance| balance.amount)
+                .unwrap_or_else(|_| "-".to_string());
+            println!("Vault:         {}", vault);
+            println!("Booth id:      {}", exchange.booth_id);
+            let metadata = find_metadata_address(program_id, &vault).0;
+            if let Ok(metadata) = ctx.client.get_account_data(&metadata) {
+                let metadata = BoothMetadata::unpack(&metadata)?;
+                println!("Name:          {} ({})", metadata.name, metadata.symbol);
+                println!("Description:   {}", metadata.description);
+                println!("URI:           {}", metadata.uri);
+            }
+            println!("Mint:          {}", exchange.mint);
+            println!("Admin:         {}", exchange.admin);
+            println!("Rate:          {} tokens per lamport", exchange.rate);
+            println!("Fee:           {} bps", exchange.fee_bps);
+            println!(
+                "Referral:      {} bps of the fee",
+                exchange.referral_share_bps
+            );
+            for tier in &exchange.fee_tiers[..exchange.fee_tier_count as usize] {
+                println!(
+                    "Fee tier:      {} bps from {} lamports",
+                    tier.fee_bps, tier.min_volume
+                );
+            }
+            println!("Curve:         {:?}", exchange.curve);
+            println!("Timelock:      {} slots", exchange.timelock_slots);
+            if let Some(pending) = exchange.pending_change() {
+                println!(
+                    "Pending:       rate {}, fee {} bps, timelock {} slots from slot {}",
+                    pending.rate, pending.fee_bps, pending.timelock_slots, pending.eta_slot
+                );
+            }
+            println!("Paused:        {}", exchange.paused);
+            println!("Access:        {:?}", exchange.access_mode);
+            if let Some(attestor) = exchange.attestor() {
+                println!("Attestor:      {}", attestor);
+            }
+            if exchange.wallet_limit > 0 {
+                println!(
+                    "Wallet limit:  {} lamports per {}s",
+                    exchange.wallet_limit, exchange.wallet_limit_window
+                );
+            }
+            if exchange.max_sol_outflow > 0 || exchange.max_token_outflow > 0 {
+                println!(
+                    "Breaker:       {} lamports / {} tokens per {}s",
+                    exchange.max_sol_outflow, exchange.max_token_outflow, exchange.outflow_window
+                );
+                println!(
+                    "Outflow:       {} lamports / {} tokens since {}",
+                    exchange.sol_outflow, exchange.token_outflow, exchange.outflow_window_start
+                );
+            }
+            println!("SOL reserve:   {} lamports", vault_lamports);
+            println!("Token reserve: {}", vault_tokens);
+        }
+        Command::List { page, page_size } => {
+            let (registry, _) = find_registry_address(program_id);
+            let data = ctx.client.get_account_data(&registry)?;
+            let count = BoothRegistry::unpack(&data)?.count;
+            let start = page.saturating_mul(page_size);
+            let entries = BoothRegistry::entries(&data, start, page_size)?;
+            let metadata_keys: Vec<Pubkey> = entries.iter().map(|entry| entry.metadata).collect();
+            let names = ctx
+                .client
+                .get_multiple_accounts(&metadata_keys)?
+                .into_iter()
+                .map(|account| {
+                    account
+                        .and_then(|account| BoothMetadata::unpack(&account.data).ok())
+                        .map(|metadata| metadata.name)
+                        .unwrap_or_else(|| "-".to_string())
+                });
+            for ((index, entry), name) in (start..).zip(entries).zip(names) {
+                println!(
+                    "{:>5}  {:<32}  mint {}  booth {}  vault {}  admin {}  {:?}  slot {}",
+                    index,
+                    name,
+                    entry.mint,
+                    entry.booth_id,
+                    entry.vault,
+                    entry.admin,
+                    entry.curve,
+                    entry.created_slot
+                );
+            }
+            println!("{} booths registered", count);
+        }
+    }
+    Ok(())
+}
@@ -148,6 +148,10 @@ export async function initialize() {
 
     const data = Buffer.alloc(layout.span);
     layout.encode({ instruction: 0 }, data);
+    const [registry] = await PublicKey.findProgramAddress(
+        [Buffer.from("registry")],
+        programId
+    );
     const instruction = new TransactionInstruction({
         keys: [
             {
@@ -170,6 +174,11 @@ export async function initialize() {
                 isSigner: false,
                 isWritable: true,
             },
+            {
+                pubkey: registry,
+                isSigner: false,
+                isWritable: true,
+            },
         ],
         programId,
         data: data,
@@ -0,0 +1,151 @@
+//! Helpers for routing swaps through a booth from another on-chain program.
+//!
+//! Enable with the `cpi` feature, which also turns off this crate's
+//! entrypoint. When `payer` is a PDA of the calling program, pass its seeds
+//! as `signer_seeds`. The PDA must be owned by the system program and hold
+//! enough lamports for `exchange_sol_to_token`.
+
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    program::invoke_signed,
+};
+
+use crate::instruction::TokenTracingInstruction;
+
+/// Accounts for either swap direction, in the order the processors expect.
+#[derive(Clone)]
+pub struct Exchange<'a> {
+    /// The tokentracing program.
+    pub program: AccountInfo<'a>,
+    pub payer: AccountInfo<'a>,
+    pub payer_token_account: AccountInfo<'a>,
+    pub mint: AccountInfo<'a>,
+    pub vault: AccountInfo<'a>,
+    pub vault_token_account: AccountInfo<'a>,
+    pub token_program: AccountInfo<'a>,
+    pub system_program: AccountInfo<'a>,
+    /// The payer's wallet stats PDA, needed when the booth sets a wallet limit.
+    pub wallet_stats: Option<AccountInfo<'a>>,
+    /// The payer's access entry PDA, needed unless the booth is open.
+    pub access_entry: Option<AccountInfo<'a>>,
+    /// The payer's attestation PDA, needed when the booth names an attestor.
+    pub attestation: Option<AccountInfo<'a>>,
+    /// The referrer paid the booth referral share of the fee.
+    pub referral: Option<Referral<'a>>,
+}
+
+/// Referrer accounts of a swap, see `instruction::with_referrer`.
+#[derive(Clone)]
+pub struct Referral<'a> {
+    pub referrer: AccountInfo<'a>,
+    pub referrer_token_account: AccountInfo<'a>,
+    /// The referrer's stats PDA, from `find_referrer_stats_address`.
+    pub referrer_stats: AccountInfo<'a>,
+}
+
+impl<'a> Exchange<'a> {
+    /// The swap `instruction` with these accounts, as `invoke_signed` is given it.
+    pub fn instruction(&self, instruction: TokenTracingInstruction) -> Instruction {
+        let mut accounts = vec![
+            AccountMeta::new_readonly(*self.program.key, false),
+            AccountMeta::new(*self.payer.key, true),
+            AccountMeta::new(*self.payer_token_account.key, false),
+            AccountMeta::new_readonly(*self.mint.key, false),
+            AccountMeta::new(*self.vault.key, false),
+            AccountMeta::new(*self.vault_token_account.key, false),
+            AccountMeta::new_readonly(*self.token_program.key, false),
+            AccountMeta::new_readonly(*self.system_program.key, false),
+        ];
+        // optional accounts are positional, so a skipped one before a later
+        // one is padded with the program id, which the booth treats as absent
+        let optional = [
+            self.wallet_stats
+                .as_ref()
+                .map(|info| AccountMeta::new(*info.key, false)),
+            self.access_entry
+                .as_ref()
+                .map(|info| AccountMeta::new_readonly(*info.key, false)),
+            self.attestation
+                .as_ref()
+                .map(|info| AccountMeta::new_readonly(*info.key, false)),
+        ];
+        let used = match self.referral {
+            Some(_) => optional.len(),
+            None => optional
+                .iter()
+                .rposition(Option::is_some)
+                .map_or(0, |last| last + 1),
+        };
+        let placeholder = AccountMeta::new_readonly(*self.program.key, false);
+        accounts.extend(
+            optional
+                .into_iter()
+                .take(used)
+                .map(|meta| meta.unwrap_or_else(|| placeholder.clone())),
+        );
+        if let Some(referral) = &self.referral {
+            accounts.extend([
+                AccountMeta::new(*referral.referrer.key, false),
+                AccountMeta::new(*referral.referrer_token_account.key, false),
+                AccountMeta::new(*referral.referrer_stats.key, false),
+            ]);
+        }
+        Instruction {
+            program_id: *self.program.key,
+            accounts,
+            data: instruction.pack(),
+        }
+    }
+
+    fn account_infos(&self) -> Vec<AccountInfo<'a>> {
+        let mut infos = vec![
+            self.program.clone(),
+            self.payer.clone(),
+            self.payer_token_account.clone(),
+            self.mint.clone(),
+            self.vault.clone(),
+            self.vault_token_account.clone(),
+            self.token_program.clone(),
+            self.system_program.clone(),
+        ];
+        infos.extend(self.wallet_stats.clone());
+        infos.extend(self.access_entry.clone());
+        infos.extend(self.attestation.clone());
+        if let Some(referral) = &self.referral {
+            infos.extend([
+                referral.referrer.clone(),
+                referral.referrer_token_account.clone(),
+                referral.referrer_stats.clone(),
+            ]);
+        }
+        infos
+    }
+}
+
+/// Invoke `ExchangeSOLToToken`, paying `amount` lamports from `accounts.payer`.
+pub fn exchange_sol_to_token(
+    accounts: &Exchange,
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    invoke_signed(
+        &accounts.instruction(TokenTracingInstruction::ExchangeSOLToToken { amount }),
+        &accounts.account_infos(),
+        signer_seeds,
+    )
+}
+
+/// Invoke `ExchangeTokenToSOL`, paying `amount` tokens from `accounts.payer_token_account`.
+pub fn exchange_token_to_sol(
+    accounts: &Exchange,
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    invoke_signed(
+        &accounts.instruction(TokenTracingInstruction::ExchangeTokenToSOL { amount }),
+        &accounts.account_infos(),
+        signer_seeds,
+    )
+}
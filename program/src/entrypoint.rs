@@ -5,8 +5,10 @@ use solana_program::{
     pubkey::Pubkey,
 };
 
+#[cfg(not(feature = "no-entrypoint"))]
 use solana_program::entrypoint;
 
+#[cfg(not(feature = "no-entrypoint"))]
 entrypoint!(process_instruction);
 pub fn process_instruction(
     program_id: &Pubkey,
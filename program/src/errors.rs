@@ -32,6 +32,46 @@ pub enum TokenTracingError {
     InvalidMint,
     #[error("Accounts cannot have the same mint")]
     UniqueMintAccounts,
+    #[error("Exchange booth is paused")]
+    BoothPaused,
+    #[error("Exchange rate must be greater than zero")]
+    InvalidRate,
+    #[error("Account discriminator does not match")]
+    InvalidAccountDiscriminator,
+    #[error("Unsupported account version")]
+    UnsupportedAccountVersion,
+    #[error("Math overflow")]
+    MathOverflow,
+    #[error("Wallet swap limit exceeded")]
+    LimitExceeded,
+    #[error("Wallet is not allowed to use this booth")]
+    WalletNotAllowed,
+    #[error("Wallet has no valid attestation")]
+    AttestationInvalid,
+    #[error("Not enough multisig signers")]
+    NotEnoughSigners,
+    #[error("Invalid multisig configuration")]
+    InvalidMultisig,
+    #[error("Fee must not exceed 10000 basis points")]
+    InvalidFee,
+    #[error("No change is pending")]
+    NoPendingChange,
+    #[error("Pending change is still timelocked")]
+    TimelockNotExpired,
+    #[error("Booth is timelocked, propose the change instead")]
+    TimelockActive,
+    #[error("Metadata field is too long")]
+    MetadataTooLong,
+    #[error("Flash loan was not repaid with its fee")]
+    FlashLoanNotRepaid,
+    #[error("DCA order is not due yet")]
+    DcaNotDue,
+    #[error("Exchange booth is already at the current version")]
+    BoothUpToDate,
+    #[error("Swap would exceed the booth outflow cap")]
+    OutflowCapExceeded,
+    #[error("Booth reserves belong to its LP holders")]
+    LiquidityOwedToLp,
 }
 
 impl From<TokenTracingError> for ProgramError {
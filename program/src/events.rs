@@ -0,0 +1,96 @@
+//! Events logged with `sol_log_data` for indexers following booth activity.
+//!
+//! Each event is the Borsh encoding of [`TokenTracingEvent`] in a single
+//! `Program data:` log line.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{log::sol_log_data, pubkey::Pubkey};
+
+use crate::state::{LoanSide, OrderSide, PendingChange};
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum TokenTracingEvent {
+    /// A swap brought outflow up to a circuit breaker cap and paused the
+    /// booth. Outflows are those of the current window, including the swap.
+    CircuitBreakerTripped {
+        vault: Pubkey,
+        sol_outflow: u64,
+        token_outflow: u64,
+    },
+    /// A pricing change was queued.
+    ChangeProposed { vault: Pubkey, change: PendingChange },
+    /// The queued pricing change was dropped by the admin.
+    ChangeCancelled { vault: Pubkey },
+    /// The queued pricing change took effect.
+    ChangeExecuted { vault: Pubkey, change: PendingChange },
+    /// A flash loan was repaid, leaving `fee` with the vault.
+    FlashLoan {
+        vault: Pubkey,
+        borrower: Pubkey,
+        side: LoanSide,
+        amount: u64,
+        fee: u64,
+    },
+    /// A DCA order swapped `lamports` for `tokens` delivered to its owner,
+    /// charging `fee_bps`, which fee tier `tier` set if any.
+    DcaExecuted {
+        vault: Pubkey,
+        dca: Pubkey,
+        owner: Pubkey,
+        lamports: u64,
+        tokens: u64,
+        fee_bps: u16,
+        tier: Option<u8>,
+    },
+    /// A limit order escrowed its input.
+    LimitOrderPlaced {
+        vault: Pubkey,
+        order: Pubkey,
+        owner: Pubkey,
+        side: OrderSide,
+        amount: u64,
+        min_out: u64,
+    },
+    /// A limit order was withdrawn by its owner.
+    LimitOrderCancelled { vault: Pubkey, order: Pubkey },
+    /// A limit order was filled at the booth quote, paying `payout` to its
+    /// owner and charging `fee_bps`, which fee tier `tier` set if any.
+    LimitOrderFilled {
+        vault: Pubkey,
+        order: Pubkey,
+        owner: Pubkey,
+        amount: u64,
+        payout: u64,
+        fee_bps: u16,
+        tier: Option<u8>,
+    },
+    /// A swap paid `amount_out` for `amount_in`, charging `fee_bps`, which
+    /// fee tier `tier` set if any.
+    Swap {
+        vault: Pubkey,
+        payer: Pubkey,
+        side: OrderSide,
+        amount_in: u64,
+        amount_out: u64,
+        fee_bps: u16,
+        tier: Option<u8>,
+    },
+    /// The admin added `amount` to a reward pool, now emitting `reward_rate` per second.
+    RewardsFunded {
+        vault: Pubkey,
+        amount: u64,
+        reward_rate: u64,
+    },
+    /// A staker was paid `amount` of the rewards it earned.
+    RewardsClaimed {
+        vault: Pubkey,
+        owner: Pubkey,
+        amount: u64,
+    },
+}
+
+impl TokenTracingEvent {
+    pub fn emit(&self) {
+        sol_log_data(&[&self.try_to_vec().unwrap()]);
+    }
+}
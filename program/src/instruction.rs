@@ -1,37 +1,1644 @@
-use solana_program::program_error::ProgramError;
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_program,
+};
+use spl_associated_token_account::get_associated_token_address;
 use std::convert::TryInto;
 
-use solana_program::msg;
 use crate::errors::TokenTracingError;
-#[derive(Debug)]
+use crate::state::{AccessMode, CurveType, FeeTier, LoanSide, OrderSide};
+use crate::{
+    find_access_entry_address, find_attestation_address, find_booth_address, find_dca_address,
+    find_limit_order_address, find_lp_mint_address, find_metadata_address, find_multisig_address,
+    find_referrer_stats_address, find_registry_address, find_rewards_address, find_stake_address,
+    find_wallet_stats_address,
+};
+
+/// Instructions are Borsh encoded: a variant tag byte followed by the
+/// variant fields. The tag-0/1/2 layout with a little-endian `u32` amount
+/// sent by the TypeScript client is still accepted by [`unpack`].
+///
+/// Wherever an instruction takes the booth admin, a booth administered by a
+/// multisig (see `SetMultisig`) passes the multisig account unsigned instead
+/// and lists the signing keys after the accounts below, see
+/// [`with_multisig_signers`].
+///
+/// [`unpack`]: TokenTracingInstruction::unpack
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
 pub enum TokenTracingInstruction {
+    /// Create booth 0 for a mint, see `InitializeBooth`.
+    ///
+    /// Accounts expected:
+    /// 0. `[writable, signer]` Payer, becomes the booth admin
+    /// 1. `[writable]` Vault PDA `[b"vault", mint]`
+    /// 2. `[]` System program
+    /// 3. `[]` Mint
+    /// 4. `[writable]` Booth registry PDA `[b"registry"]`, created by the
+    ///    first booth
     Initialize,
-    ExchangeSOLToToken { amount: u32 },
-    ExchangeTokenToSOL { amount: u32 }
+    /// Pay `amount` lamports into the vault and receive tokens.
+    ///
+    /// Accounts expected:
+    /// 0. `[]` This program
+    /// 1. `[writable, signer]` Payer
+    /// 2. `[writable]` Payer token account
+    /// 3. `[]` Mint
+    /// 4. `[writable]` Vault PDA
+    /// 5. `[writable]` Vault token account
+    /// 6. `[]` SPL Token program
+    /// 7. `[]` System program
+    /// 8. `[writable]` Optional wallet stats PDA `[b"wallet", vault, payer]`,
+    ///    required when the booth sets a wallet limit, and for fee tier discounts
+    /// 9. `[]` Optional access entry PDA `[b"access", vault, payer]`, required
+    ///    unless the booth is open to every wallet
+    /// 10. `[]` Optional attestation PDA `[b"attestation", attestor, payer]`,
+    ///     required when the booth names an attestor, see [`with_attestation`]
+    /// 11. `[writable]` Optional referrer, paid lamports on token to SOL swaps
+    /// 12. `[writable]` Optional referrer token account, paid tokens on SOL to
+    ///     token swaps
+    /// 13. `[writable]` Optional referrer stats PDA `[b"referrer", vault,
+    ///     referrer]`, see [`with_referrer`]
+    ///
+    /// A payout past the booth circuit breaker cap fails the swap, and one
+    /// that reaches the cap pauses the booth after it settles.
+    ExchangeSOLToToken { amount: u64 },
+    /// Pay `amount` tokens into the vault and receive lamports.
+    ///
+    /// Accounts expected: same as `ExchangeSOLToToken`.
+    ExchangeTokenToSOL { amount: u64 },
+    /// Add liquidity to the booth. Anyone may deposit, and receives LP
+    /// tokens worth the deposit in lamports at the booth rate, which the
+    /// booth reward pool stakes.
+    ///
+    /// Accounts expected:
+    /// 0. `[writable, signer]` Depositor
+    /// 1. `[writable]` Depositor token account
+    /// 2. `[]` Mint
+    /// 3. `[writable]` Vault PDA
+    /// 4. `[writable]` Vault token account
+    /// 5. `[]` SPL Token program
+    /// 6. `[]` System program
+    /// 7. `[writable]` LP mint PDA `[b"lp_mint", vault]`, created by the
+    ///    first deposit
+    /// 8. `[writable]` Depositor LP associated token account, created unless
+    ///    it already exists
+    /// 9. `[]` Associated Token Account program
+    Deposit { sol_amount: u64, token_amount: u64 },
+    /// Pay booth reserves out to the admin. The reserves belong to LP holders
+    /// while any LP tokens are outstanding, so it fails until they are all
+    /// removed. The vault keeps its rent-exempt minimum.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Admin
+    /// 1. `[writable]` Receiver token account
+    /// 2. `[]` Mint
+    /// 3. `[writable]` Vault PDA
+    /// 4. `[writable]` Vault token account
+    /// 5. `[]` SPL Token program
+    /// 6. `[writable]` Receiver of the lamports
+    /// 7. `[]` LP mint PDA `[b"lp_mint", vault]`
+    Withdraw { sol_amount: u64, token_amount: u64 },
+    /// Queue a change of the token base units paid per lamport alone, like
+    /// `ProposeChange` with the booth's current fee, curve and timelock.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Admin
+    /// 1. `[writable]` Vault PDA
+    SetRate { rate: u64 },
+    /// Pause or resume swaps.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Admin
+    /// 1. `[writable]` Vault PDA
+    SetPaused { paused: bool },
+    /// Upgrade a booth written by an older program version to the current
+    /// layout, reallocating the vault and topping up its rent from the payer.
+    ///
+    /// Accounts expected:
+    /// 0. `[writable, signer]` Payer
+    /// 1. `[signer]` Admin
+    /// 2. `[writable]` Vault PDA
+    /// 3. `[]` Mint
+    /// 4. `[]` System program
+    MigrateExchange,
+    /// Cap the lamports of swap value any single wallet may move per
+    /// `window_seconds`. A zero `limit` removes the cap.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Admin
+    /// 1. `[writable]` Vault PDA
+    SetWalletLimit { limit: u64, window_seconds: i64 },
+    /// Cap the lamports and tokens the booth pays out per `window_seconds`.
+    /// A swap that would exceed a cap fails, and one that reaches it pauses
+    /// the booth. A zero cap disables that side.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Admin
+    /// 1. `[writable]` Vault PDA
+    SetCircuitBreaker {
+        max_sol_outflow: u64,
+        max_token_outflow: u64,
+        window_seconds: i64,
+    },
+    /// Choose which wallets may swap against the booth.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Admin
+    /// 1. `[writable]` Vault PDA
+    SetAccessMode { mode: AccessMode },
+    /// List `wallet` on the booth allow- or denylist.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Payer of the entry rent
+    /// 1. `[signer]` Admin
+    /// 2. `[]` Vault PDA
+    /// 3. `[writable]` Access entry PDA `[b"access", vault, wallet]`
+    /// 4. `[]` System program
+    AddAccessEntry { wallet: Pubkey },
+    /// Remove `wallet` from the booth allow- or denylist.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Admin
+    /// 1. `[]` Vault PDA
+    /// 2. `[writable]` Access entry PDA `[b"access", vault, wallet]`
+    /// 3. `[writable]` Receiver of the entry rent
+    RemoveAccessEntry { wallet: Pubkey },
+    /// Require swaps to carry an attestation from `attestor`, or lift the
+    /// requirement with `None`.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Admin
+    /// 1. `[writable]` Vault PDA
+    SetAttestor { attestor: Option<Pubkey> },
+    /// Vouch for `wallet` until `expires_at` (unix time, zero for never).
+    /// Re-attesting updates the expiry.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Payer of the attestation rent
+    /// 1. `[signer]` Attestor
+    /// 2. `[writable]` Attestation PDA `[b"attestation", attestor, wallet]`
+    /// 3. `[]` System program
+    Attest { wallet: Pubkey, expires_at: i64 },
+    /// Withdraw an attestation for `wallet`.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Attestor
+    /// 1. `[writable]` Attestation PDA `[b"attestation", attestor, wallet]`
+    /// 2. `[writable]` Receiver of the attestation rent
+    RevokeAttestation { wallet: Pubkey },
+    /// Create or update the booth multisig and make it the booth admin.
+    /// `threshold` of `signers` must then sign for every admin instruction.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Payer of the multisig rent
+    /// 1. `[signer]` Admin
+    /// 2. `[writable]` Vault PDA
+    /// 3. `[writable]` Multisig PDA `[b"multisig", vault]`
+    /// 4. `[]` System program
+    SetMultisig { threshold: u8, signers: Vec<Pubkey> },
+    /// Hand the booth over to a new admin.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Admin
+    /// 1. `[writable]` Vault PDA
+    SetAdmin { admin: Pubkey },
+    /// Pay out all liquidity and close the booth, once no LP tokens are
+    /// outstanding.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Admin
+    /// 1. `[writable]` Receiver token account
+    /// 2. `[]` Mint
+    /// 3. `[writable]` Vault PDA
+    /// 4. `[writable]` Vault token account, closed as well
+    /// 5. `[]` SPL Token program
+    /// 6. `[writable]` Receiver of the lamports
+    /// 7. `[writable]` Registry PDA `[b"registry"]`, which drops the booth
+    /// 8. `[writable]` Metadata PDA `[b"metadata", vault]`, closed if it exists
+    /// 9. `[]` LP mint PDA `[b"lp_mint", vault]`
+    CloseExchange,
+    /// Queue new pricing, replacing any queued change. It can be executed
+    /// once the booth's current `timelock_slots`, and at least
+    /// [`MIN_TIMELOCK_SLOTS`](crate::state::MIN_TIMELOCK_SLOTS), have passed.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Admin
+    /// 1. `[writable]` Vault PDA
+    ProposeChange {
+        rate: u64,
+        fee_bps: u16,
+        curve: CurveType,
+        timelock_slots: u64,
+    },
+    /// Drop the queued change.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Admin
+    /// 1. `[writable]` Vault PDA
+    CancelChange,
+    /// Apply the queued change once its timelock has passed. Anyone may call.
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` Vault PDA
+    ExecuteChange,
+    /// Create booth `booth_id` for a mint, so one mint can have several
+    /// booths with their own rates and liquidity. Booth 0 is the one
+    /// `Initialize` creates.
+    ///
+    /// Accounts expected:
+    /// 0. `[writable, signer]` Payer, becomes the booth admin
+    /// 1. `[writable]` Vault PDA `[b"vault", mint, booth_id]`, see [`find_booth_address`]
+    /// 2. `[]` System program
+    /// 3. `[]` Mint
+    /// 4. `[writable]` Booth registry PDA `[b"registry"]`
+    InitializeBooth { booth_id: u64 },
+    /// Create or overwrite the booth metadata. Each field is limited in
+    /// length, see [`crate::state::MAX_NAME_LEN`] and its neighbours.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Payer of the metadata rent
+    /// 1. `[signer]` Admin
+    /// 2. `[]` Vault PDA
+    /// 3. `[writable]` Metadata PDA `[b"metadata", vault]`
+    /// 4. `[]` System program
+    SetMetadata {
+        name: String,
+        symbol: String,
+        description: String,
+        uri: String,
+    },
+    /// Lend `amount` of the vault's `side` reserve to `destination`, invoke
+    /// the receiver program with a [`FlashLoanCallback`], and fail unless the
+    /// vault got back the amount plus
+    /// [`FLASH_LOAN_FEE_BPS`](crate::state::FLASH_LOAN_FEE_BPS) by the time
+    /// it returns.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Borrower
+    /// 1. `[]` Mint
+    /// 2. `[writable]` Vault PDA
+    /// 3. `[writable]` Vault token account
+    /// 4. `[writable]` Destination: any account for SOL, a token account of
+    ///    the mint for tokens
+    /// 5. `[]` SPL Token program
+    /// 6. `[]` Receiver program
+    /// 7. .. Accounts passed on to the receiver program, with the privileges
+    ///    given here
+    FlashLoan { amount: u64, side: LoanSide },
+    /// Escrow `amount` lamports or tokens, per `side`, in a limit order that
+    /// `FillLimitOrders` executes once the booth quote pays out at least
+    /// `min_out`. The owner also escrows
+    /// [`LIMIT_ORDER_CRANK_REWARD`](crate::state::LIMIT_ORDER_CRANK_REWARD)
+    /// lamports for whoever fills it. The fill counts toward the owner's
+    /// wallet limit, so the owner pays for its wallet stats PDA here.
+    ///
+    /// Accounts expected:
+    /// 0. `[writable, signer]` Owner
+    /// 1. `[writable]` Owner token account
+    /// 2. `[]` Mint
+    /// 3. `[]` Vault PDA
+    /// 4. `[writable]` Limit order PDA `[b"order", vault, owner, order_id]`
+    /// 5. `[writable]` Limit order associated token account, created for
+    ///    `TokenToSol` orders unless it already exists
+    /// 6. `[]` SPL Token program
+    /// 7. `[]` System program
+    /// 8. `[]` Associated Token Account program
+    /// 9. `[writable]` Optional wallet stats PDA, as for swaps
+    /// 10. `[]` Optional access entry PDA, as for swaps
+    /// 11. `[]` Optional attestation PDA, as for swaps, see [`with_attestation`]
+    PlaceLimitOrder {
+        order_id: u64,
+        side: OrderSide,
+        amount: u64,
+        min_out: u64,
+    },
+    /// Close a limit order, refunding everything it escrows to its owner.
+    ///
+    /// Accounts expected:
+    /// 0. `[writable, signer]` Owner
+    /// 1. `[writable]` Token account receiving the tokens of a `TokenToSol` order
+    /// 2. `[writable]` Limit order PDA
+    /// 3. `[writable]` Limit order associated token account
+    /// 4. `[]` SPL Token program
+    CancelLimitOrder,
+    /// Fill every listed limit order the booth quote satisfies at its current
+    /// rate, paying each order's crank reward to the signer. Orders the booth
+    /// cannot fill yet, that would exceed its circuit breaker cap or its
+    /// owner's wallet limit, or whose owner fails the booth access list or
+    /// attestation, are skipped, and orders after one that pauses the booth
+    /// are left. Anyone may call this.
+    ///
+    /// Accounts expected:
+    /// 0. `[writable, signer]` Cranker, paying for owner wallet stats PDAs
+    ///    the booth needs and that do not exist yet
+    /// 1. `[]` Mint
+    /// 2. `[writable]` Vault PDA
+    /// 3. `[writable]` Vault token account
+    /// 4. `[]` SPL Token program
+    /// 5. `[]` System program
+    ///
+    /// followed by seven accounts per order:
+    /// 0. `[writable]` Limit order PDA
+    /// 1. `[writable]` Limit order associated token account
+    /// 2. `[writable]` Order owner
+    /// 3. `[writable]` Owner token account
+    /// 4. `[writable]` Owner wallet stats PDA `[b"wallet", vault, owner]`
+    /// 5. `[]` Owner access entry PDA `[b"access", vault, owner]`
+    /// 6. `[]` Owner attestation PDA from the booth attestor, any account
+    ///    if the booth names none
+    FillLimitOrders,
+    /// Escrow `deposit` lamports in a DCA order that `ExecuteDca` swaps for
+    /// tokens `amount_per_period` at a time, at most once per `interval`
+    /// seconds. The first period is due right away. Each period counts toward
+    /// the owner's wallet limit as it executes, so the owner pays for its
+    /// wallet stats PDA here.
+    ///
+    /// Accounts expected:
+    /// 0. `[writable, signer]` Owner
+    /// 1. `[]` Vault PDA
+    /// 2. `[writable]` DCA PDA `[b"dca", vault, owner, dca_id]`
+    /// 3. `[]` System program
+    /// 4. `[writable]` Optional wallet stats PDA, as for swaps
+    /// 5. `[]` Optional access entry PDA, as for swaps
+    /// 6. `[]` Optional attestation PDA, as for swaps, see [`with_attestation`]
+    CreateDca {
+        dca_id: u64,
+        amount_per_period: u64,
+        interval: i64,
+        deposit: u64,
+    },
+    /// Close a DCA order, refunding the lamports it still escrows to its owner.
+    ///
+    /// Accounts expected:
+    /// 0. `[writable, signer]` Owner
+    /// 1. `[writable]` DCA PDA
+    CloseDca,
+    /// Swap one period of a due DCA order at the booth quote, delivering the
+    /// tokens to the owner's associated token account. The order closes
+    /// once its escrow runs out. The owner must still pass the booth access
+    /// list and attestation, and the period counts toward its wallet limit.
+    /// Anyone may call this.
+    ///
+    /// Accounts expected:
+    /// 0. `[]` Mint
+    /// 1. `[writable]` Vault PDA
+    /// 2. `[writable]` Vault token account
+    /// 3. `[writable]` DCA PDA
+    /// 4. `[writable]` Order owner
+    /// 5. `[writable]` Owner associated token account
+    /// 6. `[]` SPL Token program
+    /// 7. `[writable, signer]` Cranker, paying for the owner's wallet stats
+    ///    PDA if the booth needs it and it does not exist yet
+    /// 8. `[]` System program
+    /// 9. `[writable]` Owner wallet stats PDA `[b"wallet", vault, owner]`
+    /// 10. `[]` Owner access entry PDA `[b"access", vault, owner]`
+    /// 11. `[]` Owner attestation PDA from the booth attestor, any account
+    ///     if the booth names none
+    ExecuteDca,
+    /// Pay lamports into the vault once and deliver each recipient the tokens
+    /// its amount buys, all or none of them.
+    ///
+    /// Accounts expected: same as `ExchangeSOLToToken`, with the payer token
+    /// account left unpaid and no referrer accounts, followed by one
+    /// `[writable]` token account per entry of `amounts`, in order.
+    ///
+    /// Payouts past the booth circuit breaker cap fail the whole batch, and
+    /// ones that reach the cap pause the booth after they settle.
+    BatchExchangeSOLToToken { amounts: Vec<u64> },
+    /// Pay swap referrers `share_bps` of the booth fee. Zero stops paying them.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Admin
+    /// 1. `[writable]` Vault PDA
+    SetReferralShare { share_bps: u16 },
+    /// Replace the booth fee tiers, which discount swaps by wallets whose
+    /// total volume reaches a tier. Tiers must raise `min_volume` and never
+    /// the fee, and a tier above the booth fee charges the booth fee.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Admin
+    /// 1. `[writable]` Vault PDA
+    SetFeeTiers { tiers: Vec<FeeTier> },
+    /// Create the booth's liquidity mining reward pool, paying stakers of
+    /// the booth LP mint in `reward_mint` once funded by `FundRewards`. The
+    /// LP mint exists from the first `Deposit`.
+    ///
+    /// Accounts expected:
+    /// 0. `[writable, signer]` Payer
+    /// 1. `[signer]` Admin
+    /// 2. `[]` Vault PDA
+    /// 3. `[writable]` Reward pool PDA `[b"rewards", vault]`
+    /// 4. `[]` LP mint PDA `[b"lp_mint", vault]`, the stake mint
+    /// 5. `[]` Reward mint
+    /// 6. `[writable]` Reward pool associated token account for the stake
+    ///    mint, created unless it already exists
+    /// 7. `[writable]` Reward pool associated token account for the reward
+    ///    mint, created unless it already exists
+    /// 8. `[]` SPL Token program
+    /// 9. `[]` System program
+    /// 10. `[]` Associated Token Account program
+    InitializeRewards,
+    /// Add `amount` reward tokens to the pool and emit `reward_rate` of them
+    /// per second from now on, split across stakers by stake.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Funder
+    /// 1. `[writable]` Funder reward token account
+    /// 2. `[signer]` Admin
+    /// 3. `[]` Vault PDA
+    /// 4. `[writable]` Reward pool PDA
+    /// 5. `[writable]` Reward pool reward token account
+    /// 6. `[]` SPL Token program
+    FundRewards { amount: u64, reward_rate: u64 },
+    /// Stake `amount` stake mint tokens, creating the stake PDA on first use.
+    ///
+    /// Accounts expected:
+    /// 0. `[writable, signer]` Owner
+    /// 1. `[writable]` Owner stake token account
+    /// 2. `[writable]` Reward pool PDA
+    /// 3. `[writable]` Stake PDA `[b"stake", rewards, owner]`
+    /// 4. `[writable]` Reward pool stake token account
+    /// 5. `[]` SPL Token program
+    /// 6. `[]` System program
+    Stake { amount: u64 },
+    /// Withdraw `amount` staked tokens. Rewards earned stay claimable.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Owner
+    /// 1. `[writable]` Owner stake token account
+    /// 2. `[writable]` Reward pool PDA
+    /// 3. `[writable]` Stake PDA
+    /// 4. `[writable]` Reward pool stake token account
+    /// 5. `[]` SPL Token program
+    Unstake { amount: u64 },
+    /// Pay the owner every reward its stake has earned.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Owner
+    /// 1. `[writable]` Owner reward token account
+    /// 2. `[writable]` Reward pool PDA
+    /// 3. `[writable]` Stake PDA
+    /// 4. `[writable]` Reward pool reward token account
+    /// 5. `[]` SPL Token program
+    ClaimRewards,
+    /// Burn `lp_amount` LP tokens for the same share of the booth's lamports
+    /// and tokens. Open to any LP holder, even while the booth is paused.
+    ///
+    /// Accounts expected:
+    /// 0. `[writable, signer]` Owner, paid the lamports
+    /// 1. `[writable]` Owner LP token account
+    /// 2. `[writable]` Owner token account
+    /// 3. `[]` Mint
+    /// 4. `[writable]` Vault PDA
+    /// 5. `[writable]` Vault token account
+    /// 6. `[writable]` LP mint PDA `[b"lp_mint", vault]`
+    /// 7. `[]` SPL Token program
+    RemoveLiquidity { lp_amount: u64 },
+}
+
+/// Instruction data a `FlashLoan` sends its receiver program, Borsh encoded.
+///
+/// The receiver must pay `amount + fee` back before returning: lamports to
+/// the vault PDA, tokens to the vault token account.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct FlashLoanCallback {
+    pub side: LoanSide,
+    pub amount: u64,
+    pub fee: u64,
 }
 
 impl TokenTracingInstruction {
     pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
-        msg!("+++++++++++++++++++++++++++++++++++++");
-        
-        let (&tag, rest) = input.split_first().ok_or(TokenTracingError::InvalidInstruction)?;
-        msg!("--------------------------------------------");
-        return match tag {
+        match Self::try_from_slice(input) {
+            Ok(instruction) => Ok(instruction),
+            Err(_) => Self::unpack_legacy(input),
+        }
+    }
+
+    pub fn pack(&self) -> Vec<u8> {
+        self.try_to_vec().unwrap()
+    }
+
+    /// Parse the original hand-rolled layout: tag byte, then a `u32` amount.
+    fn unpack_legacy(input: &[u8]) -> Result<Self, ProgramError> {
+        let (&tag, rest) = input
+            .split_first()
+            .ok_or(TokenTracingError::InvalidInstruction)?;
+        match tag {
             0 => Ok(Self::Initialize),
-            1 => Ok(Self::ExchangeSOLToToken { amount: Self::get_amount(rest)?}),
-            2 => Ok(Self::ExchangeTokenToSOL { amount: Self::get_amount(rest)?}),
-            _ => Err(TokenTracingError::InvalidInstructionData.into())
-        };
+            1 => Ok(Self::ExchangeSOLToToken {
+                amount: Self::get_legacy_amount(rest)?,
+            }),
+            2 => Ok(Self::ExchangeTokenToSOL {
+                amount: Self::get_legacy_amount(rest)?,
+            }),
+            _ => Err(TokenTracingError::InvalidInstructionData.into()),
+        }
+    }
+
+    fn get_legacy_amount(rest: &[u8]) -> Result<u64, ProgramError> {
+        rest.get(..4)
+            .and_then(|raw| raw.try_into().ok())
+            .map(|raw| u32::from_le_bytes(raw) as u64)
+            .ok_or_else(|| TokenTracingError::InvalidInstructionData.into())
+    }
+}
+
+/// Creates an `Initialize` instruction for booth 0, or `InitializeBooth` for any other.
+pub fn initialize(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    mint: &Pubkey,
+    booth_id: u64,
+) -> Instruction {
+    let (vault, _) = find_booth_address(program_id, mint, booth_id);
+    let (registry, _) = find_registry_address(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(vault, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(registry, false),
+        ],
+        data: match booth_id {
+            0 => TokenTracingInstruction::Initialize,
+            booth_id => TokenTracingInstruction::InitializeBooth { booth_id },
+        }
+        .pack(),
+    }
+}
+
+/// Creates an `ExchangeSOLToToken` instruction.
+pub fn exchange_sol_to_token(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    mint: &Pubkey,
+    booth_id: u64,
+    amount: u64,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: swap_accounts(program_id, payer, mint, booth_id),
+        data: TokenTracingInstruction::ExchangeSOLToToken { amount }.pack(),
+    }
+}
+
+/// Creates an `ExchangeTokenToSOL` instruction.
+pub fn exchange_token_to_sol(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    mint: &Pubkey,
+    booth_id: u64,
+    amount: u64,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: swap_accounts(program_id, payer, mint, booth_id),
+        data: TokenTracingInstruction::ExchangeTokenToSOL { amount }.pack(),
+    }
+}
+
+/// The account layout shared by both swap directions.
+fn swap_accounts(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    mint: &Pubkey,
+    booth_id: u64,
+) -> Vec<AccountMeta> {
+    let (vault, _) = find_booth_address(program_id, mint, booth_id);
+    let (wallet_stats, _) = find_wallet_stats_address(program_id, &vault, payer);
+    let (access_entry, _) = find_access_entry_address(program_id, &vault, payer);
+    vec![
+        AccountMeta::new_readonly(*program_id, false),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(get_associated_token_address(payer, mint), false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new(vault, false),
+        AccountMeta::new(get_associated_token_address(&vault, mint), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new(wallet_stats, false),
+        AccountMeta::new_readonly(access_entry, false),
+    ]
+}
+
+/// Creates a `Deposit` instruction funded from `depositor` and its associated token account.
+pub fn deposit(
+    program_id: &Pubkey,
+    depositor: &Pubkey,
+    mint: &Pubkey,
+    booth_id: u64,
+    sol_amount: u64,
+    token_amount: u64,
+) -> Instruction {
+    let (vault, _) = find_booth_address(program_id, mint, booth_id);
+    let (lp_mint, _) = find_lp_mint_address(program_id, &vault);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*depositor, true),
+            AccountMeta::new(get_associated_token_address(depositor, mint), false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new(get_associated_token_address(&vault, mint), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(lp_mint, false),
+            AccountMeta::new(get_associated_token_address(depositor, &lp_mint), false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        ],
+        data: TokenTracingInstruction::Deposit {
+            sol_amount,
+            token_amount,
+        }
+        .pack(),
+    }
+}
+
+/// Creates a `Withdraw` instruction paying out to `receiver` and its associated token account.
+pub fn withdraw(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    receiver: &Pubkey,
+    mint: &Pubkey,
+    booth_id: u64,
+    sol_amount: u64,
+    token_amount: u64,
+) -> Instruction {
+    let (vault, _) = find_booth_address(program_id, mint, booth_id);
+    let (lp_mint, _) = find_lp_mint_address(program_id, &vault);
+    let mut accounts = payout_accounts(program_id, admin, receiver, mint, booth_id);
+    accounts.push(AccountMeta::new_readonly(lp_mint, false));
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: TokenTracingInstruction::Withdraw {
+            sol_amount,
+            token_amount,
+        }
+        .pack(),
+    }
+}
+
+/// Creates a `SetRate` instruction.
+pub fn set_rate(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    mint: &Pubkey,
+    booth_id: u64,
+    rate: u64,
+) -> Instruction {
+    admin_instruction(
+        program_id,
+        admin,
+        mint,
+        booth_id,
+        TokenTracingInstruction::SetRate { rate },
+    )
+}
+
+/// Creates a `SetPaused` instruction.
+pub fn set_paused(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    mint: &Pubkey,
+    booth_id: u64,
+    paused: bool,
+) -> Instruction {
+    admin_instruction(
+        program_id,
+        admin,
+        mint,
+        booth_id,
+        TokenTracingInstruction::SetPaused { paused },
+    )
+}
+
+/// Creates a `MigrateExchange` instruction.
+pub fn migrate_exchange(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    admin: &Pubkey,
+    mint: &Pubkey,
+    booth_id: u64,
+) -> Instruction {
+    let (vault, _) = find_booth_address(program_id, mint, booth_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new(vault, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: TokenTracingInstruction::MigrateExchange.pack(),
+    }
+}
+
+/// The account layout shared by `Withdraw` and `CloseExchange`.
+fn payout_accounts(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    receiver: &Pubkey,
+    mint: &Pubkey,
+    booth_id: u64,
+) -> Vec<AccountMeta> {
+    let (vault, _) = find_booth_address(program_id, mint, booth_id);
+    vec![
+        AccountMeta::new_readonly(*admin, true),
+        AccountMeta::new(get_associated_token_address(receiver, mint), false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new(vault, false),
+        AccountMeta::new(get_associated_token_address(&vault, mint), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new(*receiver, false),
+    ]
+}
+
+/// Creates a `SetWalletLimit` instruction.
+pub fn set_wallet_limit(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    mint: &Pubkey,
+    booth_id: u64,
+    limit: u64,
+    window_seconds: i64,
+) -> Instruction {
+    admin_instruction(
+        program_id,
+        admin,
+        mint,
+        booth_id,
+        TokenTracingInstruction::SetWalletLimit {
+            limit,
+            window_seconds,
+        },
+    )
+}
+
+/// Creates a `SetCircuitBreaker` instruction.
+pub fn set_circuit_breaker(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    mint: &Pubkey,
+    booth_id: u64,
+    max_sol_outflow: u64,
+    max_token_outflow: u64,
+    window_seconds: i64,
+) -> Instruction {
+    admin_instruction(
+        program_id,
+        admin,
+        mint,
+        booth_id,
+        TokenTracingInstruction::SetCircuitBreaker {
+            max_sol_outflow,
+            max_token_outflow,
+            window_seconds,
+        },
+    )
+}
+
+/// Creates a `SetAccessMode` instruction.
+pub fn set_access_mode(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    mint: &Pubkey,
+    booth_id: u64,
+    mode: AccessMode,
+) -> Instruction {
+    admin_instruction(
+        program_id,
+        admin,
+        mint,
+        booth_id,
+        TokenTracingInstruction::SetAccessMode { mode },
+    )
+}
+
+/// Creates an `AddAccessEntry` instruction.
+pub fn add_access_entry(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    admin: &Pubkey,
+    mint: &Pubkey,
+    booth_id: u64,
+    wallet: &Pubkey,
+) -> Instruction {
+    let (vault, _) = find_booth_address(program_id, mint, booth_id);
+    let (access_entry, _) = find_access_entry_address(program_id, &vault, wallet);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new_readonly(vault, false),
+            AccountMeta::new(access_entry, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: TokenTracingInstruction::AddAccessEntry { wallet: *wallet }.pack(),
     }
+}
 
-    fn get_amount(rest: &[u8]) -> Result<u32, ProgramError> {
-        let raw_data: Result<[u8; 4], _> = rest[..4].try_into();
-        match raw_data {
-            Ok(i) => Ok(u32::from_le_bytes(i)),
-            
-            _ => Err(TokenTracingError::InvalidInstructionData.into())
+/// Creates a `SetMetadata` instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn set_metadata(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    admin: &Pubkey,
+    mint: &Pubkey,
+    booth_id: u64,
+    name: &str,
+    symbol: &str,
+    description: &str,
+    uri: &str,
+) -> Instruction {
+    let (vault, _) = find_booth_address(program_id, mint, booth_id);
+    let (metadata, _) = find_metadata_address(program_id, &vault);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new_readonly(vault, false),
+            AccountMeta::new(metadata, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: TokenTracingInstruction::SetMetadata {
+            name: name.to_string(),
+            symbol: symbol.to_string(),
+            description: description.to_string(),
+            uri: uri.to_string(),
         }
+        .pack(),
+    }
+}
+
+/// Creates a `FlashLoan` instruction. `receiver_accounts` are passed on to
+/// `receiver_program`.
+#[allow(clippy::too_many_arguments)]
+pub fn flash_loan(
+    program_id: &Pubkey,
+    borrower: &Pubkey,
+    mint: &Pubkey,
+    booth_id: u64,
+    destination: &Pubkey,
+    receiver_program: &Pubkey,
+    receiver_accounts: Vec<AccountMeta>,
+    amount: u64,
+    side: LoanSide,
+) -> Instruction {
+    let (vault, _) = find_booth_address(program_id, mint, booth_id);
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*borrower, true),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new(vault, false),
+        AccountMeta::new(get_associated_token_address(&vault, mint), false),
+        AccountMeta::new(*destination, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(*receiver_program, false),
+    ];
+    accounts.extend(receiver_accounts);
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: TokenTracingInstruction::FlashLoan { amount, side }.pack(),
+    }
+}
+
+/// Creates a `PlaceLimitOrder` instruction selling from `owner` and its
+/// associated token account.
+#[allow(clippy::too_many_arguments)]
+pub fn place_limit_order(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    booth_id: u64,
+    order_id: u64,
+    side: OrderSide,
+    amount: u64,
+    min_out: u64,
+) -> Instruction {
+    let (vault, _) = find_booth_address(program_id, mint, booth_id);
+    let (order, _) = find_limit_order_address(program_id, &vault, owner, order_id);
+    let (wallet_stats, _) = find_wallet_stats_address(program_id, &vault, owner);
+    let (access_entry, _) = find_access_entry_address(program_id, &vault, owner);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*owner, true),
+            AccountMeta::new(get_associated_token_address(owner, mint), false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(vault, false),
+            AccountMeta::new(order, false),
+            AccountMeta::new(get_associated_token_address(&order, mint), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+            AccountMeta::new(wallet_stats, false),
+            AccountMeta::new_readonly(access_entry, false),
+        ],
+        data: TokenTracingInstruction::PlaceLimitOrder {
+            order_id,
+            side,
+            amount,
+            min_out,
+        }
+        .pack(),
+    }
+}
+
+/// Creates a `CancelLimitOrder` instruction refunding to `owner` and its
+/// associated token account.
+pub fn cancel_limit_order(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    booth_id: u64,
+    order_id: u64,
+) -> Instruction {
+    let (vault, _) = find_booth_address(program_id, mint, booth_id);
+    let (order, _) = find_limit_order_address(program_id, &vault, owner, order_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*owner, true),
+            AccountMeta::new(get_associated_token_address(owner, mint), false),
+            AccountMeta::new(order, false),
+            AccountMeta::new(get_associated_token_address(&order, mint), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: TokenTracingInstruction::CancelLimitOrder.pack(),
+    }
+}
+
+/// Creates a `FillLimitOrders` instruction for the `(owner, order_id)` orders,
+/// paying out to each owner's associated token account. Pass the booth
+/// attestor, if it names one.
+pub fn fill_limit_orders(
+    program_id: &Pubkey,
+    cranker: &Pubkey,
+    mint: &Pubkey,
+    booth_id: u64,
+    orders: &[(Pubkey, u64)],
+    attestor: Option<&Pubkey>,
+) -> Instruction {
+    let (vault, _) = find_booth_address(program_id, mint, booth_id);
+    let mut accounts = vec![
+        AccountMeta::new(*cranker, true),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new(vault, false),
+        AccountMeta::new(get_associated_token_address(&vault, mint), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    for (owner, order_id) in orders {
+        let (order, _) = find_limit_order_address(program_id, &vault, owner, *order_id);
+        let (wallet_stats, _) = find_wallet_stats_address(program_id, &vault, owner);
+        let (access_entry, _) = find_access_entry_address(program_id, &vault, owner);
+        let attestation = attestor.map_or(*program_id, |attestor| {
+            find_attestation_address(program_id, attestor, owner).0
+        });
+        accounts.extend([
+            AccountMeta::new(order, false),
+            AccountMeta::new(get_associated_token_address(&order, mint), false),
+            AccountMeta::new(*owner, false),
+            AccountMeta::new(get_associated_token_address(owner, mint), false),
+            AccountMeta::new(wallet_stats, false),
+            AccountMeta::new_readonly(access_entry, false),
+            AccountMeta::new_readonly(attestation, false),
+        ]);
+    }
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: TokenTracingInstruction::FillLimitOrders.pack(),
+    }
+}
+
+/// Creates a `CreateDca` instruction funded by `owner`.
+#[allow(clippy::too_many_arguments)]
+pub fn create_dca(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    booth_id: u64,
+    dca_id: u64,
+    amount_per_period: u64,
+    interval: i64,
+    deposit: u64,
+) -> Instruction {
+    let (vault, _) = find_booth_address(program_id, mint, booth_id);
+    let (dca, _) = find_dca_address(program_id, &vault, owner, dca_id);
+    let (wallet_stats, _) = find_wallet_stats_address(program_id, &vault, owner);
+    let (access_entry, _) = find_access_entry_address(program_id, &vault, owner);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*owner, true),
+            AccountMeta::new_readonly(vault, false),
+            AccountMeta::new(dca, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(wallet_stats, false),
+            AccountMeta::new_readonly(access_entry, false),
+        ],
+        data: TokenTracingInstruction::CreateDca {
+            dca_id,
+            amount_per_period,
+            interval,
+            deposit,
+        }
+        .pack(),
+    }
+}
 
+/// Creates a `CloseDca` instruction.
+pub fn close_dca(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    booth_id: u64,
+    dca_id: u64,
+) -> Instruction {
+    let (vault, _) = find_booth_address(program_id, mint, booth_id);
+    let (dca, _) = find_dca_address(program_id, &vault, owner, dca_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![AccountMeta::new(*owner, true), AccountMeta::new(dca, false)],
+        data: TokenTracingInstruction::CloseDca.pack(),
     }
 }
 
+/// Creates an `ExecuteDca` instruction, sent by `cranker`, for `owner`'s DCA
+/// order `dca_id`. Pass the booth attestor, if it names one.
+pub fn execute_dca(
+    program_id: &Pubkey,
+    cranker: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    booth_id: u64,
+    dca_id: u64,
+    attestor: Option<&Pubkey>,
+) -> Instruction {
+    let (vault, _) = find_booth_address(program_id, mint, booth_id);
+    let (dca, _) = find_dca_address(program_id, &vault, owner, dca_id);
+    let (wallet_stats, _) = find_wallet_stats_address(program_id, &vault, owner);
+    let (access_entry, _) = find_access_entry_address(program_id, &vault, owner);
+    let attestation = attestor.map_or(*program_id, |attestor| {
+        find_attestation_address(program_id, attestor, owner).0
+    });
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new(get_associated_token_address(&vault, mint), false),
+            AccountMeta::new(dca, false),
+            AccountMeta::new(*owner, false),
+            AccountMeta::new(get_associated_token_address(owner, mint), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new(*cranker, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(wallet_stats, false),
+            AccountMeta::new_readonly(access_entry, false),
+            AccountMeta::new_readonly(attestation, false),
+        ],
+        data: TokenTracingInstruction::ExecuteDca.pack(),
+    }
+}
+
+/// Creates a `BatchExchangeSOLToToken` instruction paying each
+/// `(recipient token account, lamports)` pair. Pass the booth attestor, if
+/// it names one, rather than using [`with_attestation`], since the payer's
+/// attestation must come before the recipients.
+pub fn batch_exchange_sol_to_token(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    mint: &Pubkey,
+    booth_id: u64,
+    recipients: &[(Pubkey, u64)],
+    attestor: Option<&Pubkey>,
+) -> Instruction {
+    let mut accounts = swap_accounts(program_id, payer, mint, booth_id);
+    if let Some(attestor) = attestor {
+        let (attestation, _) = find_attestation_address(program_id, attestor, payer);
+        accounts.push(AccountMeta::new_readonly(attestation, false));
+    }
+    accounts.extend(
+        recipients
+            .iter()
+            .map(|(recipient, _)| AccountMeta::new(*recipient, false)),
+    );
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: TokenTracingInstruction::BatchExchangeSOLToToken {
+            amounts: recipients.iter().map(|(_, amount)| *amount).collect(),
+        }
+        .pack(),
+    }
+}
+
+/// Creates a `RemoveAccessEntry` instruction, refunding the entry rent to `receiver`.
+pub fn remove_access_entry(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    mint: &Pubkey,
+    booth_id: u64,
+    wallet: &Pubkey,
+    receiver: &Pubkey,
+) -> Instruction {
+    let (vault, _) = find_booth_address(program_id, mint, booth_id);
+    let (access_entry, _) = find_access_entry_address(program_id, &vault, wallet);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new_readonly(vault, false),
+            AccountMeta::new(access_entry, false),
+            AccountMeta::new(*receiver, false),
+        ],
+        data: TokenTracingInstruction::RemoveAccessEntry { wallet: *wallet }.pack(),
+    }
+}
+
+/// Append the swap payer's attestation from `attestor`, for booths that
+/// require one. `swap` must come from [`exchange_sol_to_token`],
+/// [`exchange_token_to_sol`] or [`place_limit_order`].
+pub fn with_attestation(
+    program_id: &Pubkey,
+    mut swap: Instruction,
+    attestor: &Pubkey,
+) -> Instruction {
+    let payer = swap
+        .accounts
+        .iter()
+        .find(|account| account.is_signer)
+        .expect("swap payer")
+        .pubkey;
+    let (attestation, _) = find_attestation_address(program_id, attestor, &payer);
+    swap.accounts
+        .push(AccountMeta::new_readonly(attestation, false));
+    swap
+}
+
+/// Append `referrer` to a swap from [`exchange_sol_to_token`] or
+/// [`exchange_token_to_sol`], so it earns the booth referral share. Call
+/// after [`with_attestation`], if the booth needs one.
+pub fn with_referrer(program_id: &Pubkey, mut swap: Instruction, referrer: &Pubkey) -> Instruction {
+    let mint = swap.accounts[3].pubkey;
+    let vault = swap.accounts[4].pubkey;
+    // the referrer accounts follow the optional attestation, which the booth ignores without an attestor
+    if swap.accounts.len() < 11 {
+        swap.accounts
+            .push(AccountMeta::new_readonly(*program_id, false));
+    }
+    let (referrer_stats, _) = find_referrer_stats_address(program_id, &vault, referrer);
+    swap.accounts.extend([
+        AccountMeta::new(*referrer, false),
+        AccountMeta::new(get_associated_token_address(referrer, &mint), false),
+        AccountMeta::new(referrer_stats, false),
+    ]);
+    swap
+}
+
+/// Creates a `SetReferralShare` instruction.
+pub fn set_referral_share(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    mint: &Pubkey,
+    booth_id: u64,
+    share_bps: u16,
+) -> Instruction {
+    admin_instruction(
+        program_id,
+        admin,
+        mint,
+        booth_id,
+        TokenTracingInstruction::SetReferralShare { share_bps },
+    )
+}
+
+/// Creates a `SetFeeTiers` instruction.
+pub fn set_fee_tiers(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    mint: &Pubkey,
+    booth_id: u64,
+    tiers: &[FeeTier],
+) -> Instruction {
+    admin_instruction(
+        program_id,
+        admin,
+        mint,
+        booth_id,
+        TokenTracingInstruction::SetFeeTiers {
+            tiers: tiers.to_vec(),
+        },
+    )
+}
+
+/// Creates an `InitializeRewards` instruction.
+pub fn initialize_rewards(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    admin: &Pubkey,
+    mint: &Pubkey,
+    booth_id: u64,
+    reward_mint: &Pubkey,
+) -> Instruction {
+    let (vault, _) = find_booth_address(program_id, mint, booth_id);
+    let (rewards, _) = find_rewards_address(program_id, &vault);
+    let (lp_mint, _) = find_lp_mint_address(program_id, &vault);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new_readonly(vault, false),
+            AccountMeta::new(rewards, false),
+            AccountMeta::new_readonly(lp_mint, false),
+            AccountMeta::new_readonly(*reward_mint, false),
+            AccountMeta::new(get_associated_token_address(&rewards, &lp_mint), false),
+            AccountMeta::new(get_associated_token_address(&rewards, reward_mint), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        ],
+        data: TokenTracingInstruction::InitializeRewards.pack(),
+    }
+}
+
+/// Creates a `FundRewards` instruction paying from `funder`'s associated
+/// token account.
+#[allow(clippy::too_many_arguments)]
+pub fn fund_rewards(
+    program_id: &Pubkey,
+    funder: &Pubkey,
+    admin: &Pubkey,
+    mint: &Pubkey,
+    booth_id: u64,
+    reward_mint: &Pubkey,
+    amount: u64,
+    reward_rate: u64,
+) -> Instruction {
+    let (vault, _) = find_booth_address(program_id, mint, booth_id);
+    let (rewards, _) = find_rewards_address(program_id, &vault);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*funder, true),
+            AccountMeta::new(get_associated_token_address(funder, reward_mint), false),
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new_readonly(vault, false),
+            AccountMeta::new(rewards, false),
+            AccountMeta::new(get_associated_token_address(&rewards, reward_mint), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: TokenTracingInstruction::FundRewards {
+            amount,
+            reward_rate,
+        }
+        .pack(),
+    }
+}
+
+/// Creates a `Stake` instruction from `owner`'s LP associated token account.
+pub fn stake(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    booth_id: u64,
+    amount: u64,
+) -> Instruction {
+    let (vault, _) = find_booth_address(program_id, mint, booth_id);
+    let (lp_mint, _) = find_lp_mint_address(program_id, &vault);
+    let mut instruction = staking_instruction(
+        program_id,
+        owner,
+        mint,
+        booth_id,
+        &lp_mint,
+        TokenTracingInstruction::Stake { amount },
+    );
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(system_program::id(), false));
+    instruction
+}
+
+/// Creates an `Unstake` instruction to `owner`'s LP associated token account.
+pub fn unstake(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    booth_id: u64,
+    amount: u64,
+) -> Instruction {
+    let (vault, _) = find_booth_address(program_id, mint, booth_id);
+    let (lp_mint, _) = find_lp_mint_address(program_id, &vault);
+    staking_instruction(
+        program_id,
+        owner,
+        mint,
+        booth_id,
+        &lp_mint,
+        TokenTracingInstruction::Unstake { amount },
+    )
+}
+
+/// Creates a `ClaimRewards` instruction paying `owner`'s associated token account.
+pub fn claim_rewards(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    booth_id: u64,
+    reward_mint: &Pubkey,
+) -> Instruction {
+    staking_instruction(
+        program_id,
+        owner,
+        mint,
+        booth_id,
+        reward_mint,
+        TokenTracingInstruction::ClaimRewards,
+    )
+}
+
+/// Creates a `RemoveLiquidity` instruction paying `owner` and its associated
+/// token account for LP tokens burnt from its LP associated token account.
+pub fn remove_liquidity(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    booth_id: u64,
+    lp_amount: u64,
+) -> Instruction {
+    let (vault, _) = find_booth_address(program_id, mint, booth_id);
+    let (lp_mint, _) = find_lp_mint_address(program_id, &vault);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*owner, true),
+            AccountMeta::new(get_associated_token_address(owner, &lp_mint), false),
+            AccountMeta::new(get_associated_token_address(owner, mint), false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new(get_associated_token_address(&vault, mint), false),
+            AccountMeta::new(lp_mint, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: TokenTracingInstruction::RemoveLiquidity { lp_amount }.pack(),
+    }
+}
+
+/// Accounts shared by the staker instructions, which move `token_mint`
+/// between the owner and the reward pool.
+fn staking_instruction(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    booth_id: u64,
+    token_mint: &Pubkey,
+    instruction: TokenTracingInstruction,
+) -> Instruction {
+    let (vault, _) = find_booth_address(program_id, mint, booth_id);
+    let (rewards, _) = find_rewards_address(program_id, &vault);
+    let (stake, _) = find_stake_address(program_id, &rewards, owner);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*owner, true),
+            AccountMeta::new(get_associated_token_address(owner, token_mint), false),
+            AccountMeta::new(rewards, false),
+            AccountMeta::new(stake, false),
+            AccountMeta::new(get_associated_token_address(&rewards, token_mint), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: instruction.pack(),
+    }
+}
+
+/// Creates a `SetAttestor` instruction.
+pub fn set_attestor(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    mint: &Pubkey,
+    booth_id: u64,
+    attestor: Option<Pubkey>,
+) -> Instruction {
+    admin_instruction(
+        program_id,
+        admin,
+        mint,
+        booth_id,
+        TokenTracingInstruction::SetAttestor { attestor },
+    )
+}
+
+/// Creates an `Attest` instruction.
+pub fn attest(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    attestor: &Pubkey,
+    wallet: &Pubkey,
+    expires_at: i64,
+) -> Instruction {
+    let (attestation, _) = find_attestation_address(program_id, attestor, wallet);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(*attestor, true),
+            AccountMeta::new(attestation, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: TokenTracingInstruction::Attest {
+            wallet: *wallet,
+            expires_at,
+        }
+        .pack(),
+    }
+}
+
+/// Creates a `RevokeAttestation` instruction, refunding the rent to `receiver`.
+pub fn revoke_attestation(
+    program_id: &Pubkey,
+    attestor: &Pubkey,
+    wallet: &Pubkey,
+    receiver: &Pubkey,
+) -> Instruction {
+    let (attestation, _) = find_attestation_address(program_id, attestor, wallet);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*attestor, true),
+            AccountMeta::new(attestation, false),
+            AccountMeta::new(*receiver, false),
+        ],
+        data: TokenTracingInstruction::RevokeAttestation { wallet: *wallet }.pack(),
+    }
+}
+
+/// Turn an admin instruction built for a single `multisig` admin key into one
+/// signed by `signers` of the booth multisig.
+pub fn with_multisig_signers(
+    mut instruction: Instruction,
+    multisig: &Pubkey,
+    signers: &[Pubkey],
+) -> Instruction {
+    for account in instruction.accounts.iter_mut() {
+        if account.pubkey == *multisig {
+            account.is_signer = false;
+        }
+    }
+    instruction.accounts.extend(
+        signers
+            .iter()
+            .map(|signer| AccountMeta::new_readonly(*signer, true)),
+    );
+    instruction
+}
+
+/// Creates a `SetMultisig` instruction.
+pub fn set_multisig(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    admin: &Pubkey,
+    mint: &Pubkey,
+    booth_id: u64,
+    threshold: u8,
+    signers: Vec<Pubkey>,
+) -> Instruction {
+    let (vault, _) = find_booth_address(program_id, mint, booth_id);
+    let (multisig, _) = find_multisig_address(program_id, &vault);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new(vault, false),
+            AccountMeta::new(multisig, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: TokenTracingInstruction::SetMultisig { threshold, signers }.pack(),
+    }
+}
+
+/// Creates a `SetAdmin` instruction.
+pub fn set_admin(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    mint: &Pubkey,
+    booth_id: u64,
+    new_admin: &Pubkey,
+) -> Instruction {
+    admin_instruction(
+        program_id,
+        admin,
+        mint,
+        booth_id,
+        TokenTracingInstruction::SetAdmin { admin: *new_admin },
+    )
+}
+
+/// Creates a `CloseExchange` instruction paying out to `receiver` and its
+/// associated token account.
+pub fn close_exchange(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    receiver: &Pubkey,
+    mint: &Pubkey,
+    booth_id: u64,
+) -> Instruction {
+    let (vault, _) = find_booth_address(program_id, mint, booth_id);
+    let (registry, _) = find_registry_address(program_id);
+    let (metadata, _) = find_metadata_address(program_id, &vault);
+    let (lp_mint, _) = find_lp_mint_address(program_id, &vault);
+    let mut accounts = payout_accounts(program_id, admin, receiver, mint, booth_id);
+    accounts.push(AccountMeta::new(registry, false));
+    accounts.push(AccountMeta::new(metadata, false));
+    accounts.push(AccountMeta::new_readonly(lp_mint, false));
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: TokenTracingInstruction::CloseExchange.pack(),
+    }
+}
+
+/// Creates a `ProposeChange` instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn propose_change(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    mint: &Pubkey,
+    booth_id: u64,
+    rate: u64,
+    fee_bps: u16,
+    curve: CurveType,
+    timelock_slots: u64,
+) -> Instruction {
+    admin_instruction(
+        program_id,
+        admin,
+        mint,
+        booth_id,
+        TokenTracingInstruction::ProposeChange {
+            rate,
+            fee_bps,
+            curve,
+            timelock_slots,
+        },
+    )
+}
+
+/// Creates a `CancelChange` instruction.
+pub fn cancel_change(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    mint: &Pubkey,
+    booth_id: u64,
+) -> Instruction {
+    admin_instruction(
+        program_id,
+        admin,
+        mint,
+        booth_id,
+        TokenTracingInstruction::CancelChange,
+    )
+}
+
+/// Creates an `ExecuteChange` instruction.
+pub fn execute_change(program_id: &Pubkey, mint: &Pubkey, booth_id: u64) -> Instruction {
+    let (vault, _) = find_booth_address(program_id, mint, booth_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![AccountMeta::new(vault, false)],
+        data: TokenTracingInstruction::ExecuteChange.pack(),
+    }
+}
+
+/// Admin instructions that only touch the booth state.
+fn admin_instruction(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    mint: &Pubkey,
+    booth_id: u64,
+    instruction: TokenTracingInstruction,
+) -> Instruction {
+    let (vault, _) = find_booth_address(program_id, mint, booth_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new(vault, false),
+        ],
+        data: instruction.pack(),
+    }
+}
@@ -1,5 +1,108 @@
+#[cfg(feature = "cpi")]
+pub mod cpi;
 pub mod entrypoint;
 pub mod errors;
+pub mod events;
 pub mod instruction;
+pub mod math;
 pub mod processor;
 pub mod state;
+
+use solana_program::pubkey::Pubkey;
+
+/// Derive the vault PDA of booth 0 for `mint`.
+pub fn find_vault_address(program_id: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    find_booth_address(program_id, mint, 0)
+}
+
+/// Derive the vault PDA of booth `booth_id` for `mint`. Booth 0 keeps the
+/// `[b"vault", mint]` seeds it had before mints could have several booths.
+pub fn find_booth_address(program_id: &Pubkey, mint: &Pubkey, booth_id: u64) -> (Pubkey, u8) {
+    match booth_id {
+        0 => Pubkey::find_program_address(&[b"vault", mint.as_ref()], program_id),
+        _ => Pubkey::find_program_address(
+            &[b"vault", mint.as_ref(), &booth_id.to_le_bytes()],
+            program_id,
+        ),
+    }
+}
+
+/// Derive the PDA listing `wallet` in the access list of the booth at `vault`.
+pub fn find_access_entry_address(program_id: &Pubkey, vault: &Pubkey, wallet: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"access", vault.as_ref(), wallet.as_ref()], program_id)
+}
+
+/// Derive the PDA where `attestor` vouches for `wallet`.
+pub fn find_attestation_address(program_id: &Pubkey, attestor: &Pubkey, wallet: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"attestation", attestor.as_ref(), wallet.as_ref()], program_id)
+}
+
+/// Derive the multisig PDA that can act as admin of the booth at `vault`.
+pub fn find_multisig_address(program_id: &Pubkey, vault: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"multisig", vault.as_ref()], program_id)
+}
+
+/// Derive the PDA tracking `wallet`'s swaps against the booth at `vault`.
+pub fn find_wallet_stats_address(program_id: &Pubkey, vault: &Pubkey, wallet: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"wallet", vault.as_ref(), wallet.as_ref()], program_id)
+}
+
+/// Derive the metadata PDA of the booth at `vault`.
+pub fn find_metadata_address(program_id: &Pubkey, vault: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"metadata", vault.as_ref()], program_id)
+}
+
+/// Derive the PDA of `owner`'s limit order `order_id` against the booth at `vault`.
+pub fn find_limit_order_address(
+    program_id: &Pubkey,
+    vault: &Pubkey,
+    owner: &Pubkey,
+    order_id: u64,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"order", vault.as_ref(), owner.as_ref(), &order_id.to_le_bytes()],
+        program_id,
+    )
+}
+
+/// Derive the PDA of `owner`'s DCA order `dca_id` against the booth at `vault`.
+pub fn find_dca_address(
+    program_id: &Pubkey,
+    vault: &Pubkey,
+    owner: &Pubkey,
+    dca_id: u64,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"dca", vault.as_ref(), owner.as_ref(), &dca_id.to_le_bytes()],
+        program_id,
+    )
+}
+
+/// Derive the PDA tracking what `referrer` earned from swaps against the booth at `vault`.
+pub fn find_referrer_stats_address(
+    program_id: &Pubkey,
+    vault: &Pubkey,
+    referrer: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"referrer", vault.as_ref(), referrer.as_ref()], program_id)
+}
+
+/// Derive the LP mint of the booth at `vault`, whose tokens its depositors receive.
+pub fn find_lp_mint_address(program_id: &Pubkey, vault: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"lp_mint", vault.as_ref()], program_id)
+}
+
+/// Derive the liquidity mining reward pool PDA of the booth at `vault`.
+pub fn find_rewards_address(program_id: &Pubkey, vault: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"rewards", vault.as_ref()], program_id)
+}
+
+/// Derive the PDA of `owner`'s stake in the reward pool `rewards`.
+pub fn find_stake_address(program_id: &Pubkey, rewards: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"stake", rewards.as_ref(), owner.as_ref()], program_id)
+}
+
+/// Derive the program-global booth registry PDA.
+pub fn find_registry_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"registry"], program_id)
+}
@@ -0,0 +1,49 @@
+//! Checked arithmetic for amounts, so no swap can wrap or underflow.
+
+use solana_program::program_error::ProgramError;
+
+use crate::errors::TokenTracingError;
+
+pub fn checked_add(a: u64, b: u64) -> Result<u64, ProgramError> {
+    a.checked_add(b)
+        .ok_or_else(|| TokenTracingError::MathOverflow.into())
+}
+
+/// `a - b`, where `a` is a balance that cannot cover `b`.
+pub fn checked_sub(a: u64, b: u64) -> Result<u64, ProgramError> {
+    a.checked_sub(b)
+        .ok_or_else(|| TokenTracingError::InsufficientFunds.into())
+}
+
+pub fn checked_mul(a: u64, b: u64) -> Result<u64, ProgramError> {
+    a.checked_mul(b)
+        .ok_or_else(|| TokenTracingError::MathOverflow.into())
+}
+
+pub fn checked_div(a: u64, b: u64) -> Result<u64, ProgramError> {
+    a.checked_div(b)
+        .ok_or_else(|| TokenTracingError::MathOverflow.into())
+}
+
+/// `a * b / denominator` computed in u128, rounding down.
+pub fn checked_mul_div(a: u64, b: u64, denominator: u64) -> Result<u64, ProgramError> {
+    let value = (a as u128)
+        .checked_mul(b as u128)
+        .and_then(|product| product.checked_div(denominator as u128))
+        .ok_or(TokenTracingError::MathOverflow)?;
+    to_u64(value)
+}
+
+/// `a * b / denominator` computed in u128, rounding up.
+pub fn checked_mul_div_ceil(a: u64, b: u64, denominator: u64) -> Result<u64, ProgramError> {
+    let value = (a as u128)
+        .checked_mul(b as u128)
+        .and_then(|product| product.checked_add(denominator as u128 - 1))
+        .and_then(|product| product.checked_div(denominator as u128))
+        .ok_or(TokenTracingError::MathOverflow)?;
+    to_u64(value)
+}
+
+pub fn to_u64(value: u128) -> Result<u64, ProgramError> {
+    u64::try_from(value).map_err(|_| TokenTracingError::MathOverflow.into())
+}
@@ -11,14 +11,48 @@ use solana_program::{
 };
 
 
+mod add_access_entry;
+mod attest;
+mod batch_exchange_sol_to_token;
+mod cancel_change;
+mod cancel_limit_order;
 mod initialize_exchange;
+mod initialize_rewards;
 mod exchange_SOL_to_token;
 mod exchange_token_to_SOL;
-// pub mod deposit;
-// pub mod exchange;
-// pub mod initialize_exchange_booth;
-// pub mod utils;
-// pub mod withdraw;
+mod execute_change;
+mod execute_dca;
+mod fill_limit_orders;
+mod flash_loan;
+mod fund_rewards;
+mod claim_rewards;
+mod close_dca;
+mod close_exchange;
+mod create_dca;
+mod deposit;
+mod migrate_exchange;
+mod place_limit_order;
+mod policy;
+mod propose_change;
+mod referral;
+mod remove_access_entry;
+mod remove_liquidity;
+mod revoke_attestation;
+mod set_access_mode;
+mod set_admin;
+mod set_attestor;
+mod set_circuit_breaker;
+mod set_fee_tiers;
+mod set_metadata;
+mod set_multisig;
+mod set_paused;
+mod set_referral_share;
+mod set_rate;
+mod set_wallet_limit;
+mod stake;
+mod unstake;
+pub mod utils;
+mod withdraw;
 pub struct Processor;
 
 impl Processor {
@@ -36,19 +70,201 @@ impl Processor {
         match instruction {
             TokenTracingInstruction::Initialize {} => {
                 msg!("Initialize");
-                initialize_exchange::process(&program_id, &accounts)?;
+                initialize_exchange::process(&program_id, &accounts, 0)?;
             },
             TokenTracingInstruction::ExchangeSOLToToken { amount } => {
                 msg!("Exchange SOL to token");
-                exchange_SOL_to_token::process(program_id, accounts, amount);
-                // deposit::process(program_id, accounts, amount)?;
+                exchange_SOL_to_token::process(program_id, accounts, amount)?;
             },
             TokenTracingInstruction::ExchangeTokenToSOL { amount } => {
                 msg!("Exchange token to SOL");
-                exchange_token_to_SOL::process(program_id, accounts, amount);
-                // withdraw::process(program_id, accounts, amount)?;
-            }
-    
+                exchange_token_to_SOL::process(program_id, accounts, amount)?;
+            },
+            TokenTracingInstruction::Deposit { sol_amount, token_amount } => {
+                msg!("Deposit");
+                deposit::process(program_id, accounts, sol_amount, token_amount)?;
+            },
+            TokenTracingInstruction::Withdraw { sol_amount, token_amount } => {
+                msg!("Withdraw");
+                withdraw::process(program_id, accounts, sol_amount, token_amount)?;
+            },
+            TokenTracingInstruction::SetRate { rate } => {
+                msg!("Set rate");
+                set_rate::process(program_id, accounts, rate)?;
+            },
+            TokenTracingInstruction::SetPaused { paused } => {
+                msg!("Set paused");
+                set_paused::process(program_id, accounts, paused)?;
+            },
+            TokenTracingInstruction::MigrateExchange => {
+                msg!("Migrate exchange");
+                migrate_exchange::process(program_id, accounts)?;
+            },
+            TokenTracingInstruction::SetWalletLimit { limit, window_seconds } => {
+                msg!("Set wallet limit");
+                set_wallet_limit::process(program_id, accounts, limit, window_seconds)?;
+            },
+            TokenTracingInstruction::SetCircuitBreaker {
+                max_sol_outflow,
+                max_token_outflow,
+                window_seconds,
+            } => {
+                msg!("Set circuit breaker");
+                set_circuit_breaker::process(
+                    program_id,
+                    accounts,
+                    max_sol_outflow,
+                    max_token_outflow,
+                    window_seconds,
+                )?;
+            },
+            TokenTracingInstruction::SetAccessMode { mode } => {
+                msg!("Set access mode");
+                set_access_mode::process(program_id, accounts, mode)?;
+            },
+            TokenTracingInstruction::AddAccessEntry { wallet } => {
+                msg!("Add access entry");
+                add_access_entry::process(program_id, accounts, wallet)?;
+            },
+            TokenTracingInstruction::RemoveAccessEntry { wallet } => {
+                msg!("Remove access entry");
+                remove_access_entry::process(program_id, accounts, wallet)?;
+            },
+            TokenTracingInstruction::SetAttestor { attestor } => {
+                msg!("Set attestor");
+                set_attestor::process(program_id, accounts, attestor)?;
+            },
+            TokenTracingInstruction::Attest { wallet, expires_at } => {
+                msg!("Attest");
+                attest::process(program_id, accounts, wallet, expires_at)?;
+            },
+            TokenTracingInstruction::RevokeAttestation { wallet } => {
+                msg!("Revoke attestation");
+                revoke_attestation::process(program_id, accounts, wallet)?;
+            },
+            TokenTracingInstruction::SetMultisig { threshold, signers } => {
+                msg!("Set multisig");
+                set_multisig::process(program_id, accounts, threshold, signers)?;
+            },
+            TokenTracingInstruction::SetAdmin { admin } => {
+                msg!("Set admin");
+                set_admin::process(program_id, accounts, admin)?;
+            },
+            TokenTracingInstruction::CloseExchange => {
+                msg!("Close exchange");
+                close_exchange::process(program_id, accounts)?;
+            },
+            TokenTracingInstruction::ProposeChange {
+                rate,
+                fee_bps,
+                curve,
+                timelock_slots,
+            } => {
+                msg!("Propose change");
+                propose_change::process(program_id, accounts, rate, fee_bps, curve, timelock_slots)?;
+            },
+            TokenTracingInstruction::CancelChange => {
+                msg!("Cancel change");
+                cancel_change::process(program_id, accounts)?;
+            },
+            TokenTracingInstruction::ExecuteChange => {
+                msg!("Execute change");
+                execute_change::process(program_id, accounts)?;
+            },
+            TokenTracingInstruction::InitializeBooth { booth_id } => {
+                msg!("Initialize booth {}", booth_id);
+                initialize_exchange::process(program_id, accounts, booth_id)?;
+            },
+            TokenTracingInstruction::SetMetadata {
+                name,
+                symbol,
+                description,
+                uri,
+            } => {
+                msg!("Set metadata");
+                set_metadata::process(program_id, accounts, name, symbol, description, uri)?;
+            },
+            TokenTracingInstruction::FlashLoan { amount, side } => {
+                msg!("Flash loan");
+                flash_loan::process(program_id, accounts, amount, side)?;
+            },
+            TokenTracingInstruction::PlaceLimitOrder {
+                order_id,
+                side,
+                amount,
+                min_out,
+            } => {
+                msg!("Place limit order");
+                place_limit_order::process(program_id, accounts, order_id, side, amount, min_out)?;
+            },
+            TokenTracingInstruction::CancelLimitOrder => {
+                msg!("Cancel limit order");
+                cancel_limit_order::process(program_id, accounts)?;
+            },
+            TokenTracingInstruction::FillLimitOrders => {
+                msg!("Fill limit orders");
+                fill_limit_orders::process(program_id, accounts)?;
+            },
+            TokenTracingInstruction::CreateDca {
+                dca_id,
+                amount_per_period,
+                interval,
+                deposit,
+            } => {
+                msg!("Create DCA order");
+                create_dca::process(
+                    program_id,
+                    accounts,
+                    dca_id,
+                    amount_per_period,
+                    interval,
+                    deposit,
+                )?;
+            },
+            TokenTracingInstruction::CloseDca => {
+                msg!("Close DCA order");
+                close_dca::process(program_id, accounts)?;
+            },
+            TokenTracingInstruction::ExecuteDca => {
+                msg!("Execute DCA order");
+                execute_dca::process(program_id, accounts)?;
+            },
+            TokenTracingInstruction::BatchExchangeSOLToToken { amounts } => {
+                msg!("Batch exchange SOL to token");
+                batch_exchange_sol_to_token::process(program_id, accounts, amounts)?;
+            },
+            TokenTracingInstruction::SetReferralShare { share_bps } => {
+                msg!("Set referral share");
+                set_referral_share::process(program_id, accounts, share_bps)?;
+            },
+            TokenTracingInstruction::SetFeeTiers { tiers } => {
+                msg!("Set fee tiers");
+                set_fee_tiers::process(program_id, accounts, tiers)?;
+            },
+            TokenTracingInstruction::InitializeRewards => {
+                msg!("Initialize rewards");
+                initialize_rewards::process(program_id, accounts)?;
+            },
+            TokenTracingInstruction::FundRewards { amount, reward_rate } => {
+                msg!("Fund rewards");
+                fund_rewards::process(program_id, accounts, amount, reward_rate)?;
+            },
+            TokenTracingInstruction::Stake { amount } => {
+                msg!("Stake");
+                stake::process(program_id, accounts, amount)?;
+            },
+            TokenTracingInstruction::Unstake { amount } => {
+                msg!("Unstake");
+                unstake::process(program_id, accounts, amount)?;
+            },
+            TokenTracingInstruction::ClaimRewards => {
+                msg!("Claim rewards");
+                claim_rewards::process(program_id, accounts)?;
+            },
+            TokenTracingInstruction::RemoveLiquidity { lp_amount } => {
+                msg!("Remove liquidity");
+                remove_liquidity::process(program_id, accounts, lp_amount)?;
+            },
         }
         Ok(())
     }
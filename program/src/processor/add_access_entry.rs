@@ -0,0 +1,55 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::errors::TokenTracingError;
+use crate::find_access_entry_address;
+use crate::processor::utils::{
+    assert_admin, assert_signer, assert_system_program, assert_writable, create_pda_account,
+};
+use crate::state::{load_exchange_account, store_account, AccessEntry, AccountState};
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], wallet: Pubkey) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let payer = next_account_info(accounts_iter)?;
+    let admin = next_account_info(accounts_iter)?;
+    let vault = next_account_info(accounts_iter)?;
+    let access_entry = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    assert_signer(payer)?;
+    assert_writable(access_entry)?;
+    assert_system_program(system_program)?;
+    let exchange = load_exchange_account(program_id, vault)?;
+    assert_admin(program_id, &exchange, admin, accounts_iter.as_slice())?;
+
+    let (access_entry_pda, bump_seed) = find_access_entry_address(program_id, vault.key, &wallet);
+    if access_entry_pda != *access_entry.key {
+        msg!("Invalid access entry account");
+        return Err(TokenTracingError::InvalidAccountAddress.into());
+    }
+    if access_entry.owner == program_id {
+        msg!("{} is already listed", wallet);
+        return Ok(());
+    }
+
+    msg!("add {} to the access list", wallet);
+    create_pda_account(
+        program_id,
+        payer,
+        access_entry,
+        system_program,
+        AccessEntry::LEN,
+        &[b"access", vault.key.as_ref(), wallet.as_ref(), &[bump_seed]],
+    )?;
+    store_account(
+        &AccessEntry {
+            vault: *vault.key,
+            wallet,
+        },
+        access_entry,
+    )
+}
@@ -0,0 +1,57 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::errors::TokenTracingError;
+use crate::find_attestation_address;
+use crate::processor::utils::{
+    assert_signer, assert_system_program, assert_writable, create_pda_account,
+};
+use crate::state::{load_account, store_account, AccountState, Attestation};
+
+pub fn process(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    wallet: Pubkey,
+    expires_at: i64,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let payer = next_account_info(accounts_iter)?;
+    let attestor = next_account_info(accounts_iter)?;
+    let attestation = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    assert_signer(payer)?;
+    assert_signer(attestor)?;
+    assert_writable(attestation)?;
+    assert_system_program(system_program)?;
+    let (attestation_pda, bump_seed) = find_attestation_address(program_id, attestor.key, &wallet);
+    if attestation_pda != *attestation.key {
+        msg!("Invalid attestation account");
+        return Err(TokenTracingError::InvalidAccountAddress.into());
+    }
+
+    let mut state = if attestation.owner == program_id {
+        load_account::<Attestation>(program_id, attestation)?
+    } else {
+        create_pda_account(
+            program_id,
+            payer,
+            attestation,
+            system_program,
+            Attestation::LEN,
+            &[b"attestation", attestor.key.as_ref(), wallet.as_ref(), &[bump_seed]],
+        )?;
+        Attestation {
+            attestor: *attestor.key,
+            subject: wallet,
+            ..Attestation::default()
+        }
+    };
+    msg!("attest {} until {}", wallet, expires_at);
+    state.expires_at = expires_at;
+    store_account(&state, attestation)
+}
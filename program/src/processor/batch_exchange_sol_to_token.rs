@@ -0,0 +1,110 @@
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_instruction,
+};
+
+use crate::errors::TokenTracingError;
+use crate::events::TokenTracingEvent;
+use crate::math::{checked_add, checked_sub};
+use crate::processor::exchange_SOL_to_token::{pay_tokens, quote_tokens};
+use crate::processor::policy::{
+    charge_outflow, enforce_access, enforce_attestation, enforce_wallet_limit, wallet_fee_tier,
+};
+use crate::processor::utils::{assert_token_account, SwapAccounts};
+use crate::state::{store_account, OrderSide};
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], amounts: Vec<u64>) -> ProgramResult {
+    msg!("batch swap sol to token for {} recipients", amounts.len());
+    if amounts.is_empty() || amounts.contains(&0) {
+        msg!("every recipient must be paid something");
+        return Err(TokenTracingError::InvalidInstructionData.into());
+    }
+    // the recipients trail the swap accounts, after any optional ones
+    let split = accounts
+        .len()
+        .checked_sub(amounts.len())
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let (swap_accounts, recipients) = accounts.split_at(split);
+    let SwapAccounts {
+        payer,
+        mint,
+        vault,
+        vault_token_account,
+        token_program,
+        system_program,
+        mut exchange,
+        mut vault_token_balance,
+        wallet_stats,
+        access_entry,
+        attestation,
+        referral,
+        ..
+    } = SwapAccounts::parse(program_id, swap_accounts)?;
+    if referral.is_some() {
+        msg!("batch swaps do not pay referrers");
+        return Err(TokenTracingError::InvalidAccountAddress.into());
+    }
+    if exchange.paused {
+        msg!("Exchange booth is paused");
+        return Err(TokenTracingError::BoothPaused.into());
+    }
+    enforce_access(program_id, &exchange, payer, access_entry)?;
+    enforce_attestation(program_id, &exchange, payer, attestation)?;
+    let (fee_bps, tier) = wallet_fee_tier(program_id, &exchange, payer, wallet_stats)?;
+
+    // quote every recipient before moving anything so the batch lands whole or not at all
+    let (mut total, mut total_tokens) = (0, 0);
+    let mut payouts = Vec::with_capacity(amounts.len());
+    for (recipient, amount) in recipients.iter().zip(amounts) {
+        assert_token_account(recipient, mint.key, None)?;
+        let tokens = quote_tokens(&exchange, amount, fee_bps, vault_token_balance, 0)?;
+        vault_token_balance = checked_sub(vault_token_balance, tokens)?;
+        total = checked_add(total, amount)?;
+        total_tokens = checked_add(total_tokens, tokens)?;
+        payouts.push((recipient, amount, tokens));
+    }
+    enforce_wallet_limit(
+        program_id,
+        &exchange,
+        payer,
+        wallet_stats,
+        payer,
+        system_program,
+        total,
+    )?;
+
+    msg!("transfer {} lamports from payer to program", total);
+    invoke(
+        &system_instruction::transfer(payer.key, vault.key, total),
+        &[system_program.clone(), payer.clone(), vault.clone()],
+    )?;
+    for (recipient, amount, tokens) in payouts {
+        msg!("transfer {} tokens to {}", tokens, recipient.key);
+        pay_tokens(
+            &exchange,
+            token_program,
+            vault_token_account,
+            recipient,
+            vault,
+            tokens,
+        )?;
+        TokenTracingEvent::Swap {
+            vault: *vault.key,
+            payer: *payer.key,
+            side: OrderSide::SolToToken,
+            amount_in: amount,
+            amount_out: tokens,
+            fee_bps,
+            tier,
+        }
+        .emit();
+    }
+    charge_outflow(&mut exchange, 0, total_tokens)?;
+
+    store_account(&exchange, vault)
+}
@@ -0,0 +1,29 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::errors::TokenTracingError;
+use crate::events::TokenTracingEvent;
+use crate::processor::utils::assert_admin;
+use crate::state::{load_exchange_account, store_account, PendingChange};
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let admin = next_account_info(accounts_iter)?;
+    let vault = next_account_info(accounts_iter)?;
+
+    let mut exchange = load_exchange_account(program_id, vault)?;
+    assert_admin(program_id, &exchange, admin, accounts_iter.as_slice())?;
+    if exchange.pending_change().is_none() {
+        return Err(TokenTracingError::NoPendingChange.into());
+    }
+
+    msg!("cancel pending change");
+    exchange.pending = PendingChange::default();
+    store_account(&exchange, vault)?;
+    TokenTracingEvent::ChangeCancelled { vault: *vault.key }.emit();
+    Ok(())
+}
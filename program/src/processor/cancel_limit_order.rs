@@ -0,0 +1,51 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::errors::TokenTracingError;
+use crate::events::TokenTracingEvent;
+use crate::processor::utils::{
+    assert_signer, assert_token_program, assert_writable, close_account, release_order_tokens,
+};
+use crate::state::{load_account, LimitOrder, OrderSide};
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let owner = next_account_info(accounts_iter)?;
+    let owner_token_account = next_account_info(accounts_iter)?;
+    let order = next_account_info(accounts_iter)?;
+    let order_token_account = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+
+    assert_signer(owner)?;
+    assert_writable(order)?;
+    assert_token_program(token_program)?;
+    let limit_order = load_account::<LimitOrder>(program_id, order)?;
+    if limit_order.owner != *owner.key {
+        msg!("{} does not own limit order {}", owner.key, order.key);
+        return Err(TokenTracingError::InvalidOwner.into());
+    }
+
+    msg!("cancel limit order {}", limit_order.order_id);
+    if limit_order.side == OrderSide::TokenToSol {
+        release_order_tokens(
+            &limit_order,
+            order,
+            order_token_account,
+            owner_token_account,
+            owner,
+            token_program,
+        )?;
+    }
+    // escrowed lamports, crank reward and rent all go back to the owner
+    close_account(order, owner)?;
+    TokenTracingEvent::LimitOrderCancelled {
+        vault: limit_order.vault,
+        order: *order.key,
+    }
+    .emit();
+    Ok(())
+}
@@ -0,0 +1,70 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+
+use crate::events::TokenTracingEvent;
+use crate::processor::utils::{
+    assert_signer, assert_token_account, assert_token_program, assert_vault_token_account,
+    assert_writable, invoke_signed_by_rewards, load_stake,
+};
+use crate::state::store_account;
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let owner = next_account_info(accounts_iter)?;
+    let owner_token_account = next_account_info(accounts_iter)?;
+    let rewards = next_account_info(accounts_iter)?;
+    let stake = next_account_info(accounts_iter)?;
+    let reward_token_account = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+
+    assert_signer(owner)?;
+    assert_writable(rewards)?;
+    assert_writable(stake)?;
+    assert_token_program(token_program)?;
+    let (mut pool, mut position) = load_stake(program_id, rewards, stake, owner)?;
+    assert_vault_token_account(reward_token_account, rewards.key, &pool.reward_mint)?;
+    assert_token_account(owner_token_account, &pool.reward_mint, None)?;
+
+    pool.update(Clock::get()?.unix_timestamp)?;
+    position.settle(pool.acc_reward_per_share)?;
+    position.reset_debt(pool.acc_reward_per_share)?;
+    let amount = position.pending;
+    position.pending = 0;
+
+    if amount > 0 {
+        msg!("claim {} rewards", amount);
+        invoke_signed_by_rewards(
+            &spl_token::instruction::transfer(
+                token_program.key,
+                reward_token_account.key,
+                owner_token_account.key,
+                rewards.key,
+                &[],
+                amount,
+            )?,
+            &[
+                token_program.clone(),
+                reward_token_account.clone(),
+                owner_token_account.clone(),
+                rewards.clone(),
+            ],
+            &pool,
+        )?;
+    }
+
+    store_account(&position, stake)?;
+    store_account(&pool, rewards)?;
+    TokenTracingEvent::RewardsClaimed {
+        vault: pool.vault,
+        owner: *owner.key,
+        amount,
+    }
+    .emit();
+    Ok(())
+}
@@ -0,0 +1,27 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::errors::TokenTracingError;
+use crate::processor::utils::{assert_signer, assert_writable, close_account};
+use crate::state::{load_account, DcaOrder};
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let owner = next_account_info(accounts_iter)?;
+    let dca = next_account_info(accounts_iter)?;
+
+    assert_signer(owner)?;
+    assert_writable(dca)?;
+    let dca_order = load_account::<DcaOrder>(program_id, dca)?;
+    if dca_order.owner != *owner.key {
+        msg!("{} does not own DCA order {}", owner.key, dca.key);
+        return Err(TokenTracingError::InvalidOwner.into());
+    }
+
+    msg!("close DCA order {}", dca_order.dca_id);
+    close_account(dca, owner)
+}
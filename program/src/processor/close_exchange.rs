@@ -0,0 +1,111 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::errors::TokenTracingError;
+use crate::processor::utils::{
+    assert_admin, assert_no_lp_supply, assert_token_program, assert_vault_token_account,
+    assert_writable, available_lamports, close_account, invoke_signed_by_vault, load_vault,
+    transfer_lamports,
+};
+use crate::state::{load_account, BoothRegistry};
+use crate::{find_metadata_address, find_registry_address};
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let admin = next_account_info(accounts_iter)?;
+    let receiver_token_account = next_account_info(accounts_iter)?;
+    let mint = next_account_info(accounts_iter)?;
+    let vault = next_account_info(accounts_iter)?;
+    let vault_token_account = next_account_info(accounts_iter)?;
+    let token_program_id = next_account_info(accounts_iter)?;
+    let receiver = next_account_info(accounts_iter)?;
+    let registry = next_account_info(accounts_iter)?;
+    let metadata = next_account_info(accounts_iter)?;
+    let lp_mint = next_account_info(accounts_iter)?;
+
+    assert_token_program(token_program_id)?;
+    assert_writable(receiver)?;
+    if *registry.key != find_registry_address(program_id).0 {
+        msg!("Invalid account key for registry");
+        return Err(TokenTracingError::InvalidAccountAddress.into());
+    }
+    if *metadata.key != find_metadata_address(program_id, vault.key).0 {
+        msg!("Invalid metadata account");
+        return Err(TokenTracingError::InvalidAccountAddress.into());
+    }
+    let exchange = load_vault(program_id, vault, mint.key)?;
+    assert_admin(program_id, &exchange, admin, accounts_iter.as_slice())?;
+    assert_no_lp_supply(program_id, vault, lp_mint)?;
+    let vault_tokens = assert_vault_token_account(vault_token_account, vault.key, mint.key)?.amount;
+
+    if vault_tokens > 0 {
+        msg!("withdraw {} tokens from vault", vault_tokens);
+        invoke_signed_by_vault(
+            &spl_token::instruction::transfer(
+                token_program_id.key,
+                vault_token_account.key,
+                receiver_token_account.key,
+                vault.key,
+                &[],
+                vault_tokens,
+            )?,
+            &[
+                token_program_id.clone(),
+                vault_token_account.clone(),
+                receiver_token_account.clone(),
+                vault.clone(),
+            ],
+            &exchange,
+        )?;
+    }
+    invoke_signed_by_vault(
+        &spl_token::instruction::close_account(
+            token_program_id.key,
+            vault_token_account.key,
+            receiver.key,
+            vault.key,
+            &[],
+        )?,
+        &[
+            token_program_id.clone(),
+            vault_token_account.clone(),
+            receiver.clone(),
+            vault.clone(),
+        ],
+        &exchange,
+    )?;
+
+    if metadata.owner == program_id {
+        msg!("close booth metadata {}", metadata.key);
+        close_account(metadata, receiver)?;
+    }
+    unregister_booth(program_id, registry, vault, receiver)?;
+
+    msg!("close exchange booth {}", vault.key);
+    close_account(vault, receiver)
+}
+
+/// Drop the booth from the registry, if it was registered, and send the rent
+/// its entry held to `receiver`.
+fn unregister_booth(
+    program_id: &Pubkey,
+    registry: &AccountInfo,
+    vault: &AccountInfo,
+    receiver: &AccountInfo,
+) -> ProgramResult {
+    if registry.owner != program_id {
+        return Ok(());
+    }
+    assert_writable(registry)?;
+    let mut booths = load_account::<BoothRegistry>(program_id, registry)?;
+    if !booths.remove(&mut registry.data.borrow_mut(), vault.key)? {
+        msg!("booth {} is not in the registry", vault.key);
+        return Ok(());
+    }
+    registry.realloc(BoothRegistry::space(booths.count), false)?;
+    transfer_lamports(registry, receiver, available_lamports(registry)?)
+}
@@ -0,0 +1,102 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke,
+    pubkey::Pubkey,
+    system_instruction,
+    sysvar::Sysvar,
+};
+
+use crate::errors::TokenTracingError;
+use crate::find_dca_address;
+use crate::processor::policy::{enforce_access, enforce_attestation, prepare_wallet_stats};
+use crate::processor::utils::{
+    assert_signer, assert_system_program, assert_writable, create_pda_account,
+};
+use crate::state::{load_exchange_account, store_account, AccountState, DcaOrder};
+
+pub fn process(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    dca_id: u64,
+    amount_per_period: u64,
+    interval: i64,
+    deposit: u64,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let owner = next_account_info(accounts_iter)?;
+    let vault = next_account_info(accounts_iter)?;
+    let dca = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let wallet_stats = accounts_iter.next();
+    let access_entry = accounts_iter.next();
+    let attestation = accounts_iter.next();
+
+    assert_signer(owner)?;
+    assert_writable(dca)?;
+    assert_system_program(system_program)?;
+    if amount_per_period == 0 || interval <= 0 || deposit == 0 {
+        msg!("DCA order needs an amount, an interval and a deposit");
+        return Err(TokenTracingError::InvalidInstructionData.into());
+    }
+    let exchange = load_exchange_account(program_id, vault)?;
+    if exchange.paused {
+        msg!("Exchange booth is paused");
+        return Err(TokenTracingError::BoothPaused.into());
+    }
+    enforce_access(program_id, &exchange, owner, access_entry)?;
+    enforce_attestation(program_id, &exchange, owner, attestation)?;
+    // each period counts against the wallet limit as it executes, without
+    // the owner there to pay for the stats PDA
+    prepare_wallet_stats(program_id, &exchange, owner, wallet_stats, system_program)?;
+
+    let (dca_pda, bump) = find_dca_address(program_id, vault.key, owner.key, dca_id);
+    if dca_pda != *dca.key {
+        msg!("Invalid DCA account");
+        return Err(TokenTracingError::InvalidAccountAddress.into());
+    }
+    if dca.owner == program_id {
+        msg!("DCA order {} already exists", dca_id);
+        return Err(TokenTracingError::InvalidAccountAddress.into());
+    }
+
+    msg!(
+        "create DCA order {}: {} lamports every {}s out of {}",
+        dca_id,
+        amount_per_period,
+        interval,
+        deposit
+    );
+    create_pda_account(
+        program_id,
+        owner,
+        dca,
+        system_program,
+        DcaOrder::LEN,
+        &[
+            b"dca",
+            vault.key.as_ref(),
+            owner.key.as_ref(),
+            &dca_id.to_le_bytes(),
+            &[bump],
+        ],
+    )?;
+    invoke(
+        &system_instruction::transfer(owner.key, dca.key, deposit),
+        &[owner.clone(), dca.clone(), system_program.clone()],
+    )?;
+    store_account(
+        &DcaOrder {
+            vault: *vault.key,
+            owner: *owner.key,
+            dca_id,
+            amount_per_period,
+            interval,
+            next_execution: Clock::get()?.unix_timestamp,
+            bump,
+        },
+        dca,
+    )
+}
@@ -0,0 +1,146 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    system_instruction,
+};
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+
+use crate::find_lp_mint_address;
+use crate::math::checked_mul_div;
+use crate::processor::utils::{
+    assert_signer, assert_system_program, assert_token_program, assert_vault_token_account,
+    available_lamports, create_pda_account_owned_by, invoke_signed_by_vault, load_vault, lp_supply,
+};
+
+/// LP tokens count lamports, so they share SOL's decimals.
+const LP_DECIMALS: u8 = 9;
+
+pub fn process(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    sol_amount: u64,
+    token_amount: u64,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let depositor = next_account_info(accounts_iter)?;
+    let depositor_token_account = next_account_info(accounts_iter)?;
+    let mint = next_account_info(accounts_iter)?;
+    let vault = next_account_info(accounts_iter)?;
+    let vault_token_account = next_account_info(accounts_iter)?;
+    let token_program_id = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let lp_mint = next_account_info(accounts_iter)?;
+    let depositor_lp_account = next_account_info(accounts_iter)?;
+    let associated_token_program = next_account_info(accounts_iter)?;
+
+    assert_signer(depositor)?;
+    assert_token_program(token_program_id)?;
+    assert_system_program(system_program)?;
+    let exchange = load_vault(program_id, vault, mint.key)?;
+    let vault_tokens = assert_vault_token_account(vault_token_account, vault.key, mint.key)?.amount;
+    let supply = lp_supply(program_id, vault, lp_mint)?;
+
+    // LP tokens are shares of the reserves, so a deposit mints its share of
+    // the supply. The first one mints one LP token per lamport it is worth.
+    let value = exchange.value_in_lamports(sol_amount, token_amount)?;
+    let lp_amount = match supply {
+        0 => value,
+        _ => {
+            let reserves = exchange.value_in_lamports(available_lamports(vault)?, vault_tokens)?;
+            checked_mul_div(value, supply, reserves)?
+        }
+    };
+
+    if sol_amount > 0 {
+        msg!("deposit {} lamports into vault", sol_amount);
+        invoke(
+            &system_instruction::transfer(depositor.key, vault.key, sol_amount),
+            &[system_program.clone(), depositor.clone(), vault.clone()],
+        )?;
+    }
+
+    if token_amount > 0 {
+        msg!("deposit {} tokens into vault", token_amount);
+        invoke(
+            &spl_token::instruction::transfer(
+                token_program_id.key,
+                depositor_token_account.key,
+                vault_token_account.key,
+                depositor.key,
+                &[],
+                token_amount,
+            )?,
+            &[
+                token_program_id.clone(),
+                depositor_token_account.clone(),
+                vault_token_account.clone(),
+                depositor.clone(),
+            ],
+        )?;
+    }
+
+    if lp_amount == 0 {
+        return Ok(());
+    }
+    if lp_mint.owner != token_program_id.key {
+        msg!("create the booth LP mint");
+        let (_, lp_bump) = find_lp_mint_address(program_id, vault.key);
+        create_pda_account_owned_by(
+            token_program_id.key,
+            depositor,
+            lp_mint,
+            system_program,
+            spl_token::state::Mint::LEN,
+            &[b"lp_mint", vault.key.as_ref(), &[lp_bump]],
+        )?;
+        invoke(
+            &spl_token::instruction::initialize_mint2(
+                token_program_id.key,
+                lp_mint.key,
+                vault.key,
+                None,
+                LP_DECIMALS,
+            )?,
+            std::slice::from_ref(lp_mint),
+        )?;
+    }
+    invoke(
+        &create_associated_token_account_idempotent(
+            depositor.key,
+            depositor.key,
+            lp_mint.key,
+            token_program_id.key,
+        ),
+        &[
+            depositor.clone(),
+            depositor_lp_account.clone(),
+            depositor.clone(),
+            lp_mint.clone(),
+            system_program.clone(),
+            token_program_id.clone(),
+            associated_token_program.clone(),
+        ],
+    )?;
+    msg!("mint {} LP tokens", lp_amount);
+    invoke_signed_by_vault(
+        &spl_token::instruction::mint_to(
+            token_program_id.key,
+            lp_mint.key,
+            depositor_lp_account.key,
+            vault.key,
+            &[],
+            lp_amount,
+        )?,
+        &[
+            token_program_id.clone(),
+            lp_mint.clone(),
+            depositor_lp_account.clone(),
+            vault.clone(),
+        ],
+        &exchange,
+    )
+}
@@ -1,61 +1,150 @@
 use solana_program::{
-    account_info::next_account_info,
     account_info::AccountInfo,
     entrypoint::ProgramResult,
     msg,
-    program::{invoke, invoke_signed},
+    program::invoke,
+    program_error::ProgramError,
     pubkey::Pubkey,
 };
 use spl_associated_token_account::solana_program::system_instruction;
 
 use crate::errors::TokenTracingError;
-pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], amount: u32) -> ProgramResult {
+use crate::events::TokenTracingEvent;
+use crate::math::checked_add;
+use crate::processor::policy::{
+    charge_outflow, enforce_access, enforce_attestation, enforce_wallet_limit, wallet_fee_tier,
+};
+use crate::processor::referral::{active_referral, record_referral};
+use crate::processor::utils::{assert_token_account, invoke_signed_by_vault, SwapAccounts};
+use crate::state::{store_account, ExchangeAccount, OrderSide};
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -> ProgramResult {
     msg!("swap sol to token, lamports: {}", amount);
-    let accounts_iter = &mut accounts.iter();
-    let _program = next_account_info(accounts_iter)?;
-    let payer = next_account_info(accounts_iter)?;
-    let payer_token_account = next_account_info(accounts_iter)?;
-    let mint = next_account_info(accounts_iter)?;
-    let vault = next_account_info(accounts_iter)?;
-    let vault_token_account = next_account_info(accounts_iter)?;
-    let token_program_id = next_account_info(accounts_iter)?;
-    let system_program = next_account_info(accounts_iter)?;
-
-    let (vault_pda, vault_bump_seed) =
-        Pubkey::find_program_address(&[b"vault", mint.key.as_ref()], program_id);
-    if vault_pda != *vault.key {
-        msg!("Invalid vault account");
-        return Err(TokenTracingError::InvalidAccountAddress.into());
+    let SwapAccounts {
+        payer,
+        payer_token_account,
+        mint,
+        vault,
+        vault_token_account,
+        token_program: token_program_id,
+        system_program,
+        mut exchange,
+        vault_token_balance,
+        wallet_stats,
+        access_entry,
+        attestation,
+        referral,
+        ..
+    } = SwapAccounts::parse(program_id, accounts)?;
+    if exchange.paused {
+        msg!("Exchange booth is paused");
+        return Err(TokenTracingError::BoothPaused.into());
     }
+    enforce_access(program_id, &exchange, payer, access_entry)?;
+    enforce_attestation(program_id, &exchange, payer, attestation)?;
+    let (fee_bps, tier) = wallet_fee_tier(program_id, &exchange, payer, wallet_stats)?;
+    let referral = active_referral(&exchange, payer, referral.as_ref())?;
+    let referral_tokens = match referral {
+        Some(referral) => {
+            assert_token_account(
+                referral.referrer_token_account,
+                mint.key,
+                Some(referral.referrer.key),
+            )?;
+            exchange.referral_sol_to_token(amount, fee_bps)?
+        }
+        None => 0,
+    };
+    let tokens = quote_tokens(&exchange, amount, fee_bps, vault_token_balance, referral_tokens)?;
+    enforce_wallet_limit(
+        program_id,
+        &exchange,
+        payer,
+        wallet_stats,
+        payer,
+        system_program,
+        amount,
+    )?;
 
     msg!("transfer SOL from payer to program");
-    
-    let pay_sol = system_instruction::transfer(payer.key, vault.key, amount.into());
+    let pay_sol = system_instruction::transfer(payer.key, vault.key, amount);
     let pay_sol_transation_account = [system_program.clone(), payer.clone(), vault.clone()];
-    invoke(&pay_sol, 
-        &pay_sol_transation_account);
+    invoke(&pay_sol, &pay_sol_transation_account)?;
 
     //send token
     msg!("transfer token from vault_ata: {} to payer_ata: {}", vault_token_account.key.to_string(), payer_token_account.key.to_string());
+    pay_tokens(&exchange, token_program_id, vault_token_account, payer_token_account, vault, tokens)?;
+
+    if let Some(referral) = referral {
+        msg!("pay referrer {} tokens", referral_tokens);
+        pay_tokens(
+            &exchange,
+            token_program_id,
+            vault_token_account,
+            referral.referrer_token_account,
+            vault,
+            referral_tokens,
+        )?;
+        record_referral(program_id, &exchange, referral, payer, system_program, 0, referral_tokens)?;
+    }
+    charge_outflow(&mut exchange, 0, checked_add(tokens, referral_tokens)?)?;
+
+    TokenTracingEvent::Swap {
+        vault: *vault.key,
+        payer: *payer.key,
+        side: OrderSide::SolToToken,
+        amount_in: amount,
+        amount_out: tokens,
+        fee_bps,
+        tier,
+    }
+    .emit();
+    store_account(&exchange, vault)
+}
+
+/// Tokens the booth pays for `lamports` at a fee of `fee_bps`, checked
+/// against the vault reserve together with `referral_tokens` owed to a referrer.
+pub fn quote_tokens(
+    exchange: &ExchangeAccount,
+    lamports: u64,
+    fee_bps: u16,
+    vault_token_balance: u64,
+    referral_tokens: u64,
+) -> Result<u64, ProgramError> {
+    let tokens = exchange.quote_sol_to_token_at(lamports, fee_bps)?;
+    let owed = checked_add(tokens, referral_tokens)?;
+    if owed > vault_token_balance {
+        msg!("vault cannot pay {} tokens", owed);
+        return Err(TokenTracingError::InsufficientFunds.into());
+    }
+    Ok(tokens)
+}
+
+/// Send `tokens` from the vault token account to `destination`.
+pub fn pay_tokens<'a>(
+    exchange: &ExchangeAccount,
+    token_program: &AccountInfo<'a>,
+    vault_token_account: &AccountInfo<'a>,
+    destination: &AccountInfo<'a>,
+    vault: &AccountInfo<'a>,
+    tokens: u64,
+) -> ProgramResult {
     let take_token = spl_token::instruction::transfer(
-        token_program_id.key,
-        &vault_token_account.key,
-        &payer_token_account.key,
-        &vault.key,
+        token_program.key,
+        vault_token_account.key,
+        destination.key,
+        vault.key,
         &[],
-        ((amount as u64) *( 10 as u64) as u64),
-     )?;
-    
-    invoke_signed(
+        tokens,
+    )?;
+    invoke_signed_by_vault(
         &take_token,
         &[
-            token_program_id.clone(),
-            payer_token_account.clone(),
+            token_program.clone(),
+            destination.clone(),
             vault_token_account.clone(),
             vault.clone(),
         ],
-        &[&[b"vault", mint.key.as_ref(), &[vault_bump_seed]]],
-    )?;
-
-    Ok(())
+        exchange,
+    )
 }
@@ -1,30 +1,69 @@
 use solana_program::{
-    account_info::next_account_info, account_info::AccountInfo, entrypoint::ProgramResult, msg,
-    program::invoke, pubkey::Pubkey,
+    account_info::AccountInfo, entrypoint::ProgramResult, msg, program::invoke, pubkey::Pubkey,
 };
-use spl_associated_token_account::solana_program::system_instruction;
 
-pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], amount: u32) -> ProgramResult {
-    msg!("swap sol to token, lamports: {}", amount);
-    let accounts_iter = &mut accounts.iter();
-    let program = next_account_info(accounts_iter)?;
-    let payer = next_account_info(accounts_iter)?;
-    let payer_token_account = next_account_info(accounts_iter)?;
-    let mint = next_account_info(accounts_iter)?;
-    let vault = next_account_info(accounts_iter)?;
-    let vault_token_account = next_account_info(accounts_iter)?;
-    let token_program_id = next_account_info(accounts_iter)?;
-    let system_program = next_account_info(accounts_iter)?;
-    let (vault_pda, vault_bump_seed) = Pubkey::find_program_address(&[b"vault", mint.key.as_ref()], program_id);
-    
+use crate::errors::TokenTracingError;
+use crate::events::TokenTracingEvent;
+use crate::math::checked_add;
+use crate::processor::policy::{
+    charge_outflow, enforce_access, enforce_attestation, enforce_wallet_limit, wallet_fee_tier,
+};
+use crate::processor::referral::{active_referral, record_referral};
+use crate::processor::utils::{available_lamports, transfer_lamports, SwapAccounts};
+use crate::state::{store_account, OrderSide};
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+    msg!("swap token to sol, tokens: {}", amount);
+    let SwapAccounts {
+        payer,
+        payer_token_account,
+        vault,
+        vault_token_account,
+        token_program: token_program_id,
+        system_program,
+        mut exchange,
+        wallet_stats,
+        access_entry,
+        attestation,
+        referral,
+        ..
+    } = SwapAccounts::parse(program_id, accounts)?;
+    if exchange.paused {
+        msg!("Exchange booth is paused");
+        return Err(TokenTracingError::BoothPaused.into());
+    }
+    enforce_access(program_id, &exchange, payer, access_entry)?;
+    enforce_attestation(program_id, &exchange, payer, attestation)?;
+    let (fee_bps, tier) = wallet_fee_tier(program_id, &exchange, payer, wallet_stats)?;
+    let referral = active_referral(&exchange, payer, referral.as_ref())?;
+    let referral_lamports = match referral {
+        Some(_) => exchange.referral_token_to_sol(amount, fee_bps)?,
+        None => 0,
+    };
+    let lamports = exchange.quote_token_to_sol_at(amount, fee_bps)?;
+    let owed = checked_add(lamports, referral_lamports)?;
+    if owed > available_lamports(vault)? {
+        msg!("vault cannot pay {} lamports", owed);
+        return Err(TokenTracingError::InsufficientFunds.into());
+    }
+    enforce_wallet_limit(
+        program_id,
+        &exchange,
+        payer,
+        wallet_stats,
+        payer,
+        system_program,
+        lamports,
+    )?;
+
     msg!("transfer {} Token lamports from payer to vault", amount);
     let send_token = spl_token::instruction::transfer(
-        &token_program_id.key,
-        &payer_token_account.key,
-        &vault_token_account.key,
-        &payer.key,
+        token_program_id.key,
+        payer_token_account.key,
+        vault_token_account.key,
+        payer.key,
         &[],
-        (amount as u64),
+        amount,
     )?;
     invoke(
         &send_token,
@@ -32,18 +71,30 @@ pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], amount: u32) -> Pr
             token_program_id.clone(),
             payer_token_account.clone(),
             vault_token_account.clone(),
-            program.clone(),
             payer.clone(),
         ],
     )?;
 
+    transfer_lamports(vault, payer, lamports)?;
+    msg!("vault send {} SOL lamports to payer", lamports);
+
+    if let Some(referral) = referral {
+        msg!("pay referrer {} lamports", referral_lamports);
+        transfer_lamports(vault, referral.referrer, referral_lamports)?;
+        record_referral(program_id, &exchange, referral, payer, system_program, referral_lamports, 0)?;
+    }
+    charge_outflow(&mut exchange, owed, 0)?;
 
-    **vault.try_borrow_mut_lamports()? -= (amount as u64) / 10 as u64;
-    **payer.try_borrow_mut_lamports()? += (amount as u64) / 10 as u64;
-    msg!(
-        "vault send {} SOL lamports to payer",
-        amount / 10 as u32,
-    );
+    TokenTracingEvent::Swap {
+        vault: *vault.key,
+        payer: *payer.key,
+        side: OrderSide::TokenToSol,
+        amount_in: amount,
+        amount_out: lamports,
+        fee_bps,
+        tier,
+    }
+    .emit();
 
-    Ok(())
+    store_account(&exchange, vault)
 }
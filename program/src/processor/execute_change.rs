@@ -0,0 +1,41 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+
+use crate::errors::TokenTracingError;
+use crate::events::TokenTracingEvent;
+use crate::state::{load_exchange_account, store_account, PendingChange};
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let vault = next_account_info(accounts_iter)?;
+
+    let mut exchange = load_exchange_account(program_id, vault)?;
+    let change = *exchange
+        .pending_change()
+        .ok_or(TokenTracingError::NoPendingChange)?;
+    let slot = Clock::get()?.slot;
+    if slot < change.eta_slot {
+        msg!("change is timelocked until slot {}, now {}", change.eta_slot, slot);
+        return Err(TokenTracingError::TimelockNotExpired.into());
+    }
+
+    msg!("execute {:?}", change);
+    exchange.rate = change.rate;
+    exchange.fee_bps = change.fee_bps;
+    exchange.curve = change.curve;
+    exchange.timelock_slots = change.timelock_slots;
+    exchange.pending = PendingChange::default();
+    store_account(&exchange, vault)?;
+    TokenTracingEvent::ChangeExecuted {
+        vault: *vault.key,
+        change,
+    }
+    .emit();
+    Ok(())
+}
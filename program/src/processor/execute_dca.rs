@@ -0,0 +1,126 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+use spl_associated_token_account::get_associated_token_address;
+
+use crate::errors::TokenTracingError;
+use crate::events::TokenTracingEvent;
+use crate::processor::exchange_SOL_to_token::{pay_tokens, quote_tokens};
+use crate::processor::policy::{
+    charge_outflow, enforce_access, enforce_attestation, enforce_wallet_limit, wallet_fee_tier,
+};
+use crate::processor::utils::{
+    assert_signer, assert_system_program, assert_token_account, assert_token_program,
+    assert_vault_token_account, assert_writable, available_lamports, close_account, load_vault,
+    transfer_lamports,
+};
+use crate::state::{load_account, store_account, DcaOrder};
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let mint = next_account_info(accounts_iter)?;
+    let vault = next_account_info(accounts_iter)?;
+    let vault_token_account = next_account_info(accounts_iter)?;
+    let dca = next_account_info(accounts_iter)?;
+    let owner = next_account_info(accounts_iter)?;
+    let owner_token_account = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let cranker = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let wallet_stats = next_account_info(accounts_iter)?;
+    let access_entry = next_account_info(accounts_iter)?;
+    let attestation = next_account_info(accounts_iter)?;
+
+    assert_writable(vault)?;
+    assert_writable(dca)?;
+    assert_token_program(token_program)?;
+    assert_signer(cranker)?;
+    assert_system_program(system_program)?;
+    let mut exchange = load_vault(program_id, vault, mint.key)?;
+    if exchange.paused {
+        msg!("Exchange booth is paused");
+        return Err(TokenTracingError::BoothPaused.into());
+    }
+    let mut dca_order = load_account::<DcaOrder>(program_id, dca)?;
+    if dca_order.vault != *vault.key {
+        msg!("DCA order {} is for another booth", dca.key);
+        return Err(TokenTracingError::InvalidVaultAccount.into());
+    }
+    if dca_order.owner != *owner.key {
+        msg!("{} does not own DCA order {}", owner.key, dca.key);
+        return Err(TokenTracingError::InvalidOwner.into());
+    }
+    // the booth may have shut the owner out since the order was created
+    enforce_access(program_id, &exchange, owner, Some(access_entry))?;
+    enforce_attestation(program_id, &exchange, owner, Some(attestation))?;
+    if *owner_token_account.key != get_associated_token_address(owner.key, mint.key) {
+        msg!(
+            "{} is not the owner's associated token account",
+            owner_token_account.key
+        );
+        return Err(TokenTracingError::InvalidSPLTokenAccount.into());
+    }
+    assert_token_account(owner_token_account, mint.key, Some(owner.key))?;
+    let now = Clock::get()?.unix_timestamp;
+    if now < dca_order.next_execution {
+        msg!(
+            "DCA order {} is due at {}",
+            dca_order.dca_id,
+            dca_order.next_execution
+        );
+        return Err(TokenTracingError::DcaNotDue.into());
+    }
+
+    let vault_token_balance =
+        assert_vault_token_account(vault_token_account, vault.key, mint.key)?.amount;
+    let remaining = available_lamports(dca)?;
+    let lamports = dca_order.amount_per_period.min(remaining);
+    let (fee_bps, tier) = wallet_fee_tier(program_id, &exchange, owner, Some(wallet_stats))?;
+    let tokens = quote_tokens(&exchange, lamports, fee_bps, vault_token_balance, 0)?;
+    enforce_wallet_limit(
+        program_id,
+        &exchange,
+        owner,
+        Some(wallet_stats),
+        cranker,
+        system_program,
+        lamports,
+    )?;
+
+    msg!("DCA order {} swaps {} lamports", dca_order.dca_id, lamports);
+    transfer_lamports(dca, vault, lamports)?;
+    pay_tokens(
+        &exchange,
+        token_program,
+        vault_token_account,
+        owner_token_account,
+        vault,
+        tokens,
+    )?;
+    charge_outflow(&mut exchange, 0, tokens)?;
+    store_account(&exchange, vault)?;
+
+    if lamports == remaining {
+        msg!("DCA order {} is complete", dca_order.dca_id);
+        close_account(dca, owner)?;
+    } else {
+        dca_order.advance(now)?;
+        store_account(&dca_order, dca)?;
+    }
+    TokenTracingEvent::DcaExecuted {
+        vault: *vault.key,
+        dca: *dca.key,
+        owner: *owner.key,
+        lamports,
+        tokens,
+        fee_bps,
+        tier,
+    }
+    .emit();
+    Ok(())
+}
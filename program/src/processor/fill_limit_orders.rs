@@ -0,0 +1,157 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::errors::TokenTracingError;
+use crate::events::TokenTracingEvent;
+use crate::math::checked_sub;
+use crate::processor::exchange_SOL_to_token::pay_tokens;
+use crate::processor::policy::{
+    charge_outflow, enforce_access, enforce_attestation, enforce_wallet_limit, outflow_fits,
+    wallet_fee_tier,
+};
+use crate::processor::utils::{
+    assert_signer, assert_system_program, assert_token_account, assert_token_program,
+    assert_vault_token_account, assert_writable, available_lamports, close_account, load_vault,
+    release_order_tokens, transfer_lamports,
+};
+use crate::state::{load_account, store_account, LimitOrder, OrderSide};
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let cranker = next_account_info(accounts_iter)?;
+    let mint = next_account_info(accounts_iter)?;
+    let vault = next_account_info(accounts_iter)?;
+    let vault_token_account = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    assert_signer(cranker)?;
+    assert_writable(vault)?;
+    assert_token_program(token_program)?;
+    assert_system_program(system_program)?;
+    let mut exchange = load_vault(program_id, vault, mint.key)?;
+    if exchange.paused {
+        msg!("Exchange booth is paused");
+        return Err(TokenTracingError::BoothPaused.into());
+    }
+    let mut vault_tokens =
+        assert_vault_token_account(vault_token_account, vault.key, mint.key)?.amount;
+
+    // a fill that trips the circuit breaker pauses the booth for the rest
+    while let Some(order) = accounts_iter.next().filter(|_| !exchange.paused) {
+        let order_token_account = next_account_info(accounts_iter)?;
+        let owner = next_account_info(accounts_iter)?;
+        let owner_token_account = next_account_info(accounts_iter)?;
+        let wallet_stats = next_account_info(accounts_iter)?;
+        let access_entry = next_account_info(accounts_iter)?;
+        let attestation = next_account_info(accounts_iter)?;
+
+        assert_writable(order)?;
+        let limit_order = load_account::<LimitOrder>(program_id, order)?;
+        if limit_order.vault != *vault.key {
+            msg!("limit order {} is for another booth", order.key);
+            return Err(TokenTracingError::InvalidVaultAccount.into());
+        }
+        if limit_order.owner != *owner.key {
+            msg!("{} does not own limit order {}", owner.key, order.key);
+            return Err(TokenTracingError::InvalidOwner.into());
+        }
+        // the booth may have shut the owner out since the order was placed
+        if enforce_access(program_id, &exchange, owner, Some(access_entry)).is_err()
+            || enforce_attestation(program_id, &exchange, owner, Some(attestation)).is_err()
+        {
+            msg!("owner of limit order {} may not swap", order.key);
+            continue;
+        }
+
+        let (fee_bps, tier) = wallet_fee_tier(program_id, &exchange, owner, Some(wallet_stats))?;
+        let (payout, fillable) = match limit_order.side {
+            OrderSide::SolToToken => {
+                let tokens = exchange.quote_sol_to_token_at(limit_order.amount, fee_bps)?;
+                (tokens, tokens <= vault_tokens)
+            }
+            OrderSide::TokenToSol => {
+                let lamports = exchange.quote_token_to_sol_at(limit_order.amount, fee_bps)?;
+                (lamports, lamports <= available_lamports(vault)?)
+            }
+        };
+        let (sol_out, token_out) = match limit_order.side {
+            OrderSide::SolToToken => (0, payout),
+            OrderSide::TokenToSol => (payout, 0),
+        };
+        if payout < limit_order.min_out
+            || !fillable
+            || !outflow_fits(&mut exchange, sol_out, token_out)?
+        {
+            msg!("limit order {} not fillable, quote {}", order.key, payout);
+            continue;
+        }
+        let value = match limit_order.side {
+            OrderSide::SolToToken => limit_order.amount,
+            OrderSide::TokenToSol => payout,
+        };
+        match enforce_wallet_limit(
+            program_id,
+            &exchange,
+            owner,
+            Some(wallet_stats),
+            cranker,
+            system_program,
+            value,
+        ) {
+            Err(error) if error == TokenTracingError::LimitExceeded.into() => {
+                msg!("limit order {} waits for its owner's wallet limit", order.key);
+                continue;
+            }
+            result => result?,
+        }
+
+        msg!("fill limit order {} for {}", order.key, payout);
+        match limit_order.side {
+            OrderSide::SolToToken => {
+                assert_token_account(owner_token_account, mint.key, Some(owner.key))?;
+                transfer_lamports(order, vault, limit_order.amount)?;
+                pay_tokens(
+                    &exchange,
+                    token_program,
+                    vault_token_account,
+                    owner_token_account,
+                    vault,
+                    payout,
+                )?;
+                vault_tokens = checked_sub(vault_tokens, payout)?;
+            }
+            OrderSide::TokenToSol => {
+                release_order_tokens(
+                    &limit_order,
+                    order,
+                    order_token_account,
+                    vault_token_account,
+                    owner,
+                    token_program,
+                )?;
+                transfer_lamports(vault, owner, payout)?;
+            }
+        }
+        transfer_lamports(order, cranker, limit_order.crank_reward)?;
+        close_account(order, owner)?;
+        charge_outflow(&mut exchange, sol_out, token_out)?;
+
+        TokenTracingEvent::LimitOrderFilled {
+            vault: *vault.key,
+            order: *order.key,
+            owner: *owner.key,
+            amount: limit_order.amount,
+            payout,
+            fee_bps,
+            tier,
+        }
+        .emit();
+    }
+
+    store_account(&exchange, vault)
+}
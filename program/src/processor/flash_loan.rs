@@ -0,0 +1,127 @@
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    msg,
+    program::invoke,
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+};
+
+use crate::errors::TokenTracingError;
+use crate::events::TokenTracingEvent;
+use crate::instruction::FlashLoanCallback;
+use crate::math::checked_add;
+use crate::processor::utils::{
+    assert_signer, assert_token_account, assert_token_program, assert_vault_token_account,
+    assert_writable, invoke_signed_by_vault, load_vault, transfer_lamports,
+};
+use crate::state::{flash_loan_fee, LoanSide};
+
+pub fn process(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+    side: LoanSide,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let borrower = next_account_info(accounts_iter)?;
+    let mint = next_account_info(accounts_iter)?;
+    let vault = next_account_info(accounts_iter)?;
+    let vault_token_account = next_account_info(accounts_iter)?;
+    let destination = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let receiver_program = next_account_info(accounts_iter)?;
+    let receiver_accounts = accounts_iter.as_slice();
+
+    assert_signer(borrower)?;
+    assert_writable(vault)?;
+    assert_writable(destination)?;
+    assert_token_program(token_program)?;
+    let exchange = load_vault(program_id, vault, mint.key)?;
+    if exchange.paused {
+        msg!("Exchange booth is paused");
+        return Err(TokenTracingError::BoothPaused.into());
+    }
+    // a receiver calling back into this program could swap against the lent reserves
+    if receiver_program.key == program_id {
+        msg!("flash loan receiver cannot be this program");
+        return Err(TokenTracingError::InvalidAccountAddress.into());
+    }
+    let vault_tokens = assert_vault_token_account(vault_token_account, vault.key, mint.key)?.amount;
+    let fee = flash_loan_fee(amount)?;
+    let owed = checked_add(
+        match side {
+            LoanSide::Sol => vault.lamports(),
+            LoanSide::Token => vault_tokens,
+        },
+        fee,
+    )?;
+
+    msg!("lend {} {:?} to {}", amount, side, destination.key);
+    match side {
+        LoanSide::Sol => transfer_lamports(vault, destination, amount)?,
+        LoanSide::Token => {
+            assert_token_account(destination, mint.key, None)?;
+            let lend_tokens = spl_token::instruction::transfer(
+                token_program.key,
+                vault_token_account.key,
+                destination.key,
+                vault.key,
+                &[],
+                amount,
+            )?;
+            invoke_signed_by_vault(
+                &lend_tokens,
+                &[
+                    token_program.clone(),
+                    vault_token_account.clone(),
+                    destination.clone(),
+                    vault.clone(),
+                ],
+                &exchange,
+            )?;
+        }
+    }
+
+    let callback = Instruction {
+        program_id: *receiver_program.key,
+        accounts: receiver_accounts
+            .iter()
+            .map(|account| AccountMeta {
+                pubkey: *account.key,
+                is_signer: account.is_signer,
+                is_writable: account.is_writable,
+            })
+            .collect(),
+        data: FlashLoanCallback { side, amount, fee }.try_to_vec()?,
+    };
+    let mut callback_accounts = receiver_accounts.to_vec();
+    callback_accounts.push(receiver_program.clone());
+    invoke(&callback, &callback_accounts)?;
+
+    let repaid = match side {
+        LoanSide::Sol => vault.lamports(),
+        LoanSide::Token => vault_token_balance(vault_token_account)?,
+    };
+    if repaid < owed {
+        msg!("vault holds {}, {} owed", repaid, owed);
+        return Err(TokenTracingError::FlashLoanNotRepaid.into());
+    }
+
+    TokenTracingEvent::FlashLoan {
+        vault: *vault.key,
+        borrower: *borrower.key,
+        side,
+        amount,
+        fee,
+    }
+    .emit();
+    Ok(())
+}
+
+fn vault_token_balance(vault_token_account: &AccountInfo) -> Result<u64, ProgramError> {
+    Ok(spl_token::state::Account::unpack(&vault_token_account.data.borrow())?.amount)
+}
@@ -0,0 +1,79 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+
+use crate::errors::TokenTracingError;
+use crate::events::TokenTracingEvent;
+use crate::math::checked_add;
+use crate::processor::utils::{
+    assert_admin, assert_signer, assert_token_program, assert_vault_token_account, assert_writable,
+};
+use crate::state::{load_account, load_exchange_account, store_account, RewardPool};
+
+pub fn process(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+    reward_rate: u64,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let funder = next_account_info(accounts_iter)?;
+    let funder_token_account = next_account_info(accounts_iter)?;
+    let admin = next_account_info(accounts_iter)?;
+    let vault = next_account_info(accounts_iter)?;
+    let rewards = next_account_info(accounts_iter)?;
+    let reward_token_account = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+
+    assert_signer(funder)?;
+    assert_writable(rewards)?;
+    assert_token_program(token_program)?;
+    let exchange = load_exchange_account(program_id, vault)?;
+    assert_admin(program_id, &exchange, admin, accounts_iter.as_slice())?;
+    let mut pool = load_account::<RewardPool>(program_id, rewards)?;
+    if pool.vault != *vault.key {
+        msg!("reward pool {} is for another booth", rewards.key);
+        return Err(TokenTracingError::InvalidVaultAccount.into());
+    }
+    assert_vault_token_account(reward_token_account, rewards.key, &pool.reward_mint)?;
+
+    // accrue at the old rate up to now before the new one applies
+    pool.update(Clock::get()?.unix_timestamp)?;
+    if amount > 0 {
+        msg!("fund reward pool with {}", amount);
+        invoke(
+            &spl_token::instruction::transfer(
+                token_program.key,
+                funder_token_account.key,
+                reward_token_account.key,
+                funder.key,
+                &[],
+                amount,
+            )?,
+            &[
+                token_program.clone(),
+                funder_token_account.clone(),
+                reward_token_account.clone(),
+                funder.clone(),
+            ],
+        )?;
+        pool.undistributed = checked_add(pool.undistributed, amount)?;
+    }
+    msg!("emit {} rewards per second", reward_rate);
+    pool.reward_rate = reward_rate;
+    store_account(&pool, rewards)?;
+
+    TokenTracingEvent::RewardsFunded {
+        vault: *vault.key,
+        amount,
+        reward_rate,
+    }
+    .emit();
+    Ok(())
+}
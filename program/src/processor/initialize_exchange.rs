@@ -1,22 +1,34 @@
-use solana_program::{pubkey::Pubkey, rent::Rent, account_info::{AccountInfo, next_account_info}, msg, program::invoke_signed, system_instruction::create_account, entrypoint::ProgramResult, sysvar::Sysvar};
-use borsh::BorshSerialize;
+use solana_program::{pubkey::Pubkey, rent::Rent, account_info::{AccountInfo, next_account_info}, clock::Clock, msg, system_instruction::create_account, entrypoint::ProgramResult, sysvar::Sysvar};
 use crate::errors::TokenTracingError;
+use crate::{find_booth_address, find_metadata_address, find_registry_address};
+use crate::processor::utils::{create_pda_account, invoke_signed_by_vault, resize_account};
 use crate::state::*;
-pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], booth_id: u64) -> ProgramResult {
     let acounts_iter = &mut accounts.iter();
     let payer = next_account_info(acounts_iter)?;
     let vault = next_account_info(acounts_iter)?;
     let program = next_account_info(acounts_iter)?;
     let mint = next_account_info(acounts_iter)?;
-    let (vault_pda, vault_bump_seed) = Pubkey::find_program_address(&[b"vault", mint.key.as_ref()], program_id);
+    let registry = next_account_info(acounts_iter)?;
+    let (vault_pda, vault_bump_seed) = find_booth_address(program_id, mint.key, booth_id);
     msg!("{} | {}", vault_pda, *vault.key );
     if vault_pda != *vault.key {
         msg!("Invalid account key for vault");
         return Err(TokenTracingError::InvalidVaultAccount.into());
     }
 
-    msg!("create vault {} ...", vault.key.to_string());
-    invoke_signed(
+    let account_info = ExchangeAccount {
+        admin: *payer.key,
+        vault: *vault.key,
+        mint: *mint.key,
+        rate: DEFAULT_RATE,
+        booth_id,
+        vault_bump: vault_bump_seed,
+        ..ExchangeAccount::default()
+    };
+
+    msg!("create vault {} for booth {} ...", vault.key.to_string(), booth_id);
+    invoke_signed_by_vault(
         &create_account(
             &payer.key,
             &vault.key,
@@ -25,18 +37,53 @@ pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
             program_id,
         ),
         &[payer.clone(), program.clone(), vault.clone()],
-        &[&[b"vault", mint.key.as_ref(), &[vault_bump_seed]]],
+        &account_info,
     )?;
     msg!("=================================");
     // * Allocate data to vault
-    let account_info = ExchangeAccount {
-        admin: *payer.key,
-        vault: *vault.key
-    };
+    store_account(&account_info, vault)?;
 
+    register_booth(program_id, payer, registry, program, &account_info)
+}
 
-    let account_data = &mut *vault.data.borrow_mut();
-    account_info.serialize(account_data)?;
+/// Append the new booth to the registry, creating the registry for the first booth.
+fn register_booth<'a>(
+    program_id: &Pubkey,
+    payer: &AccountInfo<'a>,
+    registry: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    exchange: &ExchangeAccount,
+) -> ProgramResult {
+    let (registry_pda, registry_bump) = find_registry_address(program_id);
+    if registry_pda != *registry.key {
+        msg!("Invalid account key for registry");
+        return Err(TokenTracingError::InvalidAccountAddress.into());
+    }
+    let mut booths = if registry.owner == program_id {
+        load_account::<BoothRegistry>(program_id, registry)?
+    } else {
+        msg!("create booth registry {}", registry.key);
+        create_pda_account(
+            program_id,
+            payer,
+            registry,
+            system_program,
+            BOOTH_REGISTRY_LEN,
+            &[b"registry", &[registry_bump]],
+        )?;
+        BoothRegistry::default()
+    };
 
-    Ok(())
+    resize_account(payer, registry, system_program, BoothRegistry::space(booths.count + 1))?;
+    let entry = RegistryEntry {
+        mint: exchange.mint,
+        vault: exchange.vault,
+        admin: exchange.admin,
+        curve: exchange.curve,
+        booth_id: exchange.booth_id,
+        created_slot: Clock::get()?.slot,
+        metadata: find_metadata_address(program_id, &exchange.vault).0,
+    };
+    msg!("register booth #{}", booths.count);
+    booths.push(&mut registry.data.borrow_mut(), &entry)
 }
@@ -0,0 +1,113 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+
+use crate::errors::TokenTracingError;
+use crate::processor::utils::{
+    assert_admin, assert_signer, assert_system_program, assert_token_program, assert_writable,
+    create_pda_account,
+};
+use crate::state::{load_exchange_account, store_account, AccountState, RewardPool};
+use crate::{find_lp_mint_address, find_rewards_address};
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let payer = next_account_info(accounts_iter)?;
+    let admin = next_account_info(accounts_iter)?;
+    let vault = next_account_info(accounts_iter)?;
+    let rewards = next_account_info(accounts_iter)?;
+    let stake_mint = next_account_info(accounts_iter)?;
+    let reward_mint = next_account_info(accounts_iter)?;
+    let stake_token_account = next_account_info(accounts_iter)?;
+    let reward_token_account = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let associated_token_program = next_account_info(accounts_iter)?;
+
+    assert_signer(payer)?;
+    assert_writable(rewards)?;
+    assert_token_program(token_program)?;
+    assert_system_program(system_program)?;
+    let exchange = load_exchange_account(program_id, vault)?;
+    assert_admin(program_id, &exchange, admin, accounts_iter.as_slice())?;
+    // stakers stake the LP tokens the booth mints to its depositors
+    let (lp_mint, _) = find_lp_mint_address(program_id, vault.key);
+    if lp_mint != *stake_mint.key {
+        msg!("Stake mint must be the booth LP mint {}", lp_mint);
+        return Err(TokenTracingError::InvalidMint.into());
+    }
+    if stake_mint.owner != token_program.key {
+        msg!("Booth has no LP mint until its first deposit");
+        return Err(TokenTracingError::InvalidMint.into());
+    }
+    // both would share the pool's associated token account
+    if stake_mint.key == reward_mint.key {
+        return Err(TokenTracingError::UniqueMintAccounts.into());
+    }
+
+    let (rewards_pda, bump) = find_rewards_address(program_id, vault.key);
+    if rewards_pda != *rewards.key {
+        msg!("Invalid reward pool account");
+        return Err(TokenTracingError::InvalidAccountAddress.into());
+    }
+    if rewards.owner == program_id {
+        msg!("booth already has a reward pool");
+        return Err(TokenTracingError::InvalidAccountAddress.into());
+    }
+
+    msg!(
+        "create reward pool staking {} for {}",
+        stake_mint.key,
+        reward_mint.key
+    );
+    create_pda_account(
+        program_id,
+        payer,
+        rewards,
+        system_program,
+        RewardPool::LEN,
+        &[b"rewards", vault.key.as_ref(), &[bump]],
+    )?;
+    // the pool address is known in advance, so anyone may have created these
+    for (mint, token_account) in [
+        (stake_mint, stake_token_account),
+        (reward_mint, reward_token_account),
+    ] {
+        invoke(
+            &create_associated_token_account_idempotent(
+                payer.key,
+                rewards.key,
+                mint.key,
+                token_program.key,
+            ),
+            &[
+                payer.clone(),
+                token_account.clone(),
+                rewards.clone(),
+                mint.clone(),
+                system_program.clone(),
+                token_program.clone(),
+                associated_token_program.clone(),
+            ],
+        )?;
+    }
+
+    store_account(
+        &RewardPool {
+            vault: *vault.key,
+            stake_mint: *stake_mint.key,
+            reward_mint: *reward_mint.key,
+            last_update: Clock::get()?.unix_timestamp,
+            bump,
+            ..RewardPool::default()
+        },
+        rewards,
+    )
+}
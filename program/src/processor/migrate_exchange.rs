@@ -0,0 +1,99 @@
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    sysvar::Sysvar,
+};
+
+use crate::errors::TokenTracingError;
+use crate::find_booth_address;
+use crate::processor::utils::assert_admin;
+use crate::state::{
+    store_account, AccountState, ExchangeAccount, LegacyExchangeAccount, ACCOUNT_HEADER_LEN,
+    DEFAULT_RATE, EXCHANGE_ACCOUNT_LEN, LEGACY_EXCHANGE_ACCOUNT_LEN,
+};
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let payer = next_account_info(accounts_iter)?;
+    let admin = next_account_info(accounts_iter)?;
+    let vault = next_account_info(accounts_iter)?;
+    let mint = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    if vault.owner != program_id {
+        msg!("Invalid vault account");
+        return Err(TokenTracingError::InvalidVaultAccount.into());
+    }
+
+    let mut exchange = match unpack_previous(&vault.data.borrow(), mint.key)? {
+        Some(exchange) => exchange,
+        None => {
+            msg!("exchange booth is already at version {}", ExchangeAccount::VERSION);
+            return Err(TokenTracingError::BoothUpToDate.into());
+        }
+    };
+    assert_admin(program_id, &exchange, admin, accounts_iter.as_slice())?;
+    // booths from before booth ids are booth 0 and did not store their bump
+    let (vault_pda, vault_bump_seed) = find_booth_address(program_id, mint.key, exchange.booth_id);
+    if vault_pda != *vault.key {
+        msg!("Invalid vault account");
+        return Err(TokenTracingError::InvalidVaultAccount.into());
+    }
+    exchange.vault_bump = vault_bump_seed;
+
+    let rent = Rent::get()?;
+    let old_len = vault.data_len();
+    let top_up = rent
+        .minimum_balance(EXCHANGE_ACCOUNT_LEN)
+        .saturating_sub(rent.minimum_balance(old_len));
+    if top_up > 0 {
+        msg!("top up vault rent with {} lamports", top_up);
+        invoke(
+            &system_instruction::transfer(payer.key, vault.key, top_up),
+            &[payer.clone(), vault.clone(), system_program.clone()],
+        )?;
+    }
+
+    msg!("realloc vault {} -> {} bytes", old_len, EXCHANGE_ACCOUNT_LEN);
+    vault.realloc(EXCHANGE_ACCOUNT_LEN, true)?;
+    store_account(&exchange, vault)
+}
+
+/// Decode a booth written by an older program version, or `None` if it is current.
+fn unpack_previous(data: &[u8], mint: &Pubkey) -> Result<Option<ExchangeAccount>, ProgramError> {
+    if data.len() == LEGACY_EXCHANGE_ACCOUNT_LEN && data[..8] != ExchangeAccount::DISCRIMINATOR {
+        msg!("migrate exchange booth from the legacy layout");
+        let legacy = LegacyExchangeAccount::try_from_slice(data)?;
+        return Ok(Some(ExchangeAccount {
+            admin: legacy.admin,
+            vault: legacy.vault,
+            mint: *mint,
+            rate: DEFAULT_RATE,
+            ..ExchangeAccount::default()
+        }));
+    }
+
+    if data.len() < ACCOUNT_HEADER_LEN || data[..8] != ExchangeAccount::DISCRIMINATOR {
+        return Err(TokenTracingError::InvalidAccountDiscriminator.into());
+    }
+    let version = data[8];
+    if version == ExchangeAccount::VERSION && data.len() >= EXCHANGE_ACCOUNT_LEN {
+        return Ok(None);
+    }
+    if version > ExchangeAccount::VERSION {
+        return Err(TokenTracingError::UnsupportedAccountVersion.into());
+    }
+
+    msg!("migrate exchange booth from version {}", version);
+    // fields are append-only, so zero-filling the tail gives the new fields their defaults
+    let mut body = data[ACCOUNT_HEADER_LEN..].to_vec();
+    body.resize(EXCHANGE_ACCOUNT_LEN - ACCOUNT_HEADER_LEN, 0);
+    Ok(Some(ExchangeAccount::deserialize(&mut &body[..])?))
+}
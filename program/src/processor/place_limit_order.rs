@@ -0,0 +1,163 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke,
+    pubkey::Pubkey,
+    system_instruction,
+};
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+
+use crate::errors::TokenTracingError;
+use crate::events::TokenTracingEvent;
+use crate::find_limit_order_address;
+use crate::math::checked_add;
+use crate::processor::policy::{enforce_access, enforce_attestation, prepare_wallet_stats};
+use crate::processor::utils::{
+    assert_signer, assert_system_program, assert_token_account, assert_token_program,
+    assert_writable, create_pda_account, load_vault,
+};
+use crate::state::{store_account, AccountState, LimitOrder, OrderSide, LIMIT_ORDER_CRANK_REWARD};
+
+pub fn process(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    order_id: u64,
+    side: OrderSide,
+    amount: u64,
+    min_out: u64,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let owner = next_account_info(accounts_iter)?;
+    let owner_token_account = next_account_info(accounts_iter)?;
+    let mint = next_account_info(accounts_iter)?;
+    let vault = next_account_info(accounts_iter)?;
+    let order = next_account_info(accounts_iter)?;
+    let order_token_account = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let associated_token_program = next_account_info(accounts_iter)?;
+    let wallet_stats = accounts_iter.next();
+    let access_entry = accounts_iter.next();
+    let attestation = accounts_iter.next();
+
+    assert_signer(owner)?;
+    assert_writable(order)?;
+    assert_token_program(token_program)?;
+    assert_system_program(system_program)?;
+    if amount == 0 {
+        msg!("limit order must sell something");
+        return Err(TokenTracingError::InvalidInstructionData.into());
+    }
+    let exchange = load_vault(program_id, vault, mint.key)?;
+    if exchange.paused {
+        msg!("Exchange booth is paused");
+        return Err(TokenTracingError::BoothPaused.into());
+    }
+    enforce_access(program_id, &exchange, owner, access_entry)?;
+    enforce_attestation(program_id, &exchange, owner, attestation)?;
+    // the order counts against the wallet limit once it fills, without the
+    // owner there to pay for the stats PDA
+    prepare_wallet_stats(program_id, &exchange, owner, wallet_stats, system_program)?;
+
+    let (order_pda, bump) = find_limit_order_address(program_id, vault.key, owner.key, order_id);
+    if order_pda != *order.key {
+        msg!("Invalid limit order account");
+        return Err(TokenTracingError::InvalidAccountAddress.into());
+    }
+    if order.owner == program_id {
+        msg!("limit order {} already exists", order_id);
+        return Err(TokenTracingError::InvalidAccountAddress.into());
+    }
+
+    msg!(
+        "place limit order {}: {:?} {} for at least {}",
+        order_id,
+        side,
+        amount,
+        min_out
+    );
+    create_pda_account(
+        program_id,
+        owner,
+        order,
+        system_program,
+        LimitOrder::LEN,
+        &[
+            b"order",
+            vault.key.as_ref(),
+            owner.key.as_ref(),
+            &order_id.to_le_bytes(),
+            &[bump],
+        ],
+    )?;
+    let escrow_lamports = match side {
+        OrderSide::SolToToken => checked_add(amount, LIMIT_ORDER_CRANK_REWARD)?,
+        OrderSide::TokenToSol => LIMIT_ORDER_CRANK_REWARD,
+    };
+    invoke(
+        &system_instruction::transfer(owner.key, order.key, escrow_lamports),
+        &[owner.clone(), order.clone(), system_program.clone()],
+    )?;
+    if side == OrderSide::TokenToSol {
+        assert_token_account(owner_token_account, mint.key, Some(owner.key))?;
+        // the escrow address is known in advance, so anyone may have created it
+        invoke(
+            &create_associated_token_account_idempotent(
+                owner.key,
+                order.key,
+                mint.key,
+                token_program.key,
+            ),
+            &[
+                owner.clone(),
+                order_token_account.clone(),
+                order.clone(),
+                mint.clone(),
+                system_program.clone(),
+                token_program.clone(),
+                associated_token_program.clone(),
+            ],
+        )?;
+        invoke(
+            &spl_token::instruction::transfer(
+                token_program.key,
+                owner_token_account.key,
+                order_token_account.key,
+                owner.key,
+                &[],
+                amount,
+            )?,
+            &[
+                token_program.clone(),
+                owner_token_account.clone(),
+                order_token_account.clone(),
+                owner.clone(),
+            ],
+        )?;
+    }
+
+    store_account(
+        &LimitOrder {
+            vault: *vault.key,
+            owner: *owner.key,
+            order_id,
+            side,
+            amount,
+            min_out,
+            crank_reward: LIMIT_ORDER_CRANK_REWARD,
+            bump,
+        },
+        order,
+    )?;
+    TokenTracingEvent::LimitOrderPlaced {
+        vault: *vault.key,
+        order: *order.key,
+        owner: *owner.key,
+        side,
+        amount,
+        min_out,
+    }
+    .emit();
+    Ok(())
+}
@@ -0,0 +1,289 @@
+//! Booth-level rules a swap must satisfy before any funds move.
+
+use solana_program::{
+    account_info::AccountInfo, clock::Clock, entrypoint::ProgramResult, msg,
+    program_error::ProgramError, pubkey::Pubkey, sysvar::Sysvar,
+};
+
+use crate::errors::TokenTracingError;
+use crate::events::TokenTracingEvent;
+use crate::{find_access_entry_address, find_attestation_address, find_wallet_stats_address};
+use crate::math::checked_add;
+use crate::processor::utils::{assert_writable, create_pda_account, resize_account};
+use crate::state::{
+    load_account, store_account, AccessEntry, AccessMode, AccountState, Attestation,
+    ExchangeAccount, WalletStats,
+};
+
+/// Check `wallet` against the booth allow- or denylist.
+pub fn enforce_access(
+    program_id: &Pubkey,
+    exchange: &ExchangeAccount,
+    wallet: &AccountInfo,
+    access_entry: Option<&AccountInfo>,
+) -> ProgramResult {
+    if exchange.access_mode == AccessMode::Open {
+        return Ok(());
+    }
+    let access_entry = access_entry.ok_or_else(|| {
+        msg!("booth is not open, the access entry account is required");
+        TokenTracingError::WalletNotAllowed
+    })?;
+    let (access_entry_pda, _) = find_access_entry_address(program_id, &exchange.vault, wallet.key);
+    if access_entry_pda != *access_entry.key {
+        msg!("Invalid access entry account");
+        return Err(TokenTracingError::InvalidAccountAddress.into());
+    }
+
+    let listed = access_entry.owner == program_id;
+    if listed {
+        let entry = load_account::<AccessEntry>(program_id, access_entry)?;
+        if entry.vault != exchange.vault || entry.wallet != *wallet.key {
+            return Err(TokenTracingError::InvalidAccountAddress.into());
+        }
+    }
+    let allowed = match exchange.access_mode {
+        AccessMode::Open => true,
+        AccessMode::Allowlist => listed,
+        AccessMode::Denylist => !listed,
+    };
+    if !allowed {
+        msg!("wallet {} is not allowed to use this booth", wallet.key);
+        return Err(TokenTracingError::WalletNotAllowed.into());
+    }
+    Ok(())
+}
+
+/// Require an unexpired attestation for `wallet` from the booth attestor.
+pub fn enforce_attestation(
+    program_id: &Pubkey,
+    exchange: &ExchangeAccount,
+    wallet: &AccountInfo,
+    attestation: Option<&AccountInfo>,
+) -> ProgramResult {
+    let attestor = match exchange.attestor() {
+        Some(attestor) => attestor,
+        None => return Ok(()),
+    };
+    let attestation = attestation.ok_or_else(|| {
+        msg!("booth requires an attestation from {}", attestor);
+        TokenTracingError::AttestationInvalid
+    })?;
+    let (attestation_pda, _) = find_attestation_address(program_id, &attestor, wallet.key);
+    if attestation_pda != *attestation.key || attestation.owner != program_id {
+        msg!("wallet {} has no attestation from {}", wallet.key, attestor);
+        return Err(TokenTracingError::AttestationInvalid.into());
+    }
+    let attestation = load_account::<Attestation>(program_id, attestation)?;
+    if attestation.attestor != attestor || attestation.subject != *wallet.key {
+        msg!("attestation does not match the wallet and attestor");
+        return Err(TokenTracingError::AttestationInvalid.into());
+    }
+    if !attestation.is_valid_at(Clock::get()?.unix_timestamp) {
+        msg!("attestation expired at {}", attestation.expires_at);
+        return Err(TokenTracingError::AttestationInvalid.into());
+    }
+    Ok(())
+}
+
+/// Fee in basis points a swap by `wallet` pays, with the booth fee tier
+/// that set it. Without its wallet stats PDA a wallet pays the booth fee.
+pub fn wallet_fee_tier(
+    program_id: &Pubkey,
+    exchange: &ExchangeAccount,
+    wallet: &AccountInfo,
+    wallet_stats: Option<&AccountInfo>,
+) -> Result<(u16, Option<u8>), ProgramError> {
+    let wallet_stats = match wallet_stats {
+        Some(wallet_stats) if exchange.fee_tier_count > 0 && wallet_stats.owner == program_id => {
+            wallet_stats
+        }
+        _ => return Ok((exchange.fee_bps, None)),
+    };
+    let (wallet_stats_pda, _) = find_wallet_stats_address(program_id, &exchange.vault, wallet.key);
+    if wallet_stats_pda != *wallet_stats.key {
+        msg!("Invalid wallet stats account");
+        return Err(TokenTracingError::InvalidAccountAddress.into());
+    }
+    let stats = WalletStats::unpack_any_version(&wallet_stats.data.borrow())?;
+    Ok(exchange.fee_tier(stats.total_volume))
+}
+
+/// Charge `value` lamports of swap volume against `wallet`'s limit for the
+/// rolling window and add it to the wallet's total volume, creating its
+/// stats PDA with rent from `payer` on first use. Volume only counts toward
+/// fee tiers when the stats PDA is passed.
+pub fn enforce_wallet_limit<'a>(
+    program_id: &Pubkey,
+    exchange: &ExchangeAccount,
+    wallet: &AccountInfo<'a>,
+    wallet_stats: Option<&AccountInfo<'a>>,
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    value: u64,
+) -> ProgramResult {
+    let wallet_stats = match wallet_stats {
+        _ if exchange.wallet_limit == 0 && exchange.fee_tier_count == 0 => return Ok(()),
+        Some(wallet_stats) => wallet_stats,
+        None if exchange.wallet_limit == 0 => return Ok(()),
+        None => {
+            msg!("booth limits wallets, the wallet stats account is required");
+            return Err(TokenTracingError::InvalidAccountAddress.into());
+        }
+    };
+    let mut stats =
+        open_wallet_stats(program_id, exchange, wallet, wallet_stats, payer, system_program)?;
+
+    if exchange.wallet_limit > 0 {
+        let now = Clock::get()?.unix_timestamp;
+        let rolling = stats.rolling_volume(now, exchange.wallet_limit_window)?;
+        let volume = checked_add(rolling, value)?;
+        if volume > exchange.wallet_limit {
+            msg!(
+                "wallet {} would move {} of {} lamports this window",
+                wallet.key,
+                volume,
+                exchange.wallet_limit
+            );
+            return Err(TokenTracingError::LimitExceeded.into());
+        }
+    }
+    stats.window_volume = checked_add(stats.window_volume, value)?;
+    stats.total_volume = checked_add(stats.total_volume, value)?;
+    store_account(&stats, wallet_stats)
+}
+
+/// Create `wallet`'s stats PDA, paid by `wallet`, for an order that swaps
+/// later without its signature, if the booth tracks wallet volume.
+pub fn prepare_wallet_stats<'a>(
+    program_id: &Pubkey,
+    exchange: &ExchangeAccount,
+    wallet: &AccountInfo<'a>,
+    wallet_stats: Option<&AccountInfo<'a>>,
+    system_program: &AccountInfo<'a>,
+) -> ProgramResult {
+    match wallet_stats {
+        Some(wallet_stats) if exchange.wallet_limit > 0 || exchange.fee_tier_count > 0 => {
+            let stats = open_wallet_stats(
+                program_id,
+                exchange,
+                wallet,
+                wallet_stats,
+                wallet,
+                system_program,
+            )?;
+            store_account(&stats, wallet_stats)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Load `wallet`'s stats PDA, creating it or growing it to the current
+/// version with rent from `payer`.
+fn open_wallet_stats<'a>(
+    program_id: &Pubkey,
+    exchange: &ExchangeAccount,
+    wallet: &AccountInfo<'a>,
+    wallet_stats: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+) -> Result<WalletStats, ProgramError> {
+    let (wallet_stats_pda, bump_seed) =
+        find_wallet_stats_address(program_id, &exchange.vault, wallet.key);
+    if wallet_stats_pda != *wallet_stats.key {
+        msg!("Invalid wallet stats account");
+        return Err(TokenTracingError::InvalidAccountAddress.into());
+    }
+    assert_writable(wallet_stats)?;
+
+    if wallet_stats.owner == program_id {
+        let stats = WalletStats::unpack_any_version(&wallet_stats.data.borrow())?;
+        if wallet_stats.data_len() < WalletStats::LEN {
+            resize_account(payer, wallet_stats, system_program, WalletStats::LEN)?;
+        }
+        return Ok(stats);
+    }
+    create_pda_account(
+        program_id,
+        payer,
+        wallet_stats,
+        system_program,
+        WalletStats::LEN,
+        &[b"wallet", exchange.vault.as_ref(), wallet.key.as_ref(), &[bump_seed]],
+    )?;
+    Ok(WalletStats {
+        vault: exchange.vault,
+        wallet: *wallet.key,
+        ..WalletStats::default()
+    })
+}
+
+/// Whether `sol_out` lamports and `token_out` tokens more fit under the
+/// booth's outflow caps for the current window.
+pub fn outflow_fits(
+    exchange: &mut ExchangeAccount,
+    sol_out: u64,
+    token_out: u64,
+) -> Result<bool, ProgramError> {
+    if exchange.max_sol_outflow == 0 && exchange.max_token_outflow == 0 {
+        return Ok(true);
+    }
+    let now = Clock::get()?.unix_timestamp;
+    if now.saturating_sub(exchange.outflow_window_start) >= exchange.outflow_window {
+        exchange.outflow_window_start = now;
+        exchange.sol_outflow = 0;
+        exchange.token_outflow = 0;
+    }
+    let sol_outflow = checked_add(exchange.sol_outflow, sol_out)?;
+    let token_outflow = checked_add(exchange.token_outflow, token_out)?;
+    Ok(!((exchange.max_sol_outflow > 0 && sol_outflow > exchange.max_sol_outflow)
+        || (exchange.max_token_outflow > 0 && token_outflow > exchange.max_token_outflow)))
+}
+
+/// Count `sol_out` lamports and `token_out` tokens a swap paid out against
+/// the booth circuit breaker, once its transfers went through.
+///
+/// A swap that would push outflow for the current window past a cap fails,
+/// rolling back its transfers. One that brings outflow up to a cap pauses
+/// the booth, which the caller commits by storing `exchange`.
+pub fn charge_outflow(
+    exchange: &mut ExchangeAccount,
+    sol_out: u64,
+    token_out: u64,
+) -> ProgramResult {
+    if !outflow_fits(exchange, sol_out, token_out)? {
+        msg!(
+            "paying {} lamports / {} tokens would exceed the booth outflow cap",
+            sol_out,
+            token_out
+        );
+        return Err(TokenTracingError::OutflowCapExceeded.into());
+    }
+    if exchange.max_sol_outflow == 0 && exchange.max_token_outflow == 0 {
+        return Ok(());
+    }
+    exchange.sol_outflow = checked_add(exchange.sol_outflow, sol_out)?;
+    exchange.token_outflow = checked_add(exchange.token_outflow, token_out)?;
+    if (exchange.max_sol_outflow > 0 && exchange.sol_outflow >= exchange.max_sol_outflow)
+        || (exchange.max_token_outflow > 0 && exchange.token_outflow >= exchange.max_token_outflow)
+    {
+        trip_circuit_breaker(exchange);
+    }
+    Ok(())
+}
+
+/// Pause the booth and log a `CircuitBreakerTripped` event.
+fn trip_circuit_breaker(exchange: &mut ExchangeAccount) {
+    msg!(
+        "circuit breaker tripped at {} lamports / {} tokens, pausing booth",
+        exchange.sol_outflow,
+        exchange.token_outflow
+    );
+    exchange.paused = true;
+    TokenTracingEvent::CircuitBreakerTripped {
+        vault: exchange.vault,
+        sol_outflow: exchange.sol_outflow,
+        token_outflow: exchange.token_outflow,
+    }
+    .emit();
+}
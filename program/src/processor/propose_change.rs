@@ -0,0 +1,68 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+
+use crate::errors::TokenTracingError;
+use crate::events::TokenTracingEvent;
+use crate::math::checked_add;
+use crate::processor::utils::assert_admin;
+use crate::state::{
+    load_exchange_account, store_account, CurveType, ExchangeAccount, PendingChange, MAX_FEE_BPS,
+};
+
+pub fn process(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    rate: u64,
+    fee_bps: u16,
+    curve: CurveType,
+    timelock_slots: u64,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let admin = next_account_info(accounts_iter)?;
+    let vault = next_account_info(accounts_iter)?;
+
+    if rate == 0 {
+        return Err(TokenTracingError::InvalidRate.into());
+    }
+    if fee_bps > MAX_FEE_BPS {
+        return Err(TokenTracingError::InvalidFee.into());
+    }
+    let exchange = load_exchange_account(program_id, vault)?;
+    assert_admin(program_id, &exchange, admin, accounts_iter.as_slice())?;
+
+    let change = PendingChange {
+        queued: true,
+        rate,
+        fee_bps,
+        curve,
+        timelock_slots,
+        eta_slot: 0,
+    };
+    queue_change(vault, exchange, change)
+}
+
+/// Store `change` as the booth's pending change, executable once the current
+/// timelock delay has passed.
+pub fn queue_change(
+    vault: &AccountInfo,
+    mut exchange: ExchangeAccount,
+    mut change: PendingChange,
+) -> ProgramResult {
+    // the current delay applies, so shortening it also waits out the old one
+    change.eta_slot = checked_add(Clock::get()?.slot, exchange.timelock_delay())?;
+    msg!("propose {:?}", change);
+    exchange.pending = change;
+    store_account(&exchange, vault)?;
+    TokenTracingEvent::ChangeProposed {
+        vault: *vault.key,
+        change,
+    }
+    .emit();
+    Ok(())
+}
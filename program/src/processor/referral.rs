@@ -0,0 +1,82 @@
+//! Share of the booth fee paid to whoever referred a swap.
+
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::errors::TokenTracingError;
+use crate::find_referrer_stats_address;
+use crate::math::checked_add;
+use crate::processor::utils::{assert_writable, create_pda_account, Referral};
+use crate::state::{load_account, store_account, AccountState, ExchangeAccount, ReferrerStats};
+
+/// The referral a swap by `payer` must pay, if it names a referrer and the
+/// booth shares its fee. Referrer accounts are ignored otherwise.
+pub fn active_referral<'r, 'a, 'b>(
+    exchange: &ExchangeAccount,
+    payer: &AccountInfo,
+    referral: Option<&'r Referral<'a, 'b>>,
+) -> Result<Option<&'r Referral<'a, 'b>>, ProgramError> {
+    let referral = match referral {
+        Some(referral) if exchange.referral_share_bps > 0 => referral,
+        _ => return Ok(None),
+    };
+    if referral.referrer.key == payer.key {
+        msg!("a swap cannot refer itself");
+        return Err(TokenTracingError::InvalidAccountAddress.into());
+    }
+    assert_writable(referral.referrer)?;
+    Ok(Some(referral))
+}
+
+/// Add a paid referral to the referrer's stats PDA, which `payer` creates on first use.
+pub fn record_referral<'a>(
+    program_id: &Pubkey,
+    exchange: &ExchangeAccount,
+    referral: &Referral<'_, 'a>,
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    lamports: u64,
+    tokens: u64,
+) -> ProgramResult {
+    let Referral {
+        referrer,
+        referrer_stats,
+        ..
+    } = referral;
+    let (referrer_stats_pda, bump_seed) =
+        find_referrer_stats_address(program_id, &exchange.vault, referrer.key);
+    if referrer_stats_pda != *referrer_stats.key {
+        msg!("Invalid referrer stats account");
+        return Err(TokenTracingError::InvalidAccountAddress.into());
+    }
+    assert_writable(referrer_stats)?;
+
+    let mut stats = if referrer_stats.owner == program_id {
+        load_account::<ReferrerStats>(program_id, referrer_stats)?
+    } else {
+        create_pda_account(
+            program_id,
+            payer,
+            referrer_stats,
+            system_program,
+            ReferrerStats::LEN,
+            &[
+                b"referrer",
+                exchange.vault.as_ref(),
+                referrer.key.as_ref(),
+                &[bump_seed],
+            ],
+        )?;
+        ReferrerStats {
+            vault: exchange.vault,
+            referrer: *referrer.key,
+            ..ReferrerStats::default()
+        }
+    };
+    stats.swaps = checked_add(stats.swaps, 1)?;
+    stats.lamports_earned = checked_add(stats.lamports_earned, lamports)?;
+    stats.tokens_earned = checked_add(stats.tokens_earned, tokens)?;
+    store_account(&stats, referrer_stats)
+}
@@ -0,0 +1,34 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::errors::TokenTracingError;
+use crate::find_access_entry_address;
+use crate::processor::utils::{assert_admin, assert_writable, close_account};
+use crate::state::{load_account, load_exchange_account, AccessEntry};
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], wallet: Pubkey) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let admin = next_account_info(accounts_iter)?;
+    let vault = next_account_info(accounts_iter)?;
+    let access_entry = next_account_info(accounts_iter)?;
+    let receiver = next_account_info(accounts_iter)?;
+
+    let exchange = load_exchange_account(program_id, vault)?;
+    assert_admin(program_id, &exchange, admin, accounts_iter.as_slice())?;
+    assert_writable(access_entry)?;
+    assert_writable(receiver)?;
+
+    let (access_entry_pda, _) = find_access_entry_address(program_id, vault.key, &wallet);
+    if access_entry_pda != *access_entry.key {
+        msg!("Invalid access entry account");
+        return Err(TokenTracingError::InvalidAccountAddress.into());
+    }
+    load_account::<AccessEntry>(program_id, access_entry)?;
+
+    msg!("remove {} from the access list", wallet);
+    close_account(access_entry, receiver)
+}
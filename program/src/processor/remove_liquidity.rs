@@ -0,0 +1,83 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke,
+    pubkey::Pubkey,
+};
+
+use crate::errors::TokenTracingError;
+use crate::math::checked_mul_div;
+use crate::processor::exchange_SOL_to_token::pay_tokens;
+use crate::processor::utils::{
+    assert_signer, assert_token_account, assert_token_program, assert_vault_token_account,
+    assert_writable, available_lamports, load_vault, lp_supply, transfer_lamports,
+};
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], lp_amount: u64) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let owner = next_account_info(accounts_iter)?;
+    let owner_lp_account = next_account_info(accounts_iter)?;
+    let owner_token_account = next_account_info(accounts_iter)?;
+    let mint = next_account_info(accounts_iter)?;
+    let vault = next_account_info(accounts_iter)?;
+    let vault_token_account = next_account_info(accounts_iter)?;
+    let lp_mint = next_account_info(accounts_iter)?;
+    let token_program_id = next_account_info(accounts_iter)?;
+
+    assert_signer(owner)?;
+    assert_writable(owner)?;
+    assert_token_program(token_program_id)?;
+    if lp_amount == 0 {
+        msg!("nothing to remove");
+        return Err(TokenTracingError::InvalidInstructionData.into());
+    }
+    let exchange = load_vault(program_id, vault, mint.key)?;
+    let vault_tokens = assert_vault_token_account(vault_token_account, vault.key, mint.key)?.amount;
+    let supply = lp_supply(program_id, vault, lp_mint)?;
+    let held = assert_token_account(owner_lp_account, lp_mint.key, Some(owner.key))?.amount;
+    if lp_amount > held {
+        msg!("cannot remove {} of {} LP tokens held", lp_amount, held);
+        return Err(TokenTracingError::InsufficientFunds.into());
+    }
+
+    // the LP tokens are this share of everything the booth holds, rounded
+    // down so the holders left behind never lose out
+    let lamports = checked_mul_div(available_lamports(vault)?, lp_amount, supply)?;
+    let tokens = checked_mul_div(vault_tokens, lp_amount, supply)?;
+
+    msg!("burn {} LP tokens", lp_amount);
+    invoke(
+        &spl_token::instruction::burn(
+            token_program_id.key,
+            owner_lp_account.key,
+            lp_mint.key,
+            owner.key,
+            &[],
+            lp_amount,
+        )?,
+        &[
+            token_program_id.clone(),
+            owner_lp_account.clone(),
+            lp_mint.clone(),
+            owner.clone(),
+        ],
+    )?;
+
+    if lamports > 0 {
+        msg!("remove {} lamports from vault", lamports);
+        transfer_lamports(vault, owner, lamports)?;
+    }
+    if tokens > 0 {
+        msg!("remove {} tokens from vault", tokens);
+        pay_tokens(
+            &exchange,
+            token_program_id,
+            vault_token_account,
+            owner_token_account,
+            vault,
+            tokens,
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,31 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::errors::TokenTracingError;
+use crate::find_attestation_address;
+use crate::processor::utils::{assert_signer, assert_writable, close_account};
+use crate::state::{load_account, Attestation};
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], wallet: Pubkey) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let attestor = next_account_info(accounts_iter)?;
+    let attestation = next_account_info(accounts_iter)?;
+    let receiver = next_account_info(accounts_iter)?;
+
+    assert_signer(attestor)?;
+    assert_writable(attestation)?;
+    assert_writable(receiver)?;
+    let (attestation_pda, _) = find_attestation_address(program_id, attestor.key, &wallet);
+    if attestation_pda != *attestation.key {
+        msg!("Invalid attestation account");
+        return Err(TokenTracingError::InvalidAccountAddress.into());
+    }
+    load_account::<Attestation>(program_id, attestation)?;
+
+    msg!("revoke attestation for {}", wallet);
+    close_account(attestation, receiver)
+}
@@ -0,0 +1,22 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::processor::utils::assert_admin;
+use crate::state::{load_exchange_account, store_account, AccessMode};
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], mode: AccessMode) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let admin = next_account_info(accounts_iter)?;
+    let vault = next_account_info(accounts_iter)?;
+
+    let mut exchange = load_exchange_account(program_id, vault)?;
+    assert_admin(program_id, &exchange, admin, accounts_iter.as_slice())?;
+
+    msg!("set access mode: {:?}", mode);
+    exchange.access_mode = mode;
+    store_account(&exchange, vault)
+}
@@ -0,0 +1,40 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::errors::TokenTracingError;
+use crate::processor::utils::assert_admin;
+use crate::state::{load_exchange_account, store_account};
+
+pub fn process(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    max_sol_outflow: u64,
+    max_token_outflow: u64,
+    window_seconds: i64,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let admin = next_account_info(accounts_iter)?;
+    let vault = next_account_info(accounts_iter)?;
+
+    if (max_sol_outflow > 0 || max_token_outflow > 0) && window_seconds <= 0 {
+        msg!("an outflow cap needs a positive window");
+        return Err(TokenTracingError::InvalidInstructionData.into());
+    }
+    let mut exchange = load_exchange_account(program_id, vault)?;
+    assert_admin(program_id, &exchange, admin, accounts_iter.as_slice())?;
+
+    msg!(
+        "set circuit breaker {} lamports / {} tokens per {}s",
+        max_sol_outflow,
+        max_token_outflow,
+        window_seconds
+    );
+    exchange.max_sol_outflow = max_sol_outflow;
+    exchange.max_token_outflow = max_token_outflow;
+    exchange.outflow_window = window_seconds;
+    store_account(&exchange, vault)
+}
@@ -0,0 +1,31 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::processor::utils::assert_admin;
+use crate::state::{
+    load_exchange_account, store_account, validate_fee_tiers, FeeTier, MAX_FEE_TIERS,
+};
+
+pub fn process(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    tiers: Vec<FeeTier>,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let admin = next_account_info(accounts_iter)?;
+    let vault = next_account_info(accounts_iter)?;
+
+    validate_fee_tiers(&tiers)?;
+    let mut exchange = load_exchange_account(program_id, vault)?;
+    assert_admin(program_id, &exchange, admin, accounts_iter.as_slice())?;
+
+    msg!("set {} fee tiers", tiers.len());
+    exchange.fee_tiers = [FeeTier::default(); MAX_FEE_TIERS];
+    exchange.fee_tiers[..tiers.len()].copy_from_slice(&tiers);
+    exchange.fee_tier_count = tiers.len() as u8;
+    store_account(&exchange, vault)
+}
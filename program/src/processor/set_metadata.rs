@@ -0,0 +1,64 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::errors::TokenTracingError;
+use crate::find_metadata_address;
+use crate::processor::utils::{
+    assert_admin, assert_signer, assert_system_program, assert_writable, create_pda_account,
+};
+use crate::state::{load_exchange_account, store_account, AccountState, BoothMetadata};
+
+pub fn process(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    name: String,
+    symbol: String,
+    description: String,
+    uri: String,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let payer = next_account_info(accounts_iter)?;
+    let admin = next_account_info(accounts_iter)?;
+    let vault = next_account_info(accounts_iter)?;
+    let metadata = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    assert_signer(payer)?;
+    assert_writable(metadata)?;
+    assert_system_program(system_program)?;
+    let exchange = load_exchange_account(program_id, vault)?;
+    assert_admin(program_id, &exchange, admin, accounts_iter.as_slice())?;
+
+    let booth_metadata = BoothMetadata {
+        vault: *vault.key,
+        name,
+        symbol,
+        description,
+        uri,
+    };
+    booth_metadata.validate()?;
+
+    let (metadata_pda, bump_seed) = find_metadata_address(program_id, vault.key);
+    if metadata_pda != *metadata.key {
+        msg!("Invalid metadata account");
+        return Err(TokenTracingError::InvalidAccountAddress.into());
+    }
+    if metadata.owner != program_id {
+        msg!("create metadata {}", metadata.key);
+        create_pda_account(
+            program_id,
+            payer,
+            metadata,
+            system_program,
+            BoothMetadata::LEN,
+            &[b"metadata", vault.key.as_ref(), &[bump_seed]],
+        )?;
+    }
+
+    msg!("booth name: {}", booth_metadata.name);
+    store_account(&booth_metadata, metadata)
+}
@@ -0,0 +1,77 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::errors::TokenTracingError;
+use crate::find_multisig_address;
+use crate::processor::utils::{
+    assert_admin, assert_signer, assert_system_program, assert_writable, create_pda_account,
+};
+use crate::state::{
+    load_exchange_account, store_account, AccountState, Multisig, MAX_MULTISIG_SIGNERS,
+};
+
+pub fn process(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    threshold: u8,
+    signers: Vec<Pubkey>,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let payer = next_account_info(accounts_iter)?;
+    let admin = next_account_info(accounts_iter)?;
+    let vault = next_account_info(accounts_iter)?;
+    let multisig = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    if signers.is_empty()
+        || signers.len() > MAX_MULTISIG_SIGNERS
+        || threshold == 0
+        || threshold as usize > signers.len()
+    {
+        msg!("need 1 <= threshold <= signers <= {}", MAX_MULTISIG_SIGNERS);
+        return Err(TokenTracingError::InvalidMultisig.into());
+    }
+    if (1..signers.len()).any(|i| signers[..i].contains(&signers[i])) {
+        msg!("multisig signers must be unique");
+        return Err(TokenTracingError::InvalidMultisig.into());
+    }
+
+    assert_signer(payer)?;
+    assert_writable(multisig)?;
+    assert_system_program(system_program)?;
+    let mut exchange = load_exchange_account(program_id, vault)?;
+    assert_admin(program_id, &exchange, admin, accounts_iter.as_slice())?;
+
+    let (multisig_pda, bump_seed) = find_multisig_address(program_id, vault.key);
+    if multisig_pda != *multisig.key {
+        msg!("Invalid multisig account");
+        return Err(TokenTracingError::InvalidAccountAddress.into());
+    }
+    if multisig.owner != program_id {
+        create_pda_account(
+            program_id,
+            payer,
+            multisig,
+            system_program,
+            Multisig::LEN,
+            &[b"multisig", vault.key.as_ref(), &[bump_seed]],
+        )?;
+    }
+
+    msg!("set multisig {} of {}", threshold, signers.len());
+    let mut state = Multisig {
+        vault: *vault.key,
+        threshold,
+        signer_count: signers.len() as u8,
+        ..Multisig::default()
+    };
+    state.signers[..signers.len()].copy_from_slice(&signers);
+    store_account(&state, multisig)?;
+
+    exchange.admin = *multisig.key;
+    store_account(&exchange, vault)
+}
@@ -0,0 +1,34 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::errors::TokenTracingError;
+use crate::processor::propose_change::queue_change;
+use crate::processor::utils::assert_admin;
+use crate::state::{load_exchange_account, PendingChange};
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], rate: u64) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let admin = next_account_info(accounts_iter)?;
+    let vault = next_account_info(accounts_iter)?;
+
+    if rate == 0 {
+        return Err(TokenTracingError::InvalidRate.into());
+    }
+    let exchange = load_exchange_account(program_id, vault)?;
+    assert_admin(program_id, &exchange, admin, accounts_iter.as_slice())?;
+
+    msg!("queue rate {} -> {}", exchange.rate, rate);
+    let change = PendingChange {
+        queued: true,
+        rate,
+        fee_bps: exchange.fee_bps,
+        curve: exchange.curve,
+        timelock_slots: exchange.timelock_slots,
+        eta_slot: 0,
+    };
+    queue_change(vault, exchange, change)
+}
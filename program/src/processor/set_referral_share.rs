@@ -0,0 +1,31 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::errors::TokenTracingError;
+use crate::processor::utils::assert_admin;
+use crate::state::{load_exchange_account, store_account, MAX_FEE_BPS};
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], share_bps: u16) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let admin = next_account_info(accounts_iter)?;
+    let vault = next_account_info(accounts_iter)?;
+
+    if share_bps > MAX_FEE_BPS {
+        msg!("referral share {} exceeds {} bps", share_bps, MAX_FEE_BPS);
+        return Err(TokenTracingError::InvalidFee.into());
+    }
+    let mut exchange = load_exchange_account(program_id, vault)?;
+    assert_admin(program_id, &exchange, admin, accounts_iter.as_slice())?;
+
+    msg!(
+        "set referral share {} -> {} bps",
+        exchange.referral_share_bps,
+        share_bps
+    );
+    exchange.referral_share_bps = share_bps;
+    store_account(&exchange, vault)
+}
@@ -0,0 +1,33 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::errors::TokenTracingError;
+use crate::processor::utils::assert_admin;
+use crate::state::{load_exchange_account, store_account};
+
+pub fn process(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    limit: u64,
+    window_seconds: i64,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let admin = next_account_info(accounts_iter)?;
+    let vault = next_account_info(accounts_iter)?;
+
+    if limit > 0 && window_seconds <= 0 {
+        msg!("a wallet limit needs a positive window");
+        return Err(TokenTracingError::InvalidInstructionData.into());
+    }
+    let mut exchange = load_exchange_account(program_id, vault)?;
+    assert_admin(program_id, &exchange, admin, accounts_iter.as_slice())?;
+
+    msg!("set wallet limit {} lamports per {}s", limit, window_seconds);
+    exchange.wallet_limit = limit;
+    exchange.wallet_limit_window = window_seconds;
+    store_account(&exchange, vault)
+}
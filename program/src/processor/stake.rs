@@ -0,0 +1,92 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+
+use crate::errors::TokenTracingError;
+use crate::find_stake_address;
+use crate::math::checked_add;
+use crate::processor::utils::{
+    assert_signer, assert_system_program, assert_token_program, assert_vault_token_account,
+    assert_writable, create_pda_account, load_stake,
+};
+use crate::state::{load_account, store_account, AccountState, RewardPool, StakeAccount};
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let owner = next_account_info(accounts_iter)?;
+    let owner_token_account = next_account_info(accounts_iter)?;
+    let rewards = next_account_info(accounts_iter)?;
+    let stake = next_account_info(accounts_iter)?;
+    let stake_token_account = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    assert_signer(owner)?;
+    assert_writable(rewards)?;
+    assert_writable(stake)?;
+    assert_token_program(token_program)?;
+    assert_system_program(system_program)?;
+    if amount == 0 {
+        msg!("stake must be positive");
+        return Err(TokenTracingError::InvalidInstructionData.into());
+    }
+
+    let (stake_pda, bump) = find_stake_address(program_id, rewards.key, owner.key);
+    if stake_pda != *stake.key {
+        msg!("Invalid stake account");
+        return Err(TokenTracingError::InvalidAccountAddress.into());
+    }
+    let (mut pool, mut position) = if stake.owner == program_id {
+        load_stake(program_id, rewards, stake, owner)?
+    } else {
+        let pool = load_account::<RewardPool>(program_id, rewards)?;
+        create_pda_account(
+            program_id,
+            owner,
+            stake,
+            system_program,
+            StakeAccount::LEN,
+            &[b"stake", rewards.key.as_ref(), owner.key.as_ref(), &[bump]],
+        )?;
+        let position = StakeAccount {
+            rewards: *rewards.key,
+            owner: *owner.key,
+            bump,
+            ..StakeAccount::default()
+        };
+        (pool, position)
+    };
+    assert_vault_token_account(stake_token_account, rewards.key, &pool.stake_mint)?;
+
+    pool.update(Clock::get()?.unix_timestamp)?;
+    position.settle(pool.acc_reward_per_share)?;
+    msg!("stake {}", amount);
+    invoke(
+        &spl_token::instruction::transfer(
+            token_program.key,
+            owner_token_account.key,
+            stake_token_account.key,
+            owner.key,
+            &[],
+            amount,
+        )?,
+        &[
+            token_program.clone(),
+            owner_token_account.clone(),
+            stake_token_account.clone(),
+            owner.clone(),
+        ],
+    )?;
+    position.amount = checked_add(position.amount, amount)?;
+    position.reset_debt(pool.acc_reward_per_share)?;
+    pool.total_staked = checked_add(pool.total_staked, amount)?;
+
+    store_account(&position, stake)?;
+    store_account(&pool, rewards)
+}
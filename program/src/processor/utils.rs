@@ -0,0 +1,488 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    instruction::Instruction,
+    msg,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction, system_program,
+    sysvar::Sysvar,
+};
+use spl_associated_token_account::get_associated_token_address;
+
+use crate::errors::TokenTracingError;
+use crate::find_lp_mint_address;
+use crate::math::{checked_add, checked_sub};
+use crate::state::{
+    load_account, load_exchange_account, ExchangeAccount, LimitOrder, Multisig, RewardPool,
+    StakeAccount,
+};
+
+/// Require `admin` to be the booth admin and to have signed.
+///
+/// When the admin is the booth [`Multisig`], `signers` must instead hold at
+/// least its threshold of listed keys as signers, as in SPL Token.
+pub fn assert_admin(
+    program_id: &Pubkey,
+    exchange: &ExchangeAccount,
+    admin: &AccountInfo,
+    signers: &[AccountInfo],
+) -> ProgramResult {
+    if exchange.admin != *admin.key {
+        msg!("{} is not the booth admin", admin.key);
+        return Err(TokenTracingError::InvalidOwner.into());
+    }
+    if admin.owner != program_id {
+        return assert_signer(admin);
+    }
+
+    let multisig = load_account::<Multisig>(program_id, admin)?;
+    if multisig.vault != exchange.vault {
+        msg!("{} is not this booth's multisig", admin.key);
+        return Err(TokenTracingError::InvalidOwner.into());
+    }
+    let signed = multisig
+        .signers()
+        .iter()
+        .filter(|key| signers.iter().any(|signer| signer.key == *key && signer.is_signer))
+        .count();
+    if signed < multisig.threshold as usize {
+        msg!("{} of {} multisig signers signed", signed, multisig.threshold);
+        return Err(TokenTracingError::NotEnoughSigners.into());
+    }
+    Ok(())
+}
+
+pub fn assert_signer(account: &AccountInfo) -> ProgramResult {
+    if !account.is_signer {
+        msg!("{} must sign", account.key);
+        return Err(TokenTracingError::AccountIsNotSigner.into());
+    }
+    Ok(())
+}
+
+pub fn assert_writable(account: &AccountInfo) -> ProgramResult {
+    if !account.is_writable {
+        msg!("{} must be writable", account.key);
+        return Err(TokenTracingError::AccountIsNotWritable.into());
+    }
+    Ok(())
+}
+
+pub fn assert_token_program(account: &AccountInfo) -> ProgramResult {
+    if *account.key != spl_token::id() {
+        msg!("{} is not the SPL Token program", account.key);
+        return Err(TokenTracingError::InvalidAccountAddress.into());
+    }
+    Ok(())
+}
+
+pub fn assert_system_program(account: &AccountInfo) -> ProgramResult {
+    if *account.key != system_program::id() {
+        msg!("{} is not the system program", account.key);
+        return Err(TokenTracingError::InvalidAccountAddress.into());
+    }
+    Ok(())
+}
+
+/// Load the booth stored in `vault` and check that it trades `mint`.
+///
+/// Booth state is only ever written into the vault PDA it records, so a
+/// program-owned account whose state names itself is a genuine vault.
+pub fn load_vault(
+    program_id: &Pubkey,
+    vault: &AccountInfo,
+    mint: &Pubkey,
+) -> Result<ExchangeAccount, ProgramError> {
+    let exchange = load_exchange_account(program_id, vault)?;
+    if exchange.vault != *vault.key {
+        msg!("Invalid vault account");
+        return Err(TokenTracingError::InvalidVaultAccount.into());
+    }
+    if exchange.mint != *mint {
+        return Err(TokenTracingError::InvalidMint.into());
+    }
+    Ok(exchange)
+}
+
+/// Invoke `instruction` with the vault PDA of `exchange` as signer.
+pub fn invoke_signed_by_vault(
+    instruction: &Instruction,
+    account_infos: &[AccountInfo],
+    exchange: &ExchangeAccount,
+) -> ProgramResult {
+    let booth_id = exchange.booth_id.to_le_bytes();
+    let bump_seed = [exchange.vault_bump];
+    let mut seeds: Vec<&[u8]> = vec![b"vault", exchange.mint.as_ref()];
+    if exchange.booth_id != 0 {
+        seeds.push(&booth_id);
+    }
+    seeds.push(&bump_seed);
+    invoke_signed(instruction, account_infos, &[&seeds])
+}
+
+/// Invoke `instruction` with the PDA of limit `order` as signer.
+pub fn invoke_signed_by_order(
+    instruction: &Instruction,
+    account_infos: &[AccountInfo],
+    order: &LimitOrder,
+) -> ProgramResult {
+    invoke_signed(
+        instruction,
+        account_infos,
+        &[&[
+            b"order",
+            order.vault.as_ref(),
+            order.owner.as_ref(),
+            &order.order_id.to_le_bytes(),
+            &[order.bump],
+        ]],
+    )
+}
+
+/// Invoke `instruction` with the reward pool PDA as signer.
+pub fn invoke_signed_by_rewards(
+    instruction: &Instruction,
+    account_infos: &[AccountInfo],
+    rewards: &RewardPool,
+) -> ProgramResult {
+    invoke_signed(
+        instruction,
+        account_infos,
+        &[&[b"rewards", rewards.vault.as_ref(), &[rewards.bump]]],
+    )
+}
+
+/// Load the reward pool `rewards` and `owner`'s stake in it.
+pub fn load_stake(
+    program_id: &Pubkey,
+    rewards: &AccountInfo,
+    stake: &AccountInfo,
+    owner: &AccountInfo,
+) -> Result<(RewardPool, StakeAccount), ProgramError> {
+    let pool = load_account::<RewardPool>(program_id, rewards)?;
+    let position = load_account::<StakeAccount>(program_id, stake)?;
+    if position.rewards != *rewards.key || position.owner != *owner.key {
+        msg!("{} does not own stake {}", owner.key, stake.key);
+        return Err(TokenTracingError::InvalidOwner.into());
+    }
+    Ok((pool, position))
+}
+
+/// Move every token in the escrow of a `TokenToSol` limit order to
+/// `destination` and close the escrow, refunding its rent to `rent_receiver`.
+///
+/// The whole balance moves, not just the order amount, so tokens sent to the
+/// escrow by anyone else cannot keep it from closing.
+pub fn release_order_tokens<'a>(
+    order: &LimitOrder,
+    order_account: &AccountInfo<'a>,
+    escrow: &AccountInfo<'a>,
+    destination: &AccountInfo<'a>,
+    rent_receiver: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+) -> ProgramResult {
+    let balance = spl_token::state::Account::unpack(&escrow.data.borrow())?.amount;
+    invoke_signed_by_order(
+        &spl_token::instruction::transfer(
+            token_program.key,
+            escrow.key,
+            destination.key,
+            order_account.key,
+            &[],
+            balance,
+        )?,
+        &[
+            token_program.clone(),
+            escrow.clone(),
+            destination.clone(),
+            order_account.clone(),
+        ],
+        order,
+    )?;
+    invoke_signed_by_order(
+        &spl_token::instruction::close_account(
+            token_program.key,
+            escrow.key,
+            rent_receiver.key,
+            order_account.key,
+            &[],
+        )?,
+        &[
+            token_program.clone(),
+            escrow.clone(),
+            rent_receiver.clone(),
+            order_account.clone(),
+        ],
+        order,
+    )
+}
+
+/// Check that `account` is an SPL token account for `mint`, optionally owned by `owner`.
+pub fn assert_token_account(
+    account: &AccountInfo,
+    mint: &Pubkey,
+    owner: Option<&Pubkey>,
+) -> Result<spl_token::state::Account, ProgramError> {
+    if *account.owner != spl_token::id() {
+        msg!("{} is not an SPL token account", account.key);
+        return Err(TokenTracingError::InvalidSPLTokenAccount.into());
+    }
+    let token_account = spl_token::state::Account::unpack(&account.data.borrow())
+        .map_err(|_| TokenTracingError::InvalidSPLTokenAccount)?;
+    if token_account.mint != *mint {
+        msg!("{} does not hold mint {}", account.key, mint);
+        return Err(TokenTracingError::InvalidMint.into());
+    }
+    if let Some(owner) = owner {
+        if token_account.owner != *owner {
+            msg!("{} is not owned by {}", account.key, owner);
+            return Err(TokenTracingError::InvalidOwner.into());
+        }
+    }
+    Ok(token_account)
+}
+
+/// Check that `account` is the vault's associated token account for `mint`.
+pub fn assert_vault_token_account(
+    account: &AccountInfo,
+    vault: &Pubkey,
+    mint: &Pubkey,
+) -> Result<spl_token::state::Account, ProgramError> {
+    if *account.key != get_associated_token_address(vault, mint) {
+        msg!("{} is not the vault token account", account.key);
+        return Err(TokenTracingError::InvalidSPLTokenAccount.into());
+    }
+    assert_token_account(account, mint, Some(vault))
+}
+
+/// Lamports held by a program-owned account above its rent-exempt minimum.
+pub fn available_lamports(account: &AccountInfo) -> Result<u64, ProgramError> {
+    let rent_exempt = Rent::get()?.minimum_balance(account.data_len());
+    Ok(account.lamports().saturating_sub(rent_exempt))
+}
+
+/// Move lamports out of a program-owned account, keeping it rent exempt.
+pub fn transfer_lamports(from: &AccountInfo, to: &AccountInfo, amount: u64) -> ProgramResult {
+    if amount > available_lamports(from)? {
+        msg!("{} cannot pay {} lamports and stay rent exempt", from.key, amount);
+        return Err(TokenTracingError::InsufficientFunds.into());
+    }
+    let from_lamports = checked_sub(from.lamports(), amount)?;
+    let to_lamports = checked_add(to.lamports(), amount)?;
+    **from.try_borrow_mut_lamports()? = from_lamports;
+    **to.try_borrow_mut_lamports()? = to_lamports;
+    Ok(())
+}
+
+/// Check that `lp_mint` is the booth LP mint and return its supply, zero
+/// until the first deposit creates it.
+pub fn lp_supply(
+    program_id: &Pubkey,
+    vault: &AccountInfo,
+    lp_mint: &AccountInfo,
+) -> Result<u64, ProgramError> {
+    let (lp_mint_pda, _) = find_lp_mint_address(program_id, vault.key);
+    if lp_mint_pda != *lp_mint.key {
+        msg!("Invalid LP mint account");
+        return Err(TokenTracingError::InvalidAccountAddress.into());
+    }
+    if *lp_mint.owner != spl_token::id() {
+        return Ok(0);
+    }
+    Ok(spl_token::state::Mint::unpack(&lp_mint.data.borrow())?.supply)
+}
+
+/// Require that no LP tokens are outstanding, since LP holders own the
+/// booth reserves and take them out with `RemoveLiquidity`.
+pub fn assert_no_lp_supply(
+    program_id: &Pubkey,
+    vault: &AccountInfo,
+    lp_mint: &AccountInfo,
+) -> ProgramResult {
+    let supply = lp_supply(program_id, vault, lp_mint)?;
+    if supply > 0 {
+        msg!("{} LP tokens still claim the booth reserves", supply);
+        return Err(TokenTracingError::LiquidityOwedToLp.into());
+    }
+    Ok(())
+}
+
+/// Create a program-owned PDA of `space` bytes, funded by `payer`.
+///
+/// Works even if someone already sent lamports to the address.
+pub fn create_pda_account<'a>(
+    program_id: &Pubkey,
+    payer: &AccountInfo<'a>,
+    account: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    space: usize,
+    seeds: &[&[u8]],
+) -> ProgramResult {
+    create_pda_account_owned_by(program_id, payer, account, system_program, space, seeds)
+}
+
+/// [`create_pda_account`] for a PDA handed to another program, `owner`.
+pub fn create_pda_account_owned_by<'a>(
+    owner: &Pubkey,
+    payer: &AccountInfo<'a>,
+    account: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    space: usize,
+    seeds: &[&[u8]],
+) -> ProgramResult {
+    let required = Rent::get()?.minimum_balance(space);
+    if account.lamports() == 0 {
+        return invoke_signed(
+            &system_instruction::create_account(
+                payer.key,
+                account.key,
+                required,
+                space as u64,
+                owner,
+            ),
+            &[payer.clone(), account.clone(), system_program.clone()],
+            &[seeds],
+        );
+    }
+
+    let top_up = required.saturating_sub(account.lamports());
+    if top_up > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, account.key, top_up),
+            &[payer.clone(), account.clone(), system_program.clone()],
+        )?;
+    }
+    invoke_signed(
+        &system_instruction::allocate(account.key, space as u64),
+        &[account.clone(), system_program.clone()],
+        &[seeds],
+    )?;
+    invoke_signed(
+        &system_instruction::assign(account.key, owner),
+        &[account.clone(), system_program.clone()],
+        &[seeds],
+    )
+}
+
+/// Grow or shrink a program-owned account to `space` bytes, with `payer`
+/// topping up rent when it grows.
+pub fn resize_account<'a>(
+    payer: &AccountInfo<'a>,
+    account: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    space: usize,
+) -> ProgramResult {
+    let top_up = Rent::get()?.minimum_balance(space).saturating_sub(account.lamports());
+    if top_up > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, account.key, top_up),
+            &[payer.clone(), account.clone(), system_program.clone()],
+        )?;
+    }
+    account.realloc(space, true)
+}
+
+/// Close a program-owned account, sending its lamports to `destination`.
+pub fn close_account(account: &AccountInfo, destination: &AccountInfo) -> ProgramResult {
+    let lamports = checked_add(destination.lamports(), account.lamports())?;
+    **destination.try_borrow_mut_lamports()? = lamports;
+    **account.try_borrow_mut_lamports()? = 0;
+    account.realloc(0, false)?;
+    account.assign(&system_program::id());
+    Ok(())
+}
+
+/// The validated eight-account layout shared by both swap directions.
+pub struct SwapAccounts<'a, 'b> {
+    pub payer: &'a AccountInfo<'b>,
+    pub payer_token_account: &'a AccountInfo<'b>,
+    pub mint: &'a AccountInfo<'b>,
+    pub vault: &'a AccountInfo<'b>,
+    pub vault_token_account: &'a AccountInfo<'b>,
+    pub token_program: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    pub exchange: ExchangeAccount,
+    /// Token base units held by the vault token account.
+    pub vault_token_balance: u64,
+    /// Optional `[b"wallet", vault, payer]` PDA, required when the booth limits wallets.
+    pub wallet_stats: Option<&'a AccountInfo<'b>>,
+    /// Optional `[b"access", vault, payer]` PDA, required unless the booth is open.
+    pub access_entry: Option<&'a AccountInfo<'b>>,
+    /// Optional `[b"attestation", attestor, payer]` PDA, required when the booth names an attestor.
+    pub attestation: Option<&'a AccountInfo<'b>>,
+    /// Optional referrer paid a share of the booth fee.
+    pub referral: Option<Referral<'a, 'b>>,
+}
+
+/// Trailing swap accounts naming who referred the payer.
+pub struct Referral<'a, 'b> {
+    /// Receives the lamports earned on token to SOL swaps.
+    pub referrer: &'a AccountInfo<'b>,
+    /// Receives the tokens earned on SOL to token swaps, owned by `referrer`.
+    pub referrer_token_account: &'a AccountInfo<'b>,
+    /// `[b"referrer", vault, referrer]` PDA, created on first use.
+    pub referrer_stats: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> SwapAccounts<'a, 'b> {
+    pub fn parse(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        let program = next_account_info(accounts_iter)?;
+        let payer = next_account_info(accounts_iter)?;
+        let payer_token_account = next_account_info(accounts_iter)?;
+        let mint = next_account_info(accounts_iter)?;
+        let vault = next_account_info(accounts_iter)?;
+        let vault_token_account = next_account_info(accounts_iter)?;
+        let token_program = next_account_info(accounts_iter)?;
+        let system_program = next_account_info(accounts_iter)?;
+        // the program id stands in for an optional account that a later one follows
+        let mut optional = || accounts_iter.next().filter(|account| account.key != program_id);
+        let wallet_stats = optional();
+        let access_entry = optional();
+        let attestation = optional();
+        let referral = match (accounts_iter.next(), accounts_iter.next(), accounts_iter.next()) {
+            (Some(referrer), Some(referrer_token_account), Some(referrer_stats)) => Some(Referral {
+                referrer,
+                referrer_token_account,
+                referrer_stats,
+            }),
+            (None, _, _) => None,
+            _ => return Err(ProgramError::NotEnoughAccountKeys),
+        };
+
+        if program.key != program_id {
+            msg!("{} is not this program", program.key);
+            return Err(TokenTracingError::InvalidAccountAddress.into());
+        }
+        assert_signer(payer)?;
+        assert_writable(payer)?;
+        assert_writable(vault)?;
+        assert_token_program(token_program)?;
+        assert_system_program(system_program)?;
+
+        let exchange = load_vault(program_id, vault, mint.key)?;
+        let vault_token_balance =
+            assert_vault_token_account(vault_token_account, vault.key, mint.key)?.amount;
+        assert_token_account(payer_token_account, mint.key, None)?;
+
+        Ok(Self {
+            payer,
+            payer_token_account,
+            mint,
+            vault,
+            vault_token_account,
+            token_program,
+            system_program,
+            exchange,
+            vault_token_balance,
+            wallet_stats,
+            access_entry,
+            attestation,
+            referral,
+        })
+    }
+}
@@ -0,0 +1,62 @@
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    pubkey::Pubkey,
+};
+
+use crate::processor::utils::{
+    assert_admin, assert_no_lp_supply, assert_token_program, assert_vault_token_account,
+    invoke_signed_by_vault, load_vault, transfer_lamports,
+};
+
+pub fn process(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    sol_amount: u64,
+    token_amount: u64,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let admin = next_account_info(accounts_iter)?;
+    let receiver_token_account = next_account_info(accounts_iter)?;
+    let mint = next_account_info(accounts_iter)?;
+    let vault = next_account_info(accounts_iter)?;
+    let vault_token_account = next_account_info(accounts_iter)?;
+    let token_program_id = next_account_info(accounts_iter)?;
+    let receiver = next_account_info(accounts_iter)?;
+    let lp_mint = next_account_info(accounts_iter)?;
+
+    assert_token_program(token_program_id)?;
+    let exchange = load_vault(program_id, vault, mint.key)?;
+    assert_admin(program_id, &exchange, admin, accounts_iter.as_slice())?;
+    assert_no_lp_supply(program_id, vault, lp_mint)?;
+    assert_vault_token_account(vault_token_account, vault.key, mint.key)?;
+
+    if sol_amount > 0 {
+        msg!("withdraw {} lamports from vault", sol_amount);
+        transfer_lamports(vault, receiver, sol_amount)?;
+    }
+
+    if token_amount > 0 {
+        msg!("withdraw {} tokens from vault", token_amount);
+        invoke_signed_by_vault(
+            &spl_token::instruction::transfer(
+                token_program_id.key,
+                vault_token_account.key,
+                receiver_token_account.key,
+                vault.key,
+                &[],
+                token_amount,
+            )?,
+            &[
+                token_program_id.clone(),
+                vault_token_account.clone(),
+                receiver_token_account.clone(),
+                vault.clone(),
+            ],
+            &exchange,
+        )?;
+    }
+
+    Ok(())
+}
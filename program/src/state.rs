@@ -1,12 +1,908 @@
 use std::mem::size_of;
 
 use borsh::{BorshDeserialize, BorshSerialize};
-use solana_program::pubkey::Pubkey;
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError,
+    pubkey::Pubkey,
+};
 
-#[derive(Clone, Debug, BorshDeserialize, BorshSerialize)]
+use crate::errors::TokenTracingError;
+use crate::math::{
+    checked_add, checked_div, checked_mul, checked_mul_div, checked_mul_div_ceil, checked_sub,
+    to_u64,
+};
+
+/// Token base units paid out per lamport by a freshly initialized booth.
+pub const DEFAULT_RATE: u64 = 10;
+
+/// Basis points in a whole, the ceiling for `fee_bps`.
+pub const MAX_FEE_BPS: u16 = 10_000;
+
+/// Fewest slots, about an hour, any rate or fee change waits before it can be
+/// executed, whatever the booth's own `timelock_slots`.
+pub const MIN_TIMELOCK_SLOTS: u64 = 9_000;
+
+/// Fee on a flash loan in basis points of the amount, kept by the vault.
+pub const FLASH_LOAN_FEE_BPS: u16 = 9;
+
+/// Fee owed on a flash loan of `amount`, rounded up so no loan is free.
+pub fn flash_loan_fee(amount: u64) -> Result<u64, ProgramError> {
+    checked_mul_div_ceil(amount, FLASH_LOAN_FEE_BPS as u64, MAX_FEE_BPS as u64)
+}
+
+/// Most fee tiers a booth can configure.
+pub const MAX_FEE_TIERS: usize = 4;
+
+/// Lamports a limit order escrows for whoever fills it, refunded on cancel.
+pub const LIMIT_ORDER_CRANK_REWARD: u64 = 10_000;
+
+/// Every state account starts with an 8-byte discriminator and a version byte.
+pub const ACCOUNT_HEADER_LEN: usize = 8 + size_of::<u8>();
+
+/// A program-owned account with a fixed-size Borsh body behind the header.
+pub trait AccountState: BorshSerialize + BorshDeserialize {
+    const DISCRIMINATOR: [u8; 8];
+    const VERSION: u8;
+    /// Total account length, header included.
+    const LEN: usize;
+
+    /// Decode raw account data, checking the discriminator and version.
+    fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() < Self::LEN {
+            return Err(TokenTracingError::AccountNotInitialized.into());
+        }
+        if data[..8] != Self::DISCRIMINATOR {
+            return Err(TokenTracingError::InvalidAccountDiscriminator.into());
+        }
+        if data[8] != Self::VERSION {
+            msg!("account version {} != {}", data[8], Self::VERSION);
+            return Err(TokenTracingError::UnsupportedAccountVersion.into());
+        }
+        Ok(Self::deserialize(&mut &data[ACCOUNT_HEADER_LEN..])?)
+    }
+
+    /// Encode header and body into raw account data.
+    fn pack(&self, data: &mut [u8]) -> ProgramResult {
+        if data.len() < Self::LEN {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+        data[..8].copy_from_slice(&Self::DISCRIMINATOR);
+        data[8] = Self::VERSION;
+        self.serialize(&mut &mut data[ACCOUNT_HEADER_LEN..])?;
+        Ok(())
+    }
+}
+
+/// Load a state account owned by `program_id`.
+pub fn load_account<T: AccountState>(
+    program_id: &Pubkey,
+    account: &AccountInfo,
+) -> Result<T, ProgramError> {
+    if account.owner != program_id {
+        msg!("{} is not owned by the program", account.key);
+        return Err(TokenTracingError::InvalidOwner.into());
+    }
+    T::unpack(&account.data.borrow())
+}
+
+/// Write a state account back.
+pub fn store_account<T: AccountState>(state: &T, account: &AccountInfo) -> ProgramResult {
+    if !account.is_writable {
+        return Err(TokenTracingError::AccountIsNotWritable.into());
+    }
+    state.pack(&mut account.data.borrow_mut())
+}
+
+/// Load the booth state stored in `vault`.
+pub fn load_exchange_account(
+    program_id: &Pubkey,
+    vault: &AccountInfo,
+) -> Result<ExchangeAccount, ProgramError> {
+    let exchange = load_account(program_id, vault);
+    if exchange.is_err() {
+        msg!("Invalid exchange booth account {}", vault.key);
+    }
+    exchange
+}
+
+/// Booth state stored in the vault PDA.
+///
+/// Fields are append-only: `MigrateExchange` upgrades an account written by
+/// an older version by zero-filling the fields added since, so a new field
+/// must treat zero as its default. Bump `VERSION` whenever a field is added.
+#[derive(Clone, Debug, Default, BorshDeserialize, BorshSerialize)]
 pub struct ExchangeAccount {
     pub admin: Pubkey,
-    pub vault: Pubkey
+    pub vault: Pubkey,
+    pub mint: Pubkey,
+    /// Token base units per lamport.
+    pub rate: u64,
+    /// Swaps are rejected while set.
+    pub paused: bool,
+    /// Lamports of swap value any single wallet may move per window, zero for no limit.
+    pub wallet_limit: u64,
+    /// Length of the wallet limit window in seconds.
+    pub wallet_limit_window: i64,
+    /// Lamports the booth may pay out per outflow window, zero for no cap.
+    pub max_sol_outflow: u64,
+    /// Token base units the booth may pay out per outflow window, zero for no cap.
+    pub max_token_outflow: u64,
+    /// Length of the outflow window in seconds.
+    pub outflow_window: i64,
+    /// Unix timestamp the current outflow window started at.
+    pub outflow_window_start: i64,
+    /// Lamports paid out in the current outflow window.
+    pub sol_outflow: u64,
+    /// Token base units paid out in the current outflow window.
+    pub token_outflow: u64,
+    /// Which wallets may swap, see [`AccessEntry`].
+    pub access_mode: AccessMode,
+    /// Swaps require an unexpired [`Attestation`] from this key, unless it
+    /// is the default (all zero) pubkey.
+    pub attestor: Pubkey,
+    /// Share of every swap payout the booth keeps, in basis points.
+    pub fee_bps: u16,
+    /// Pricing function applied to `rate`.
+    pub curve: CurveType,
+    /// Slots a proposed change must wait before it can be executed, at least
+    /// [`MIN_TIMELOCK_SLOTS`].
+    pub timelock_slots: u64,
+    /// Queued change, see [`PendingChange`].
+    pub pending: PendingChange,
+    /// Distinguishes booths sharing a mint, see [`crate::find_booth_address`].
+    pub booth_id: u64,
+    /// Bump seed of the vault PDA.
+    pub vault_bump: u8,
+    /// Share of the booth fee paid to a swap's referrer, in basis points of the fee.
+    pub referral_share_bps: u16,
+    /// Number of `fee_tiers` in use.
+    pub fee_tier_count: u8,
+    /// Volume discounts by ascending `min_volume`, see [`ExchangeAccount::fee_tier`].
+    pub fee_tiers: [FeeTier; MAX_FEE_TIERS],
+}
+
+impl ExchangeAccount {
+    /// Token base units received for `lamports`, after the fee.
+    pub fn quote_sol_to_token(&self, lamports: u64) -> Result<u64, ProgramError> {
+        self.quote_sol_to_token_at(lamports, self.fee_bps)
+    }
+
+    /// Lamports received for `amount` token base units, after the fee.
+    pub fn quote_token_to_sol(&self, amount: u64) -> Result<u64, ProgramError> {
+        self.quote_token_to_sol_at(amount, self.fee_bps)
+    }
+
+    /// Like [`Self::quote_sol_to_token`], charging `fee_bps` instead of the booth fee.
+    pub fn quote_sol_to_token_at(&self, lamports: u64, fee_bps: u16) -> Result<u64, ProgramError> {
+        let gross = self.gross_sol_to_token(lamports)?;
+        checked_sub(gross, fee_at(gross, fee_bps)?)
+    }
+
+    /// Like [`Self::quote_token_to_sol`], charging `fee_bps` instead of the booth fee.
+    pub fn quote_token_to_sol_at(&self, amount: u64, fee_bps: u16) -> Result<u64, ProgramError> {
+        let gross = self.gross_token_to_sol(amount)?;
+        checked_sub(gross, fee_at(gross, fee_bps)?)
+    }
+
+    /// Token base units a referrer earns on a swap of `lamports`, out of a fee of `fee_bps`.
+    pub fn referral_sol_to_token(&self, lamports: u64, fee_bps: u16) -> Result<u64, ProgramError> {
+        self.referral_fee(self.gross_sol_to_token(lamports)?, fee_bps)
+    }
+
+    /// Lamports a referrer earns on a swap of `amount` token base units, out of a fee of `fee_bps`.
+    pub fn referral_token_to_sol(&self, amount: u64, fee_bps: u16) -> Result<u64, ProgramError> {
+        self.referral_fee(self.gross_token_to_sol(amount)?, fee_bps)
+    }
+
+    /// Fee in basis points for a wallet that has swapped `volume` lamports,
+    /// with the index of the tier that set it. Tiers only ever lower the
+    /// booth fee, so fee increases still go through the timelock.
+    pub fn fee_tier(&self, volume: u64) -> (u16, Option<u8>) {
+        self.fee_tiers[..self.fee_tier_count as usize]
+            .iter()
+            .rposition(|tier| volume >= tier.min_volume)
+            .map_or((self.fee_bps, None), |index| {
+                let fee_bps = self.fee_tiers[index].fee_bps.min(self.fee_bps);
+                (fee_bps, Some(index as u8))
+            })
+    }
+
+    /// Worth in lamports of `lamports` and `tokens` together, valuing the
+    /// tokens through the booth curve without a fee.
+    pub fn value_in_lamports(&self, lamports: u64, tokens: u64) -> Result<u64, ProgramError> {
+        checked_add(lamports, self.gross_token_to_sol(tokens)?)
+    }
+
+    fn gross_sol_to_token(&self, lamports: u64) -> Result<u64, ProgramError> {
+        match self.curve {
+            CurveType::ConstantPrice => checked_mul(lamports, self.rate),
+        }
+    }
+
+    fn gross_token_to_sol(&self, amount: u64) -> Result<u64, ProgramError> {
+        match self.curve {
+            CurveType::ConstantPrice => checked_div(amount, self.rate),
+        }
+    }
+
+    /// The booth fee on a payout of `gross`, rounded down.
+    pub fn fee(&self, gross: u64) -> Result<u64, ProgramError> {
+        fee_at(gross, self.fee_bps)
+    }
+
+    fn referral_fee(&self, gross: u64, fee_bps: u16) -> Result<u64, ProgramError> {
+        checked_mul_div(
+            fee_at(gross, fee_bps)?,
+            self.referral_share_bps as u64,
+            MAX_FEE_BPS as u64,
+        )
+    }
+
+    /// The attestor swaps must be vouched for by, if any.
+    pub fn attestor(&self) -> Option<Pubkey> {
+        Some(self.attestor).filter(|attestor| *attestor != Pubkey::default())
+    }
+
+    /// The queued change, if any.
+    pub fn pending_change(&self) -> Option<&PendingChange> {
+        Some(&self.pending).filter(|pending| pending.queued)
+    }
+
+    /// Slots a change proposed now must wait.
+    pub fn timelock_delay(&self) -> u64 {
+        self.timelock_slots.max(MIN_TIMELOCK_SLOTS)
+    }
+}
+
+/// How a booth prices swaps from its `rate`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+pub enum CurveType {
+    /// `rate` token base units per lamport, whatever the reserves.
+    #[default]
+    ConstantPrice,
+}
+
+/// Which vault reserve a flash loan draws on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+pub enum LoanSide {
+    /// Lamports held by the vault PDA.
+    Sol,
+    /// Tokens held by the vault token account.
+    Token,
+}
+
+/// What a limit order sells to the booth.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+pub enum OrderSide {
+    /// Sell lamports for tokens.
+    #[default]
+    SolToToken,
+    /// Sell tokens for lamports.
+    TokenToSol,
+}
+
+fn fee_at(gross: u64, fee_bps: u16) -> Result<u64, ProgramError> {
+    checked_mul_div(gross, fee_bps as u64, MAX_FEE_BPS as u64)
+}
+
+/// Fee charged to wallets whose cumulative swap volume reaches `min_volume` lamports.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+pub struct FeeTier {
+    pub min_volume: u64,
+    pub fee_bps: u16,
+}
+
+pub const FEE_TIER_LEN: usize = size_of::<u64>() + size_of::<u16>();
+
+/// Check a fee tier table: at most `MAX_FEE_TIERS` tiers by strictly
+/// ascending volume, each charging no more than the one before.
+pub fn validate_fee_tiers(tiers: &[FeeTier]) -> Result<(), TokenTracingError> {
+    if tiers.len() > MAX_FEE_TIERS {
+        msg!(
+            "{} fee tiers, at most {} allowed",
+            tiers.len(),
+            MAX_FEE_TIERS
+        );
+        return Err(TokenTracingError::InvalidFee);
+    }
+    if tiers.iter().any(|tier| tier.fee_bps > MAX_FEE_BPS) {
+        return Err(TokenTracingError::InvalidFee);
+    }
+    for pair in tiers.windows(2) {
+        if pair[1].min_volume <= pair[0].min_volume || pair[1].fee_bps > pair[0].fee_bps {
+            msg!("fee tiers must raise the volume and not the fee");
+            return Err(TokenTracingError::InvalidFee);
+        }
+    }
+    Ok(())
+}
+
+/// Pricing change queued by `ProposeChange`, executable from `eta_slot` on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+pub struct PendingChange {
+    /// Unset when nothing is queued.
+    pub queued: bool,
+    pub rate: u64,
+    pub fee_bps: u16,
+    pub curve: CurveType,
+    pub timelock_slots: u64,
+    pub eta_slot: u64,
+}
+
+impl AccountState for ExchangeAccount {
+    const DISCRIMINATOR: [u8; 8] = *b"exchange";
+    const VERSION: u8 = 9;
+    const LEN: usize = EXCHANGE_ACCOUNT_LEN;
+}
+
+/// Who may swap against a booth.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+pub enum AccessMode {
+    /// Any wallet.
+    #[default]
+    Open,
+    /// Only wallets with an [`AccessEntry`].
+    Allowlist,
+    /// Any wallet without an [`AccessEntry`].
+    Denylist,
+}
+
+/// Booth layout written before accounts carried a header.
+#[derive(Clone, Debug, BorshDeserialize, BorshSerialize)]
+pub struct LegacyExchangeAccount {
+    pub admin: Pubkey,
+    pub vault: Pubkey,
+}
+
+pub const LEGACY_EXCHANGE_ACCOUNT_LEN: usize = size_of::<Pubkey>() * 2;
+
+pub const EXCHANGE_ACCOUNT_LEN: usize = ACCOUNT_HEADER_LEN
+    + size_of::<Pubkey>() * 3
+    + size_of::<u64>()
+    + size_of::<bool>()
+    + size_of::<u64>()
+    + size_of::<i64>()
+    + size_of::<u64>() * 2
+    + size_of::<i64>() * 2
+    + size_of::<u64>() * 2
+    + size_of::<u8>()
+    + size_of::<Pubkey>()
+    + size_of::<u16>()
+    + size_of::<u8>()
+    + size_of::<u64>()
+    + PENDING_CHANGE_LEN
+    + size_of::<u64>()
+    + size_of::<u8>()
+    + size_of::<u16>()
+    + size_of::<u8>()
+    + FEE_TIER_LEN * MAX_FEE_TIERS;
+
+pub const PENDING_CHANGE_LEN: usize = size_of::<bool>()
+    + size_of::<u64>()
+    + size_of::<u16>()
+    + size_of::<u8>()
+    + size_of::<u64>()
+    + size_of::<u64>();
+
+/// Per-wallet swap volume for one booth, PDA `[b"wallet", vault, wallet]`.
+///
+/// The wallet limit applies over a rolling window, estimated from fixed
+/// windows: the previous window's volume counts in proportion to how much of
+/// it the rolling window still overlaps.
+#[derive(Clone, Debug, Default, BorshDeserialize, BorshSerialize)]
+pub struct WalletStats {
+    pub vault: Pubkey,
+    pub wallet: Pubkey,
+    /// Unix timestamp the current fixed window started at.
+    pub window_start: i64,
+    /// Lamports of swap value moved in the current fixed window.
+    pub window_volume: u64,
+    /// Lamports of swap value moved ever, which sets the wallet's fee tier.
+    pub total_volume: u64,
+    /// Lamports of swap value moved in the fixed window before the current one.
+    pub previous_window_volume: u64,
+}
+
+impl WalletStats {
+    /// Decode stats written by any version. Fields added since an older
+    /// version read as zero until the account is next stored.
+    pub fn unpack_any_version(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() >= ACCOUNT_HEADER_LEN
+            && data[..8] == Self::DISCRIMINATOR
+            && data[8] < Self::VERSION
+        {
+            let mut body = data[ACCOUNT_HEADER_LEN..].to_vec();
+            body.resize(WALLET_STATS_LEN - ACCOUNT_HEADER_LEN, 0);
+            return Ok(Self::deserialize(&mut &body[..])?);
+        }
+        Self::unpack(data)
+    }
+
+    /// Lamports moved in the rolling `window` seconds up to `now`, after
+    /// moving the fixed windows forward to `now`.
+    pub fn rolling_volume(&mut self, now: i64, window: i64) -> Result<u64, ProgramError> {
+        let elapsed = now.saturating_sub(self.window_start);
+        if elapsed >= window.saturating_mul(2) {
+            self.window_start = now;
+            self.previous_window_volume = 0;
+            self.window_volume = 0;
+        } else if elapsed >= window {
+            self.window_start = self.window_start.saturating_add(window);
+            self.previous_window_volume = self.window_volume;
+            self.window_volume = 0;
+        }
+        let overlap = window.saturating_sub(now.saturating_sub(self.window_start));
+        let carried = checked_mul_div(self.previous_window_volume, overlap as u64, window as u64)?;
+        checked_add(carried, self.window_volume)
+    }
+}
+
+impl AccountState for WalletStats {
+    const DISCRIMINATOR: [u8; 8] = *b"walletst";
+    const VERSION: u8 = 3;
+    const LEN: usize = WALLET_STATS_LEN;
+}
+
+pub const WALLET_STATS_LEN: usize =
+    ACCOUNT_HEADER_LEN + size_of::<Pubkey>() * 2 + size_of::<i64>() + size_of::<u64>() * 3;
+
+/// A wallet on a booth's allow- or denylist, PDA `[b"access", vault, wallet]`.
+#[derive(Clone, Debug, Default, BorshDeserialize, BorshSerialize)]
+pub struct AccessEntry {
+    pub vault: Pubkey,
+    pub wallet: Pubkey,
+}
+
+impl AccountState for AccessEntry {
+    const DISCRIMINATOR: [u8; 8] = *b"accessen";
+    const VERSION: u8 = 1;
+    const LEN: usize = ACCESS_ENTRY_LEN;
+}
+
+pub const ACCESS_ENTRY_LEN: usize = ACCOUNT_HEADER_LEN + size_of::<Pubkey>() * 2;
+
+/// An attestor vouching for a wallet, PDA `[b"attestation", attestor, wallet]`.
+#[derive(Clone, Debug, Default, BorshDeserialize, BorshSerialize)]
+pub struct Attestation {
+    pub attestor: Pubkey,
+    pub subject: Pubkey,
+    /// Unix timestamp the attestation stops being valid at, zero for never.
+    pub expires_at: i64,
+}
+
+impl Attestation {
+    pub fn is_valid_at(&self, now: i64) -> bool {
+        self.expires_at == 0 || now < self.expires_at
+    }
+}
+
+impl AccountState for Attestation {
+    const DISCRIMINATOR: [u8; 8] = *b"attested";
+    const VERSION: u8 = 1;
+    const LEN: usize = ATTESTATION_LEN;
+}
+
+pub const ATTESTATION_LEN: usize = ACCOUNT_HEADER_LEN + size_of::<Pubkey>() * 2 + size_of::<i64>();
+
+/// Most keys a booth [`Multisig`] can list.
+pub const MAX_MULTISIG_SIGNERS: usize = 11;
+
+/// M-of-N signer set acting as a booth admin, PDA `[b"multisig", vault]`.
+#[derive(Clone, Debug, Default, BorshDeserialize, BorshSerialize)]
+pub struct Multisig {
+    pub vault: Pubkey,
+    /// Signatures required to act as admin.
+    pub threshold: u8,
+    /// Number of valid entries in `signers`.
+    pub signer_count: u8,
+    pub signers: [Pubkey; MAX_MULTISIG_SIGNERS],
+}
+
+impl Multisig {
+    /// The listed keys, without unused slots.
+    pub fn signers(&self) -> &[Pubkey] {
+        &self.signers[..self.signer_count as usize]
+    }
+}
+
+impl AccountState for Multisig {
+    const DISCRIMINATOR: [u8; 8] = *b"multisig";
+    const VERSION: u8 = 1;
+    const LEN: usize = MULTISIG_LEN;
+}
+
+pub const MULTISIG_LEN: usize = ACCOUNT_HEADER_LEN
+    + size_of::<Pubkey>()
+    + size_of::<u8>() * 2
+    + size_of::<Pubkey>() * MAX_MULTISIG_SIGNERS;
+
+/// Program-global directory of booths, PDA `[b"registry"]`.
+///
+/// `count` [`RegistryEntry`] records of `REGISTRY_ENTRY_LEN` bytes follow
+/// the header back to back; `Initialize` grows the account by one entry per
+/// booth and `CloseExchange` shrinks it again. Use [`BoothRegistry::entries`]
+/// to page through them.
+#[derive(Clone, Debug, Default, BorshDeserialize, BorshSerialize)]
+pub struct BoothRegistry {
+    pub count: u32,
+}
+
+impl BoothRegistry {
+    /// Account length holding `count` entries.
+    pub fn space(count: u32) -> usize {
+        BOOTH_REGISTRY_LEN + count as usize * REGISTRY_ENTRY_LEN
+    }
+
+    /// Decode up to `limit` entries starting at index `start` from raw
+    /// registry account data.
+    pub fn entries(
+        data: &[u8],
+        start: u32,
+        limit: u32,
+    ) -> Result<Vec<RegistryEntry>, ProgramError> {
+        let registry = Self::unpack(data)?;
+        let end = registry.count.min(start.saturating_add(limit));
+        if data.len() < Self::space(end) {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+        (start..end)
+            .map(|index| {
+                let offset = Self::space(index);
+                Ok(RegistryEntry::try_from_slice(
+                    &data[offset..offset + REGISTRY_ENTRY_LEN],
+                )?)
+            })
+            .collect()
+    }
+
+    /// Write `entry` at index `count` and bump the count. `data` must already
+    /// be `space(count + 1)` bytes long.
+    pub fn push(&mut self, data: &mut [u8], entry: &RegistryEntry) -> ProgramResult {
+        let offset = Self::space(self.count);
+        if data.len() < offset + REGISTRY_ENTRY_LEN {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+        entry.serialize(&mut &mut data[offset..offset + REGISTRY_ENTRY_LEN])?;
+        self.count = self
+            .count
+            .checked_add(1)
+            .ok_or(TokenTracingError::MathOverflow)?;
+        self.pack(data)
+    }
+
+    /// Remove the entry for `vault`, moving the last entry into its place, and
+    /// drop the count. Returns whether `vault` was registered; `data` may then
+    /// shrink to `space(count)` bytes.
+    pub fn remove(&mut self, data: &mut [u8], vault: &Pubkey) -> Result<bool, ProgramError> {
+        if data.len() < Self::space(self.count) {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+        // compare the raw vault field, which follows the mint, rather than
+        // decoding every entry
+        let vault_at = |index| Self::space(index) + size_of::<Pubkey>();
+        let index = match (0..self.count).find(|&index| {
+            data[vault_at(index)..vault_at(index) + size_of::<Pubkey>()] == vault.to_bytes()
+        }) {
+            Some(index) => index,
+            None => return Ok(false),
+        };
+        let last = self.count - 1;
+        if index != last {
+            let (to, from) = (Self::space(index), Self::space(last));
+            data.copy_within(from..from + REGISTRY_ENTRY_LEN, to);
+        }
+        self.count = last;
+        self.pack(data)?;
+        Ok(true)
+    }
+}
+
+impl AccountState for BoothRegistry {
+    const DISCRIMINATOR: [u8; 8] = *b"registry";
+    const VERSION: u8 = 1;
+    const LEN: usize = BOOTH_REGISTRY_LEN;
+}
+
+pub const BOOTH_REGISTRY_LEN: usize = ACCOUNT_HEADER_LEN + size_of::<u32>();
+
+/// A booth as it was created. Admin and curve may change later, the booth
+/// account itself stays authoritative.
+#[derive(Clone, Debug, Default, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+pub struct RegistryEntry {
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+    pub admin: Pubkey,
+    pub curve: CurveType,
+    pub booth_id: u64,
+    pub created_slot: u64,
+    /// The booth's [`BoothMetadata`] PDA, which exists once the admin has
+    /// called `SetMetadata`.
+    pub metadata: Pubkey,
+}
+
+pub const REGISTRY_ENTRY_LEN: usize =
+    size_of::<Pubkey>() * 4 + size_of::<u8>() + size_of::<u64>() * 2;
+
+/// Longest booth name, in bytes.
+pub const MAX_NAME_LEN: usize = 32;
+/// Longest booth symbol, in bytes.
+pub const MAX_SYMBOL_LEN: usize = 10;
+/// Longest booth description, in bytes.
+pub const MAX_DESCRIPTION_LEN: usize = 200;
+/// Longest booth metadata URI, in bytes.
+pub const MAX_URI_LEN: usize = 200;
+
+/// Human-readable booth details, PDA `[b"metadata", vault]`.
+///
+/// The account is sized for the longest strings allowed, so it never has to
+/// be reallocated when the admin edits them.
+#[derive(Clone, Debug, Default, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+pub struct BoothMetadata {
+    pub vault: Pubkey,
+    pub name: String,
+    pub symbol: String,
+    pub description: String,
+    pub uri: String,
+}
+
+impl BoothMetadata {
+    /// Check every field against its length limit.
+    pub fn validate(&self) -> ProgramResult {
+        let fields = [
+            ("name", &self.name, MAX_NAME_LEN),
+            ("symbol", &self.symbol, MAX_SYMBOL_LEN),
+            ("description", &self.description, MAX_DESCRIPTION_LEN),
+            ("uri", &self.uri, MAX_URI_LEN),
+        ];
+        for (field, value, max_len) in fields {
+            if value.len() > max_len {
+                msg!(
+                    "{} is {} bytes, at most {} allowed",
+                    field,
+                    value.len(),
+                    max_len
+                );
+                return Err(TokenTracingError::MetadataTooLong.into());
+            }
+        }
+        Ok(())
+    }
+}
+
+impl AccountState for BoothMetadata {
+    const DISCRIMINATOR: [u8; 8] = *b"metadata";
+    const VERSION: u8 = 1;
+    const LEN: usize = BOOTH_METADATA_LEN;
+}
+
+/// Borsh prefixes each string with a `u32` length.
+pub const BOOTH_METADATA_LEN: usize = ACCOUNT_HEADER_LEN
+    + size_of::<Pubkey>()
+    + size_of::<u32>() * 4
+    + MAX_NAME_LEN
+    + MAX_SYMBOL_LEN
+    + MAX_DESCRIPTION_LEN
+    + MAX_URI_LEN;
+
+/// A limit order resting against a booth, PDA `[b"order", vault, owner, order_id]`.
+///
+/// The order account holds `crank_reward` lamports above rent, plus the
+/// `amount` lamports of a `SolToToken` order. A `TokenToSol` order escrows its
+/// `amount` tokens in the order's associated token account.
+#[derive(Clone, Debug, Default, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+pub struct LimitOrder {
+    pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub order_id: u64,
+    pub side: OrderSide,
+    /// Lamports or token base units sold.
+    pub amount: u64,
+    /// Least the booth quote must pay out, after fees, for the order to fill.
+    pub min_out: u64,
+    /// Lamports paid to whoever fills the order.
+    pub crank_reward: u64,
+    /// Bump seed of the order PDA.
+    pub bump: u8,
+}
+
+impl AccountState for LimitOrder {
+    const DISCRIMINATOR: [u8; 8] = *b"limitord";
+    const VERSION: u8 = 1;
+    const LEN: usize = LIMIT_ORDER_LEN;
+}
+
+pub const LIMIT_ORDER_LEN: usize =
+    ACCOUNT_HEADER_LEN + size_of::<Pubkey>() * 2 + size_of::<u64>() * 4 + size_of::<u8>() * 2;
+
+/// A dollar-cost-averaging schedule buying tokens from a booth, PDA
+/// `[b"dca", vault, owner, dca_id]`.
+///
+/// The account escrows the lamports still to be swapped above its rent.
+/// `ExecuteDca` swaps `amount_per_period` of them, or whatever is left, once
+/// per `interval`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+pub struct DcaOrder {
+    pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub dca_id: u64,
+    /// Lamports swapped per execution.
+    pub amount_per_period: u64,
+    /// Seconds between executions.
+    pub interval: i64,
+    /// Unix timestamp from which the next execution may run.
+    pub next_execution: i64,
+    /// Bump seed of the DCA PDA.
+    pub bump: u8,
+}
+
+impl DcaOrder {
+    /// Move `next_execution` past `now` by whole intervals, so a crank that
+    /// runs late does not make up the missed periods in a burst.
+    pub fn advance(&mut self, now: i64) -> Result<(), ProgramError> {
+        let missed = now.saturating_sub(self.next_execution) / self.interval;
+        self.next_execution = missed
+            .checked_add(1)
+            .and_then(|periods| periods.checked_mul(self.interval))
+            .and_then(|delay| self.next_execution.checked_add(delay))
+            .ok_or(TokenTracingError::MathOverflow)?;
+        Ok(())
+    }
+}
+
+impl AccountState for DcaOrder {
+    const DISCRIMINATOR: [u8; 8] = *b"dcaorder";
+    const VERSION: u8 = 1;
+    const LEN: usize = DCA_ORDER_LEN;
+}
+
+pub const DCA_ORDER_LEN: usize = ACCOUNT_HEADER_LEN
+    + size_of::<Pubkey>() * 2
+    + size_of::<u64>() * 2
+    + size_of::<i64>() * 2
+    + size_of::<u8>();
+
+/// Referral earnings of one referrer on one booth, PDA `[b"referrer", vault, referrer]`.
+#[derive(Clone, Debug, Default, BorshDeserialize, BorshSerialize)]
+pub struct ReferrerStats {
+    pub vault: Pubkey,
+    pub referrer: Pubkey,
+    /// Swaps that paid this referrer.
+    pub swaps: u64,
+    /// Lamports earned on token to SOL swaps.
+    pub lamports_earned: u64,
+    /// Token base units earned on SOL to token swaps.
+    pub tokens_earned: u64,
+}
+
+impl AccountState for ReferrerStats {
+    const DISCRIMINATOR: [u8; 8] = *b"referrer";
+    const VERSION: u8 = 1;
+    const LEN: usize = REFERRER_STATS_LEN;
+}
+
+pub const REFERRER_STATS_LEN: usize =
+    ACCOUNT_HEADER_LEN + size_of::<Pubkey>() * 2 + size_of::<u64>() * 3;
+
+/// Scale of [`RewardPool::acc_reward_per_share`], so small stakes still accrue.
+pub const REWARD_PRECISION: u128 = 1_000_000_000_000;
+
+/// Liquidity mining rewards for one booth, PDA `[b"rewards", vault]`.
+///
+/// Stakers deposit `stake_mint` tokens, the booth's LP tokens, and earn
+/// `reward_mint` tokens at `reward_rate` per second split by stake. The PDA
+/// holds both in its associated token accounts, and emission stops once the
+/// funded rewards are all distributed.
+#[derive(Clone, Debug, Default, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+pub struct RewardPool {
+    pub vault: Pubkey,
+    pub stake_mint: Pubkey,
+    pub reward_mint: Pubkey,
+    /// Reward base units emitted per second across all stakers.
+    pub reward_rate: u64,
+    /// Rewards earned per staked base unit since the pool opened, scaled by
+    /// `REWARD_PRECISION`.
+    pub acc_reward_per_share: u128,
+    /// Unix timestamp rewards have accrued up to.
+    pub last_update: i64,
+    pub total_staked: u64,
+    /// Funded rewards not yet accrued to stakers.
+    pub undistributed: u64,
+    /// Bump seed of the reward pool PDA.
+    pub bump: u8,
+}
+
+impl RewardPool {
+    /// Accrue rewards up to `now`. Nothing accrues while nothing is staked.
+    pub fn update(&mut self, now: i64) -> Result<(), ProgramError> {
+        let elapsed = now.saturating_sub(self.last_update).max(0) as u64;
+        self.last_update = now;
+        if self.total_staked == 0 {
+            return Ok(());
+        }
+        let reward = elapsed
+            .checked_mul(self.reward_rate)
+            .map_or(self.undistributed, |reward| reward.min(self.undistributed));
+        let per_share = (reward as u128)
+            .checked_mul(REWARD_PRECISION)
+            .ok_or(TokenTracingError::MathOverflow)?
+            / self.total_staked as u128;
+        self.acc_reward_per_share = self
+            .acc_reward_per_share
+            .checked_add(per_share)
+            .ok_or(TokenTracingError::MathOverflow)?;
+        // the truncated remainder stays undistributed for later updates
+        let credited = per_share * self.total_staked as u128 / REWARD_PRECISION;
+        self.undistributed = checked_sub(self.undistributed, to_u64(credited)?)?;
+        Ok(())
+    }
+}
+
+impl AccountState for RewardPool {
+    const DISCRIMINATOR: [u8; 8] = *b"rewardpl";
+    const VERSION: u8 = 1;
+    const LEN: usize = REWARD_POOL_LEN;
+}
+
+pub const REWARD_POOL_LEN: usize = ACCOUNT_HEADER_LEN
+    + size_of::<Pubkey>() * 3
+    + size_of::<u64>()
+    + size_of::<u128>()
+    + size_of::<i64>()
+    + size_of::<u64>() * 2
+    + size_of::<u8>();
+
+/// One staker's position in a [`RewardPool`], PDA `[b"stake", rewards, owner]`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+pub struct StakeAccount {
+    pub rewards: Pubkey,
+    pub owner: Pubkey,
+    /// Stake mint base units staked.
+    pub amount: u64,
+    /// `amount` times the pool accumulator when last settled, already paid
+    /// into `pending`.
+    pub reward_debt: u128,
+    /// Rewards earned and not yet claimed.
+    pub pending: u64,
+    /// Bump seed of the stake PDA.
+    pub bump: u8,
+}
+
+impl StakeAccount {
+    /// Move rewards earned up to `acc_reward_per_share` into `pending`.
+    /// Call with the pool updated, before changing `amount`, then
+    /// [`Self::reset_debt`] after.
+    pub fn settle(&mut self, acc_reward_per_share: u128) -> Result<(), ProgramError> {
+        let earned = self
+            .accrued(acc_reward_per_share)?
+            .checked_sub(self.reward_debt)
+            .ok_or(TokenTracingError::MathOverflow)?;
+        self.pending = checked_add(self.pending, to_u64(earned)?)?;
+        Ok(())
+    }
+
+    /// Mark everything `amount` earned up to `acc_reward_per_share` as settled.
+    pub fn reset_debt(&mut self, acc_reward_per_share: u128) -> Result<(), ProgramError> {
+        self.reward_debt = self.accrued(acc_reward_per_share)?;
+        Ok(())
+    }
+
+    fn accrued(&self, acc_reward_per_share: u128) -> Result<u128, ProgramError> {
+        Ok((self.amount as u128)
+            .checked_mul(acc_reward_per_share)
+            .ok_or(TokenTracingError::MathOverflow)?
+            / REWARD_PRECISION)
+    }
+}
+
+impl AccountState for StakeAccount {
+    const DISCRIMINATOR: [u8; 8] = *b"stakeacc";
+    const VERSION: u8 = 1;
+    const LEN: usize = STAKE_ACCOUNT_LEN;
 }
 
-pub const EXCHANGE_ACCOUNT_LEN: usize = size_of::<Pubkey>() * 2;
+pub const STAKE_ACCOUNT_LEN: usize = ACCOUNT_HEADER_LEN
+    + size_of::<Pubkey>() * 2
+    + size_of::<u64>()
+    + size_of::<u128>()
+    + size_of::<u64>()
+    + size_of::<u8>();
@@ -1,21 +1,25 @@
+#![allow(non_snake_case)]
+
+use borsh::BorshDeserialize;
 use solana_program::{
-    system_program, 
-    account_info::{AccountInfo}, 
-    lamports, 
-    instruction, 
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    instruction::AccountMeta,
+    program::invoke,
     program_error::ProgramError,
+    system_instruction,
 };
 use solana_program_test::*;
 use solana_sdk::{
-    instruction::{AccountMeta, Instruction},
-    pubkey::Pubkey,
-    signature::{Keypair},
-    transaction::Transaction,
-    sysvar::SysvarId,
     hash::Hash,
+    instruction::{Instruction, InstructionError},
+    native_token::LAMPORTS_PER_SOL,
     program_pack::Pack,
+    pubkey::Pubkey,
+    signature::Keypair,
     signer::Signer,
-    system_instruction,
+    transaction::{Transaction, TransactionError},
     transport::TransportError,
 };
 use spl_associated_token_account::{
@@ -24,7 +28,22 @@ use spl_associated_token_account::{
 use more_asserts as ma;
 use spl_token::state::{Account, Mint};
 use tokentracing::entrypoint::process_instruction;
-use solana_program::clock::Epoch;
+use tokentracing::errors::TokenTracingError;
+use tokentracing::{
+    find_booth_address, find_dca_address, find_limit_order_address, find_lp_mint_address,
+    find_metadata_address, find_multisig_address, find_referrer_stats_address, find_registry_address,
+    find_rewards_address, find_stake_address, find_vault_address, find_wallet_stats_address,
+};
+use tokentracing::instruction::{self, FlashLoanCallback, TokenTracingInstruction};
+use tokentracing::math;
+use tokentracing::state::{
+    flash_loan_fee, AccessMode, AccountState, BoothMetadata, BoothRegistry, CurveType, DcaOrder,
+    ExchangeAccount, FeeTier, LimitOrder, LoanSide, OrderSide, ReferrerStats, RewardPool,
+    StakeAccount, WalletStats, DEFAULT_RATE, EXCHANGE_ACCOUNT_LEN, FEE_TIER_LEN,
+    LIMIT_ORDER_CRANK_REWARD, MAX_FEE_TIERS, MAX_NAME_LEN, MIN_TIMELOCK_SLOTS,
+};
+
+const MINT_DECIMALS: u8 = 9;
 
 async fn mint_amount(
     banks_client: &mut BanksClient,
@@ -148,33 +167,277 @@ async fn create_and_initialize_mint(
     Ok(())
 }
 
-#[tokio::test]
-async fn test_initialize() {
-    let program_id = Pubkey::new_unique();
-    let mint = Keypair::new();
-    let mint_decimals: u8 = 9;
+/// A started test validator with an initialized, funded booth.
+///
+/// The vault holds 100 tokens and 1 SOL of liquidity, the payer holds 100 tokens.
+struct Booth {
+    context: ProgramTestContext,
+    banks_client: BanksClient,
+    payer: Keypair,
+    program_id: Pubkey,
+    mint: Keypair,
+    vault: Pubkey,
+}
+
+impl Booth {
+    fn new(context: ProgramTestContext, program_id: Pubkey, mint: Keypair) -> Self {
+        let (vault, _) = find_vault_address(&program_id, &mint.pubkey());
+        Self {
+            banks_client: context.banks_client.clone(),
+            payer: Keypair::from_bytes(&context.payer.to_bytes()).unwrap(),
+            context,
+            program_id,
+            mint,
+            vault,
+        }
+    }
+
+    /// Move the clock sysvar `seconds` forward.
+    async fn advance_clock(&mut self, seconds: i64) {
+        let mut clock: Clock = self.banks_client.get_sysvar().await.unwrap();
+        clock.unix_timestamp += seconds;
+        self.context.set_sysvar(&clock);
+    }
+
+    /// Warp the bank `slots` slots forward.
+    async fn advance_slots(&mut self, slots: u64) {
+        let clock: Clock = self.banks_client.get_sysvar().await.unwrap();
+        self.context.warp_to_slot(clock.slot + slots).unwrap();
+    }
+
+    /// Queue `change` on booth `booth_id`, wait out the minimum timelock and
+    /// execute it.
+    async fn apply_change(&mut self, change: Instruction, booth_id: u64) {
+        self.process(&[change], &[]).await.unwrap();
+        self.advance_slots(MIN_TIMELOCK_SLOTS).await;
+        let execute = instruction::execute_change(&self.program_id, &self.mint.pubkey(), booth_id);
+        self.process(&[execute], &[]).await.unwrap();
+    }
+
+    async fn process(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), BanksClientError> {
+        let recent_blockhash = self.banks_client.get_latest_blockhash().await.unwrap();
+        let mut transaction = Transaction::new_with_payer(instructions, Some(&self.payer.pubkey()));
+        let mut all_signers = vec![&self.payer];
+        all_signers.extend_from_slice(signers);
+        transaction.sign(&all_signers, recent_blockhash);
+        self.banks_client.process_transaction(transaction).await
+    }
+
+    async fn lamports(&mut self, address: &Pubkey) -> u64 {
+        self.banks_client.get_balance(*address).await.unwrap()
+    }
+
+    async fn token_balance(&mut self, address: &Pubkey) -> u64 {
+        let account = self
+            .banks_client
+            .get_account(*address)
+            .await
+            .unwrap()
+            .expect("token account");
+        Account::unpack(&account.data).unwrap().amount
+    }
+
+    async fn exchange(&mut self) -> ExchangeAccount {
+        let account = self
+            .banks_client
+            .get_account(self.vault)
+            .await
+            .unwrap()
+            .expect("vault");
+        ExchangeAccount::unpack(&account.data).unwrap()
+    }
+
+    fn payer_ata(&self) -> Pubkey {
+        get_associated_token_address(&self.payer.pubkey(), &self.mint.pubkey())
+    }
 
+    fn vault_ata(&self) -> Pubkey {
+        get_associated_token_address(&self.vault, &self.mint.pubkey())
+    }
+}
+
+/// Assert that `result` failed with the booth program's `error`.
+fn assert_booth_error(result: Result<(), BanksClientError>, error: TokenTracingError) {
+    match result.unwrap_err().unwrap() {
+        TransactionError::InstructionError(_, InstructionError::Custom(code)) => {
+            assert_eq!(code, error as u32, "expected {:?}", error)
+        }
+        other => panic!("expected {:?}, got {:?}", error, other),
+    }
+}
+
+async fn setup_booth() -> Booth {
+    setup_booth_with(|_| {}).await
+}
 
+/// Like [`setup_booth`], letting `configure` add programs to the validator first.
+async fn setup_booth_with(configure: impl FnOnce(&mut ProgramTest)) -> Booth {
+    let program_id = Pubkey::new_unique();
+    let mint = Keypair::new();
     let mut program_test = ProgramTest::new(
         "tokentracing",
         program_id,
-        processor!(process_instruction)
+        processor!(process_instruction),
     );
+    configure(&mut program_test);
+    let mut context = program_test.start_with_context().await;
+    create_and_initialize_mint(
+        &mut context.banks_client,
+        context.last_blockhash,
+        &context.payer,
+        &context.payer,
+        &mint,
+        &spl_token::id(),
+        &MINT_DECIMALS,
+    )
+    .await
+    .unwrap();
+    let mut booth = Booth::new(context, program_id, mint);
+    let vault = booth.vault;
+
+    let payer = booth.payer.pubkey();
+    let mint = booth.mint.pubkey();
+    booth
+        .process(
+            &[
+                instruction::initialize(&program_id, &payer, &mint, 0),
+                create_associated_token_account(&payer, &vault, &mint, &spl_token::id()),
+                create_associated_token_account(&payer, &payer, &mint, &spl_token::id()),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+    for owner_ata in [booth.vault_ata(), booth.payer_ata()] {
+        let recent_blockhash = booth.banks_client.get_latest_blockhash().await.unwrap();
+        mint_amount(
+            &mut booth.banks_client,
+            recent_blockhash,
+            &spl_token::id(),
+            &owner_ata,
+            &mint,
+            &booth.payer,
+            &booth.payer,
+            100.0,
+            MINT_DECIMALS,
+        )
+        .await
+        .unwrap();
+    }
+    booth
+        .process(
+            &[instruction::deposit(&program_id, &payer, &mint, 0, LAMPORTS_PER_SOL, 0)],
+            &[],
+        )
+        .await
+        .unwrap();
+    booth
+}
+
+/// Flash loan receiver repaying the loan and its fee out of the borrower's funds.
+///
+/// Accounts: borrower, borrower token account, vault, vault token account,
+/// token program, system program.
+fn repay_flash_loan(_program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let callback = FlashLoanCallback::try_from_slice(data)?;
+    repay(accounts, &callback, callback.amount + callback.fee)
+}
+
+/// Flash loan receiver that returns the loan but keeps the fee.
+fn repay_flash_loan_without_fee(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let callback = FlashLoanCallback::try_from_slice(data)?;
+    repay(accounts, &callback, callback.amount)
+}
+
+fn repay(accounts: &[AccountInfo], callback: &FlashLoanCallback, amount: u64) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let borrower = next_account_info(accounts_iter)?;
+    let borrower_ata = next_account_info(accounts_iter)?;
+    let vault = next_account_info(accounts_iter)?;
+    let vault_ata = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    match callback.side {
+        LoanSide::Sol => invoke(
+            &system_instruction::transfer(borrower.key, vault.key, amount),
+            &[borrower.clone(), vault.clone(), system_program.clone()],
+        ),
+        LoanSide::Token => invoke(
+            &spl_token::instruction::transfer(
+                token_program.key,
+                borrower_ata.key,
+                vault_ata.key,
+                borrower.key,
+                &[],
+                amount,
+            )?,
+            &[borrower_ata.clone(), vault_ata.clone(), borrower.clone(), token_program.clone()],
+        ),
+    }
+}
 
+/// Router swapping `data` lamports through the booth by CPI, naming a
+/// referrer but no attestation.
+///
+/// Accounts: the eight fixed swap accounts, wallet stats, access entry,
+/// referrer, referrer token account, referrer stats.
+#[cfg(feature = "cpi")]
+fn route_referred_swap(_program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    use tokentracing::cpi::{exchange_sol_to_token, Exchange, Referral};
+
+    let amount = u64::try_from_slice(data)?;
+    let exchange = Exchange {
+        program: accounts[0].clone(),
+        payer: accounts[1].clone(),
+        payer_token_account: accounts[2].clone(),
+        mint: accounts[3].clone(),
+        vault: accounts[4].clone(),
+        vault_token_account: accounts[5].clone(),
+        token_program: accounts[6].clone(),
+        system_program: accounts[7].clone(),
+        wallet_stats: Some(accounts[8].clone()),
+        access_entry: Some(accounts[9].clone()),
+        attestation: None,
+        referral: Some(Referral {
+            referrer: accounts[10].clone(),
+            referrer_token_account: accounts[11].clone(),
+            referrer_stats: accounts[12].clone(),
+        }),
+    };
+    exchange_sol_to_token(&exchange, amount, &[])
+}
+
+#[tokio::test]
+async fn test_initialize() {
+    let program_id = Pubkey::new_unique();
+    let mint = Keypair::new();
+    let program_test = ProgramTest::new(
+        "tokentracing",
+        program_id,
+        processor!(process_instruction),
+    );
     let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
     create_and_initialize_mint(
-        &mut banks_client, 
-        recent_blockhash, 
-        &payer, 
-        &payer, 
-        &mint, 
-        &spl_token::id(), 
-        &mint_decimals
+        &mut banks_client,
+        recent_blockhash,
+        &payer,
+        &payer,
+        &mint,
+        &spl_token::id(),
+        &MINT_DECIMALS,
     )
     .await
     .unwrap();
-    
-    let (vault, vault_bump_seed) = Pubkey::find_program_address(&[b"vault", &mint.pubkey().to_bytes()], &program_id);
+
+    let (vault, _) = find_vault_address(&program_id, &mint.pubkey());
 
     // check if vault exists or not
     assert_eq!(
@@ -182,316 +445,1940 @@ async fn test_initialize() {
         None,
     );
 
-    let payer_account = AccountMeta {
-        pubkey: payer.pubkey(),
-        is_signer: true,
-        is_writable: true,
-    };
-
-    let vault_account = AccountMeta {
-        pubkey: vault,
-        is_signer: false,
-        is_writable: true
-    };
-
-    let mint_account = AccountMeta {
-        pubkey: mint.pubkey(),
-        is_signer: false,
-        is_writable: true
-    };
-
-    let sys_account = AccountMeta {
-        pubkey: system_program::id(),
-        is_signer: false,
-        is_writable: false
-    };
-    let accounts = vec![payer_account, vault_account, sys_account, mint_account];
-    let ins_data = &[0_u8]; 
     let mut transaction = Transaction::new_with_payer(
-        &[Instruction::new_with_bincode(
-            program_id,
-            ins_data,
-            accounts,
-        )],
+        &[instruction::initialize(&program_id, &payer.pubkey(), &mint.pubkey(), 0)],
         Some(&payer.pubkey()),
     );
     transaction.sign(&[&payer], recent_blockhash);
     banks_client.process_transaction(transaction).await.unwrap();
 
-    // check if vault exists or not
-    assert_ne!(
-        banks_client.get_account(vault).await.expect("Account"),
-        None,
-    );
+    // check if vault exists and holds the booth state
+    let vault_account = banks_client
+        .get_account(vault)
+        .await
+        .expect("Account")
+        .expect("vault");
+    let exchange = ExchangeAccount::unpack(&vault_account.data).unwrap();
+    assert_eq!(exchange.admin, payer.pubkey());
+    assert_eq!(exchange.mint, mint.pubkey());
 }
 
 #[tokio::test]
 async fn test_SOL_to_token() {
-    let program_id = Pubkey::new_unique();
-    let mint = Keypair::new();
-    let mint_decimals: u8 = 9;
+    let mut booth = setup_booth().await;
+    let (payer_ata, vault) = (booth.payer_ata(), booth.vault);
+    let vault_before = booth.lamports(&vault).await;
+    let tokens_before = booth.token_balance(&payer_ata).await;
 
+    let amount = 1000;
+    let swap = instruction::exchange_sol_to_token(
+        &booth.program_id,
+        &booth.payer.pubkey(),
+        &booth.mint.pubkey(),
+        0,
+        amount,
+    );
+    booth.process(&[swap], &[]).await.unwrap();
 
-    let mut program_test = ProgramTest::new(
-        "tokentracing",
-        program_id,
-        processor!(process_instruction)
+    assert_eq!(booth.lamports(&vault).await, vault_before + amount);
+    assert_eq!(booth.token_balance(&payer_ata).await, tokens_before + amount * 10);
+}
+
+#[tokio::test]
+async fn test_token_to_SOL() {
+    let mut booth = setup_booth().await;
+    let (payer_ata, vault) = (booth.payer_ata(), booth.vault);
+    let vault_before = booth.lamports(&vault).await;
+    let tokens_before = booth.token_balance(&payer_ata).await;
+
+    let amount = 1000;
+    let swap = instruction::exchange_token_to_sol(
+        &booth.program_id,
+        &booth.payer.pubkey(),
+        &booth.mint.pubkey(),
+        0,
+        amount,
     );
+    booth.process(&[swap], &[]).await.unwrap();
 
-    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
-    create_and_initialize_mint(
-        &mut banks_client, 
-        recent_blockhash, 
-        &payer, 
-        &payer, 
-        &mint, 
-        &spl_token::id(), 
-        &mint_decimals
+    assert_eq!(booth.lamports(&vault).await, vault_before - amount / 10);
+    assert_eq!(booth.token_balance(&payer_ata).await, tokens_before - amount);
+}
+
+#[tokio::test]
+async fn test_SOL_to_token_fails_without_token_liquidity() {
+    let mut booth = setup_booth().await;
+    let vault = booth.vault;
+    let vault_before = booth.lamports(&vault).await;
+
+    // 20 SOL buys 200 tokens but the vault only holds 100
+    let swap = instruction::exchange_sol_to_token(
+        &booth.program_id,
+        &booth.payer.pubkey(),
+        &booth.mint.pubkey(),
+        0,
+        20 * LAMPORTS_PER_SOL,
+    );
+    assert_booth_error(
+        booth.process(&[swap], &[]).await,
+        TokenTracingError::InsufficientFunds,
+    );
+
+    // the SOL leg was rolled back with the failed token leg
+    assert_eq!(booth.lamports(&vault).await, vault_before);
+}
+
+#[tokio::test]
+async fn test_token_to_SOL_fails_without_token_balance() {
+    let mut booth = setup_booth().await;
+    let vault = booth.vault;
+    let vault_before = booth.lamports(&vault).await;
+
+    let swap = instruction::exchange_token_to_sol(
+        &booth.program_id,
+        &booth.payer.pubkey(),
+        &booth.mint.pubkey(),
+        0,
+        1000 * 10_u64.pow(MINT_DECIMALS as u32),
+    );
+    assert_booth_error(
+        booth.process(&[swap], &[]).await,
+        TokenTracingError::InsufficientFunds,
+    );
+    assert_eq!(booth.lamports(&vault).await, vault_before);
+}
+
+#[tokio::test]
+async fn test_token_to_SOL_keeps_vault_rent_exempt() {
+    let mut booth = setup_booth().await;
+    let (payer_ata, vault) = (booth.payer_ata(), booth.vault);
+    let tokens_before = booth.token_balance(&payer_ata).await;
+
+    // 20 tokens buy 2 SOL but the vault only holds 1 SOL above rent
+    let swap = instruction::exchange_token_to_sol(
+        &booth.program_id,
+        &booth.payer.pubkey(),
+        &booth.mint.pubkey(),
+        0,
+        20 * 10_u64.pow(MINT_DECIMALS as u32),
+    );
+    assert_booth_error(
+        booth.process(&[swap], &[]).await,
+        TokenTracingError::InsufficientFunds,
+    );
+    assert_eq!(booth.token_balance(&payer_ata).await, tokens_before);
+    let rent = booth.banks_client.get_rent().await.unwrap();
+    ma::assert_ge!(
+        booth.lamports(&vault).await,
+        rent.minimum_balance(EXCHANGE_ACCOUNT_LEN)
+    );
+}
+
+#[tokio::test]
+async fn test_swap_rejects_foreign_vault_token_account() {
+    let mut booth = setup_booth().await;
+    let foreign = Keypair::new();
+    let recent_blockhash = booth.banks_client.get_latest_blockhash().await.unwrap();
+    create_and_initialize_account_for_mint(
+        &mut booth.banks_client,
+        recent_blockhash,
+        &spl_token::id(),
+        &foreign,
+        &booth.mint,
+        &booth.payer,
     )
     .await
     .unwrap();
-    
-    let (vault, vault_bump_seed) = Pubkey::find_program_address(&[b"vault", &mint.pubkey().to_bytes()], &program_id);
 
-    let payer_account = AccountMeta {
-        pubkey: payer.pubkey(),
-        is_signer: true,
-        is_writable: true,
-    };
+    let mut swap = instruction::exchange_sol_to_token(
+        &booth.program_id,
+        &booth.payer.pubkey(),
+        &booth.mint.pubkey(),
+        0,
+        1000,
+    );
+    swap.accounts[5].pubkey = foreign.pubkey();
+    assert_booth_error(
+        booth.process(&[swap], &[]).await,
+        TokenTracingError::InvalidSPLTokenAccount,
+    );
+}
 
-    let vault_account = AccountMeta {
-        pubkey: vault,
-        is_signer: false,
-        is_writable: true
-    };
+#[tokio::test]
+async fn test_swap_fails_when_paused() {
+    let mut booth = setup_booth().await;
+    let pause = instruction::set_paused(
+        &booth.program_id,
+        &booth.payer.pubkey(),
+        &booth.mint.pubkey(),
+        0,
+        true,
+    );
+    booth.process(&[pause], &[]).await.unwrap();
 
-    let mint_account = AccountMeta {
-        pubkey: mint.pubkey(),
-        is_signer: false,
-        is_writable: true
-    };
+    let swap = instruction::exchange_sol_to_token(
+        &booth.program_id,
+        &booth.payer.pubkey(),
+        &booth.mint.pubkey(),
+        0,
+        1000,
+    );
+    assert_booth_error(booth.process(&[swap], &[]).await, TokenTracingError::BoothPaused);
+}
 
-    let sys_account = AccountMeta {
-        pubkey: system_program::id(),
-        is_signer: false,
-        is_writable: false
-    };
-    let accounts = vec![payer_account, vault_account, sys_account, mint_account];
-    let ins_data = &[0_u8]; 
-    let mut transaction = Transaction::new_with_payer(
-        &[Instruction::new_with_bincode(
-            program_id,
-            ins_data,
-            accounts,
-        )],
-        Some(&payer.pubkey()),
+#[tokio::test]
+async fn test_withdraw() {
+    let mut booth = setup_booth().await;
+    let (program_id, payer, mint, vault) = (
+        booth.program_id,
+        booth.payer.pubkey(),
+        booth.mint.pubkey(),
+        booth.vault,
     );
-    transaction.sign(&[&payer], recent_blockhash);
-    banks_client.process_transaction(transaction).await.unwrap();
+    let vault_ata = booth.vault_ata();
+    let receiver = Keypair::new();
+    let receiver_ata = get_associated_token_address(&receiver.pubkey(), &mint);
+    let create_ata = create_associated_token_account(&payer, &receiver.pubkey(), &mint, &spl_token::id());
+    let fund = system_instruction::transfer(&payer, &receiver.pubkey(), LAMPORTS_PER_SOL);
+    booth.process(&[create_ata, fund], &[]).await.unwrap();
 
-    let vault_ata = get_associated_token_address(&vault, &mint.pubkey());
-    let payer_ata = get_associated_token_address(&payer.pubkey(), &mint.pubkey());
-    
-    let amount = 1000;
-    let arr = u64::to_le_bytes(amount);
-    let mut instruction_data = [1; 9];
-    for i in 0..8 {
-        instruction_data[i + 1] = arr[i];
-    }
-    let mut transation_SOL_to_token = Transaction::new_with_payer(
-        &[Instruction::new_with_bincode(
-            program_id,
-            &instruction_data,
-            vec![
-                AccountMeta::new(program_id, false),
-                AccountMeta::new(payer.pubkey(), true),
-                AccountMeta::new(payer_ata, false),
-                AccountMeta::new(mint.pubkey(), false),
-                AccountMeta::new(vault, false),
-                AccountMeta::new(vault_ata, false),
-                AccountMeta::new_readonly(spl_token::id(), false),
-                AccountMeta::new(system_program::id(), false),
-            ],
-        )],
-        Some(&payer.pubkey()),
+    // the reserves belong to the LP holders while any LP token is left
+    let withdraw = instruction::withdraw(&program_id, &payer, &receiver.pubkey(), &mint, 0, 1000, 0);
+    assert_booth_error(
+        booth.process(&[withdraw], &[]).await,
+        TokenTracingError::LiquidityOwedToLp,
     );
-    transation_SOL_to_token.sign(&[&payer], recent_blockhash);
-    let payer_before = banks_client.get_account(payer.pubkey().clone()).await.unwrap().expect("payer lamport before");
-    let vault_before = banks_client.get_account(vault.clone()).await.unwrap().expect("vault lamport before");
-    
-    banks_client.process_transaction(transation_SOL_to_token).await.unwrap();
-    
-    let payer_after = banks_client.get_account(payer.pubkey().clone()).await.unwrap().expect("payer lamport after");
-    let vault_after = banks_client.get_account(vault.clone()).await.unwrap().expect("vault lamport after");
-    
-    // check if payer balance is decrease
-    ma::assert_gt!(
-        payer_before.lamports,
-        payer_after.lamports
+
+    // once they are removed the admin pays out what else reaches the vault
+    let remove = instruction::remove_liquidity(&program_id, &payer, &mint, 0, LAMPORTS_PER_SOL);
+    let send_lamports = system_instruction::transfer(&payer, &vault, 1000);
+    let send_tokens =
+        spl_token::instruction::transfer(&spl_token::id(), &booth.payer_ata(), &vault_ata, &payer, &[], 500).unwrap();
+    booth.process(&[remove, send_lamports, send_tokens], &[]).await.unwrap();
+    let (vault_before, vault_tokens) = (booth.lamports(&vault).await, booth.token_balance(&vault_ata).await);
+    assert_eq!(vault_tokens, 500);
+
+    let withdraw = instruction::withdraw(&program_id, &payer, &receiver.pubkey(), &mint, 0, 1000, 500);
+    booth.process(&[withdraw], &[]).await.unwrap();
+    assert_eq!(booth.lamports(&vault).await, vault_before - 1000);
+    assert_eq!(booth.lamports(&receiver.pubkey()).await, LAMPORTS_PER_SOL + 1000);
+    assert_eq!(booth.token_balance(&vault_ata).await, vault_tokens - 500);
+    assert_eq!(booth.token_balance(&receiver_ata).await, 500);
+
+    // only the admin withdraws
+    let stranger = Keypair::new();
+    let withdraw = instruction::withdraw(&program_id, &stranger.pubkey(), &stranger.pubkey(), &mint, 0, 1000, 0);
+    assert_booth_error(
+        booth.process(&[withdraw], &[&stranger]).await,
+        TokenTracingError::InvalidOwner,
     );
 
-    // check if vault balance is increase
-    ma::assert_lt!(
-        vault_before.lamports,
-        vault_after.lamports
+    // the vault keeps its rent exemption
+    let withdraw = instruction::withdraw(&program_id, &payer, &payer, &mint, 0, vault_before, 0);
+    assert_booth_error(
+        booth.process(&[withdraw], &[]).await,
+        TokenTracingError::InsufficientFunds,
     );
+    assert_eq!(booth.lamports(&vault).await, vault_before - 1000);
 }
 
-//Wrong unit test
 #[tokio::test]
-async fn test_token_to_SOL() {
-    let program_id = Pubkey::new_unique();
-    let mint = Keypair::new();
-    let mint_decimals: u8 = 9;
+async fn test_set_rate() {
+    let mut booth = setup_booth().await;
+    let (program_id, payer, mint) = (booth.program_id, booth.payer.pubkey(), booth.mint.pubkey());
+    let payer_ata = booth.payer_ata();
+
+    // the new rate waits out the minimum timelock like any proposed change
+    let set_rate = instruction::set_rate(&program_id, &payer, &mint, 0, 20);
+    booth.process(&[set_rate], &[]).await.unwrap();
+    let exchange = booth.exchange().await;
+    assert_eq!(exchange.rate, 10);
+    assert_eq!(exchange.pending_change().map(|change| change.rate), Some(20));
+    let execute = instruction::execute_change(&program_id, &mint, 0);
+    assert_booth_error(
+        booth.process(&[execute.clone()], &[]).await,
+        TokenTracingError::TimelockNotExpired,
+    );
+    booth.advance_slots(MIN_TIMELOCK_SLOTS).await;
+    booth.process(&[execute], &[]).await.unwrap();
+    let tokens_before = booth.token_balance(&payer_ata).await;
+    let swap = instruction::exchange_sol_to_token(&program_id, &payer, &mint, 0, 1000);
+    booth.process(&[swap], &[]).await.unwrap();
+    assert_eq!(booth.token_balance(&payer_ata).await, tokens_before + 20_000);
 
+    let zero = instruction::set_rate(&program_id, &payer, &mint, 0, 0);
+    assert_booth_error(booth.process(&[zero], &[]).await, TokenTracingError::InvalidRate);
+    let stranger = Keypair::new();
+    let not_admin = instruction::set_rate(&program_id, &stranger.pubkey(), &mint, 0, 30);
+    assert_booth_error(
+        booth.process(&[not_admin], &[&stranger]).await,
+        TokenTracingError::InvalidOwner,
+    );
+}
 
+/// Start a validator holding, owned by the program, the vault of booth 0 of
+/// `mint` with raw `vault_data`, and an `admin` wallet with 1 SOL.
+async fn start_with_vault(
+    program_id: &Pubkey,
+    mint: &Keypair,
+    admin: &Pubkey,
+    vault_data: Vec<u8>,
+) -> ProgramTestContext {
     let mut program_test = ProgramTest::new(
         "tokentracing",
-        program_id,
-        processor!(process_instruction)
+        *program_id,
+        processor!(process_instruction),
     );
-
-    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    let (vault, _) = find_vault_address(program_id, &mint.pubkey());
+    let rent = solana_program::rent::Rent::default();
+    program_test.add_account(
+        vault,
+        solana_sdk::account::Account {
+            lamports: rent.minimum_balance(vault_data.len()) + LAMPORTS_PER_SOL,
+            data: vault_data,
+            owner: *program_id,
+            ..solana_sdk::account::Account::default()
+        },
+    );
+    program_test.add_account(
+        *admin,
+        solana_sdk::account::Account::new(LAMPORTS_PER_SOL, 0, &solana_program::system_program::id()),
+    );
+    let mut context = program_test.start_with_context().await;
     create_and_initialize_mint(
-        &mut banks_client, 
-        recent_blockhash, 
-        &payer, 
-        &payer, 
-        &mint, 
-        &spl_token::id(), 
-        &mint_decimals
+        &mut context.banks_client,
+        context.last_blockhash,
+        &context.payer,
+        &context.payer,
+        mint,
+        &spl_token::id(),
+        &MINT_DECIMALS,
     )
     .await
     .unwrap();
-    
-    let vault_ata = Keypair::new();
-    create_and_initialize_account_for_mint(
-        &mut banks_client,
+    context
+}
+
+#[tokio::test]
+async fn test_migrate_legacy_booth() {
+    let program_id = Pubkey::new_unique();
+    let (mint, admin) = (Keypair::new(), Keypair::new());
+    let (vault, vault_bump) = find_vault_address(&program_id, &mint.pubkey());
+    // the original layout: admin and vault, no header
+    let legacy = [admin.pubkey().to_bytes(), vault.to_bytes()].concat();
+    assert_eq!(legacy.len(), 64);
+    let context = start_with_vault(&program_id, &mint, &admin.pubkey(), legacy).await;
+    let mut booth = Booth::new(context, program_id, mint);
+    let (payer_key, mint) = (booth.payer.pubkey(), booth.mint.pubkey());
+    let vault_before = booth.lamports(&vault).await;
+
+    // only the admin migrates
+    let stranger = Keypair::new();
+    let not_admin = instruction::migrate_exchange(&program_id, &payer_key, &stranger.pubkey(), &mint, 0);
+    assert_booth_error(
+        booth.process(&[not_admin], &[&stranger]).await,
+        TokenTracingError::InvalidOwner,
+    );
+
+    let migrate = instruction::migrate_exchange(&program_id, &payer_key, &admin.pubkey(), &mint, 0);
+    booth.process(&[migrate], &[&admin]).await.unwrap();
+    let account = booth.banks_client.get_account(vault).await.unwrap().unwrap();
+    assert_eq!(account.data.len(), EXCHANGE_ACCOUNT_LEN);
+    let rent = solana_program::rent::Rent::default();
+    assert_eq!(
+        account.lamports - vault_before,
+        rent.minimum_balance(EXCHANGE_ACCOUNT_LEN) - rent.minimum_balance(64)
+    );
+    let exchange = ExchangeAccount::unpack(&account.data).unwrap();
+    assert_eq!(exchange.admin, admin.pubkey());
+    assert_eq!(exchange.vault, vault);
+    assert_eq!(exchange.mint, mint);
+    assert_eq!(exchange.rate, DEFAULT_RATE);
+    assert_eq!(exchange.vault_bump, vault_bump);
+
+    // a second run has nothing to do
+    let migrate = instruction::migrate_exchange(&program_id, &payer_key, &admin.pubkey(), &mint, 0);
+    assert_booth_error(
+        booth.process(&[migrate], &[&admin]).await,
+        TokenTracingError::BoothUpToDate,
+    );
+
+    // the migrated booth swaps, signing with its recomputed bump
+    let payer_ata = booth.payer_ata();
+    booth
+        .process(
+            &[
+                create_associated_token_account(&payer_key, &vault, &mint, &spl_token::id()),
+                create_associated_token_account(&payer_key, &payer_key, &mint, &spl_token::id()),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+    let recent_blockhash = booth.banks_client.get_latest_blockhash().await.unwrap();
+    let vault_ata = booth.vault_ata();
+    mint_amount(
+        &mut booth.banks_client,
         recent_blockhash,
         &spl_token::id(),
         &vault_ata,
         &mint,
-        &payer,
+        &booth.payer,
+        &booth.payer,
+        100.0,
+        MINT_DECIMALS,
     )
     .await
     .unwrap();
+    let swap = instruction::exchange_sol_to_token(&program_id, &payer_key, &mint, 0, 1000);
+    booth.process(&[swap], &[]).await.unwrap();
+    assert_eq!(booth.token_balance(&payer_ata).await, 1000 * DEFAULT_RATE);
+}
 
-    mint_amount(
-        &mut banks_client,
+#[tokio::test]
+async fn test_migrate_previous_version() {
+    let program_id = Pubkey::new_unique();
+    let (mint, admin) = (Keypair::new(), Keypair::new());
+    let (vault, vault_bump) = find_vault_address(&program_id, &mint.pubkey());
+    let exchange = ExchangeAccount {
+        admin: admin.pubkey(),
+        vault,
+        mint: mint.pubkey(),
+        rate: 7,
+        fee_bps: 25,
+        referral_share_bps: 1000,
+        ..ExchangeAccount::default()
+    };
+    // version 8, from before fee tiers were appended
+    let mut data = vec![0; EXCHANGE_ACCOUNT_LEN];
+    exchange.pack(&mut data).unwrap();
+    data.truncate(EXCHANGE_ACCOUNT_LEN - 1 - MAX_FEE_TIERS * FEE_TIER_LEN);
+    data[8] = ExchangeAccount::VERSION - 1;
+    let old_len = data.len();
+    let ProgramTestContext {
+        mut banks_client,
+        payer,
+        ..
+    } = start_with_vault(&program_id, &mint, &admin.pubkey(), data).await;
+    let vault_before = banks_client.get_balance(vault).await.unwrap();
+
+    let migrate = instruction::migrate_exchange(&program_id, &payer.pubkey(), &admin.pubkey(), &mint.pubkey(), 0);
+    let recent_blockhash = banks_client.get_latest_blockhash().await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[migrate],
+        Some(&payer.pubkey()),
+        &[&payer, &admin],
         recent_blockhash,
-        &spl_token::id(),
-        &vault_ata.pubkey(),
-        &mint.pubkey(),
-        &payer,
-        &payer,
-        100_u64,
-        9_u8,
-    )
-    .await
-    .unwrap();
+    );
+    banks_client.process_transaction(transaction).await.unwrap();
 
-    let (vault, vault_bump_seed) = Pubkey::find_program_address(&[b"vault", &mint.pubkey().to_bytes()], &program_id);
+    let account = banks_client.get_account(vault).await.unwrap().unwrap();
+    let rent = solana_program::rent::Rent::default();
+    assert_eq!(
+        account.lamports - vault_before,
+        rent.minimum_balance(EXCHANGE_ACCOUNT_LEN) - rent.minimum_balance(old_len)
+    );
+    let migrated = ExchangeAccount::unpack(&account.data).unwrap();
+    assert_eq!(migrated.admin, exchange.admin);
+    assert_eq!(migrated.mint, exchange.mint);
+    assert_eq!((migrated.rate, migrated.fee_bps), (7, 25));
+    assert_eq!(migrated.referral_share_bps, 1000);
+    assert_eq!(migrated.vault_bump, vault_bump);
+    assert_eq!(migrated.fee_tier_count, 0);
+}
 
-    let payer_account = AccountMeta {
-        pubkey: payer.pubkey(),
-        is_signer: true,
-        is_writable: true,
-    };
+#[tokio::test]
+async fn test_swap_respects_wallet_limit() {
+    let mut booth = setup_booth().await;
+    let limit = instruction::set_wallet_limit(
+        &booth.program_id,
+        &booth.payer.pubkey(),
+        &booth.mint.pubkey(),
+        0,
+        LAMPORTS_PER_SOL,
+        86_400,
+    );
+    booth.process(&[limit], &[]).await.unwrap();
 
-    let vault_account = AccountMeta {
-        pubkey: vault,
-        is_signer: false,
-        is_writable: true
+    let swap = |amount| {
+        instruction::exchange_sol_to_token(
+            &booth.program_id,
+            &booth.payer.pubkey(),
+            &booth.mint.pubkey(),
+            0,
+            amount,
+        )
     };
+    let (first, second, third) = (
+        swap(LAMPORTS_PER_SOL / 2),
+        swap(LAMPORTS_PER_SOL / 2 + 1),
+        swap(LAMPORTS_PER_SOL / 4),
+    );
+    let (full, rest) = (swap(LAMPORTS_PER_SOL), swap(LAMPORTS_PER_SOL / 2 + 2));
+    booth.process(&[first], &[]).await.unwrap();
+    // half a SOL and one lamport would push the window over one SOL
+    assert_booth_error(booth.process(&[second], &[]).await, TokenTracingError::LimitExceeded);
+    booth.process(&[third], &[]).await.unwrap();
 
-    let mint_account = AccountMeta {
-        pubkey: mint.pubkey(),
-        is_signer: false,
-        is_writable: true
-    };
+    // the window rolls: half a window later, half of the 0.75 SOL still counts
+    booth.advance_clock(86_400 + 43_200).await;
+    assert_booth_error(booth.process(&[full], &[]).await, TokenTracingError::LimitExceeded);
+    booth.process(&[rest], &[]).await.unwrap();
 
-    let sys_account = AccountMeta {
-        pubkey: system_program::id(),
-        is_signer: false,
-        is_writable: false
-    };
-    let accounts = vec![payer_account, vault_account, sys_account, mint_account];
-    let ins_data = &[0_u8]; 
-    let mut transaction = Transaction::new_with_payer(
-        &[Instruction::new_with_bincode(
-            program_id,
-            ins_data,
-            accounts,
-        )],
-        Some(&payer.pubkey()),
+    // a wallet limit needs a window to reset on
+    let no_window = instruction::set_wallet_limit(
+        &booth.program_id,
+        &booth.payer.pubkey(),
+        &booth.mint.pubkey(),
+        0,
+        LAMPORTS_PER_SOL,
+        0,
     );
-    transaction.sign(&[&payer], recent_blockhash);
-    banks_client.process_transaction(transaction).await.unwrap();
+    assert_booth_error(
+        booth.process(&[no_window], &[]).await,
+        TokenTracingError::InvalidInstructionData,
+    );
+}
 
+#[tokio::test]
+async fn test_circuit_breaker_pauses_booth() {
+    let mut booth = setup_booth().await;
+    let payer_ata = booth.payer_ata();
+    let breaker = instruction::set_circuit_breaker(
+        &booth.program_id,
+        &booth.payer.pubkey(),
+        &booth.mint.pubkey(),
+        0,
+        0,
+        15 * 10_u64.pow(MINT_DECIMALS as u32),
+        3_600,
+    );
+    booth.process(&[breaker], &[]).await.unwrap();
 
-    // let vault_ata = banks_client
-    //     .get_account(token_account.pubkey().clone())
-    //     .await
-    //     .unwrap();
-    let payer_ata = get_associated_token_address(&payer.pubkey(), &mint.pubkey());
-    
-    let amount = 1000;
-    let arr = u64::to_le_bytes(amount);
-    let mut instruction_data = [2; 9];
-    for i in 0..8 {
-        instruction_data[i + 1] = arr[i];
-    }
-    let mut transation_token_to_SOL = Transaction::new_with_payer(
-        &[Instruction::new_with_bincode(
-            program_id,
-            &instruction_data,
-            vec![
-                AccountMeta::new(program_id, false),
-                AccountMeta::new(payer.pubkey(), true),
-                AccountMeta::new(payer_ata, false),
-                AccountMeta::new(mint.pubkey(), false),
-                AccountMeta::new(vault, false),
-                AccountMeta::new(vault_ata.pubkey(), false),
-                AccountMeta::new_readonly(spl_token::id(), false),
-                AccountMeta::new(system_program::id(), false),
-            ],
-        )],
-        Some(&payer.pubkey()),
+    let swap = |amount| {
+        instruction::exchange_sol_to_token(
+            &booth.program_id,
+            &booth.payer.pubkey(),
+            &booth.mint.pubkey(),
+            0,
+            amount,
+        )
+    };
+    let (first, oversized, last, after) = (
+        swap(LAMPORTS_PER_SOL),
+        swap(LAMPORTS_PER_SOL + 1),
+        swap(LAMPORTS_PER_SOL / 2),
+        swap(LAMPORTS_PER_SOL / 10),
     );
-    transation_token_to_SOL.sign(&[&payer], recent_blockhash);
-    // let payer_balance_before = banks_client.get_balance(payer.pubkey()).await.unwrap();
-    let vault_balance_before = banks_client.get_account(vault.clone()).await.unwrap().expect("vault balance before");
-    let vault_ata_before = banks_client
-        .get_account(vault_ata.pubkey().clone())
-        .await
-        .unwrap()
-        .expect("vault_ata_before");
-    
-    let vault_ata_data_before = Account::unpack(&vault_ata_before.data).unwrap();
-
-    // let vault_data = Account::unpack(&vault_balance_before.data).unwrap();
-    banks_client.process_transaction(transation_token_to_SOL).await.unwrap();
-    
-    // let payer_balance_after = banks_client.get_balance(payer.pubkey()).await.unwrap();
-    let vault_balance_after = banks_client.get_account(vault.clone()).await.unwrap().expect("vault balance after");
-    
-    let vault_ata_after = banks_client
-        .get_account(vault_ata.pubkey().clone())
-        .await
-        .unwrap()
-        .expect("vault_ata_after");
-    
-    let vault_ata_data_after = Account::unpack(&vault_ata_after.data).unwrap();
-
-    // check if payer balance is increase
-    // ma::assert_lt!(
-    //     payer_balance_before,
-    //     payer_balance_after
-    // );
-
-    // check if payer balance is decrease
-    ma::assert_eq!(
-        1,
-        1
+    // 1 SOL pays out 10 tokens, within the 15 token cap
+    booth.process(&[first], &[]).await.unwrap();
+    let tokens_before = booth.token_balance(&payer_ata).await;
+
+    // another 10 tokens would exceed it: the swap fails and the booth stays open
+    assert_booth_error(
+        booth.process(&[oversized], &[]).await,
+        TokenTracingError::OutflowCapExceeded,
+    );
+    assert_eq!(booth.token_balance(&payer_ata).await, tokens_before);
+    assert!(!booth.exchange().await.paused);
+
+    // 5 more tokens settle and bring outflow up to the cap, pausing the booth
+    booth.process(&[last], &[]).await.unwrap();
+    assert_eq!(
+        booth.token_balance(&payer_ata).await,
+        tokens_before + 5 * 10_u64.pow(MINT_DECIMALS as u32)
     );
-}
\ No newline at end of file
+    assert!(booth.exchange().await.paused);
+
+    assert_booth_error(booth.process(&[after], &[]).await, TokenTracingError::BoothPaused);
+}
+
+#[tokio::test]
+async fn test_allowlist_gates_swaps() {
+    let mut booth = setup_booth().await;
+    let (program_id, payer, mint) = (booth.program_id, booth.payer.pubkey(), booth.mint.pubkey());
+    let allowlist = instruction::set_access_mode(&program_id, &payer, &mint, 0, AccessMode::Allowlist);
+    booth.process(&[allowlist], &[]).await.unwrap();
+
+    // not listed yet
+    let swap = instruction::exchange_sol_to_token(&program_id, &payer, &mint, 0, 1000);
+    assert_booth_error(booth.process(&[swap], &[]).await, TokenTracingError::WalletNotAllowed);
+
+    let allow = instruction::add_access_entry(&program_id, &payer, &payer, &mint, 0, &payer);
+    booth.process(&[allow], &[]).await.unwrap();
+    let swap = instruction::exchange_sol_to_token(&program_id, &payer, &mint, 0, 1001);
+    booth.process(&[swap], &[]).await.unwrap();
+
+    // the same entry blocks the wallet once the booth switches to a denylist
+    let denylist = instruction::set_access_mode(&program_id, &payer, &mint, 0, AccessMode::Denylist);
+    booth.process(&[denylist], &[]).await.unwrap();
+    let swap = instruction::exchange_sol_to_token(&program_id, &payer, &mint, 0, 1002);
+    assert_booth_error(booth.process(&[swap], &[]).await, TokenTracingError::WalletNotAllowed);
+
+    let disallow = instruction::remove_access_entry(&program_id, &payer, &mint, 0, &payer, &payer);
+    booth.process(&[disallow], &[]).await.unwrap();
+    let swap = instruction::exchange_sol_to_token(&program_id, &payer, &mint, 0, 1003);
+    booth.process(&[swap], &[]).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_attestation_gates_swaps() {
+    let mut booth = setup_booth().await;
+    let (program_id, payer, mint) = (booth.program_id, booth.payer.pubkey(), booth.mint.pubkey());
+    let attestor = Keypair::new();
+    let set_attestor =
+        instruction::set_attestor(&program_id, &payer, &mint, 0, Some(attestor.pubkey()));
+    booth.process(&[set_attestor], &[]).await.unwrap();
+    let swap = |amount| {
+        instruction::with_attestation(
+            &program_id,
+            instruction::exchange_sol_to_token(&program_id, &payer, &mint, 0, amount),
+            &attestor.pubkey(),
+        )
+    };
+
+    // no attestation yet
+    assert_booth_error(
+        booth.process(&[swap(1000)], &[]).await,
+        TokenTracingError::AttestationInvalid,
+    );
+
+    // an expired attestation is rejected
+    let expired = instruction::attest(&program_id, &payer, &attestor.pubkey(), &payer, 1);
+    booth.process(&[expired], &[&attestor]).await.unwrap();
+    assert_booth_error(
+        booth.process(&[swap(1001)], &[]).await,
+        TokenTracingError::AttestationInvalid,
+    );
+
+    let valid = instruction::attest(&program_id, &payer, &attestor.pubkey(), &payer, 0);
+    booth.process(&[valid], &[&attestor]).await.unwrap();
+    booth.process(&[swap(1002)], &[]).await.unwrap();
+
+    let revoke = instruction::revoke_attestation(&program_id, &attestor.pubkey(), &payer, &payer);
+    booth.process(&[revoke], &[&attestor]).await.unwrap();
+    assert_booth_error(
+        booth.process(&[swap(1003)], &[]).await,
+        TokenTracingError::AttestationInvalid,
+    );
+}
+
+#[tokio::test]
+async fn test_multisig_admin() {
+    let mut booth = setup_booth().await;
+    let (program_id, payer, mint, vault) = (
+        booth.program_id,
+        booth.payer.pubkey(),
+        booth.mint.pubkey(),
+        booth.vault,
+    );
+    let keys = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let set_multisig = instruction::set_multisig(
+        &program_id,
+        &payer,
+        &payer,
+        &mint,
+        0,
+        2,
+        keys.iter().map(|key| key.pubkey()).collect(),
+    );
+    booth.process(&[set_multisig], &[]).await.unwrap();
+    let (multisig, _) = find_multisig_address(&program_id, &vault);
+
+    // the old admin key no longer works on its own
+    let pause = instruction::set_paused(&program_id, &payer, &mint, 0, true);
+    assert_booth_error(booth.process(&[pause], &[]).await, TokenTracingError::InvalidOwner);
+
+    let pause = |signers: &[&Keypair]| {
+        instruction::with_multisig_signers(
+            instruction::set_paused(&program_id, &multisig, &mint, 0, true),
+            &multisig,
+            &signers.iter().map(|key| key.pubkey()).collect::<Vec<_>>(),
+        )
+    };
+    assert_booth_error(
+        booth.process(&[pause(&[&keys[0]])], &[&keys[0]]).await,
+        TokenTracingError::NotEnoughSigners,
+    );
+    booth
+        .process(&[pause(&[&keys[0], &keys[2]])], &[&keys[0], &keys[2]])
+        .await
+        .unwrap();
+
+    let receiver = Keypair::new();
+    let close = |signers: &[&Keypair]| {
+        instruction::with_multisig_signers(
+            instruction::close_exchange(&program_id, &multisig, &receiver.pubkey(), &mint, 0),
+            &multisig,
+            &signers.iter().map(|key| key.pubkey()).collect::<Vec<_>>(),
+        )
+    };
+    let receiver_ata = create_associated_token_account(&payer, &receiver.pubkey(), &mint, &spl_token::id());
+    booth.process(&[receiver_ata], &[]).await.unwrap();
+    assert_booth_error(
+        booth.process(&[close(&[&keys[1], &keys[2]])], &[&keys[1], &keys[2]]).await,
+        TokenTracingError::LiquidityOwedToLp,
+    );
+
+    // the LP holder takes the reserves out even while paused, then the
+    // multisig closes the booth
+    let remove = instruction::remove_liquidity(&program_id, &payer, &mint, 0, LAMPORTS_PER_SOL);
+    booth
+        .process(&[remove, close(&[&keys[0], &keys[1]])], &[&keys[0], &keys[1]])
+        .await
+        .unwrap();
+    let payer_ata = booth.payer_ata();
+    assert_eq!(booth.token_balance(&payer_ata).await, 200 * 10_u64.pow(MINT_DECIMALS as u32));
+    assert_eq!(booth.banks_client.get_account(vault).await.unwrap(), None);
+    ma::assert_gt!(booth.lamports(&receiver.pubkey()).await, 0);
+}
+
+#[tokio::test]
+async fn test_timelocked_changes() {
+    let mut booth = setup_booth().await;
+    let (program_id, payer, mint, vault) = (
+        booth.program_id,
+        booth.payer.pubkey(),
+        booth.mint.pubkey(),
+        booth.vault,
+    );
+    let propose = |rate, timelock_slots| {
+        instruction::propose_change(
+            &program_id,
+            &payer,
+            &mint,
+            0,
+            rate,
+            30,
+            CurveType::ConstantPrice,
+            timelock_slots,
+        )
+    };
+
+    // a fresh booth still waits the minimum timelock
+    booth.process(&[propose(10, 1_000_000)], &[]).await.unwrap();
+    let execute = instruction::execute_change(&program_id, &mint, 0);
+    assert_booth_error(
+        booth.process(&[execute.clone()], &[]).await,
+        TokenTracingError::TimelockNotExpired,
+    );
+    booth.advance_slots(MIN_TIMELOCK_SLOTS).await;
+    booth.process(&[execute], &[]).await.unwrap();
+    let exchange = booth.exchange().await;
+    assert_eq!((exchange.fee_bps, exchange.timelock_slots), (30, 1_000_000));
+    assert!(exchange.pending_change().is_none());
+
+    // now changes, rate changes included, wait the booth's longer timelock
+    let set_rate = instruction::set_rate(&program_id, &payer, &mint, 0, 20);
+    booth.process(&[set_rate], &[]).await.unwrap();
+    let pending = *booth.exchange().await.pending_change().unwrap();
+    assert_eq!((pending.rate, pending.fee_bps, pending.timelock_slots), (20, 30, 1_000_000));
+    booth.process(&[propose(20, 0)], &[]).await.unwrap();
+    booth.advance_slots(MIN_TIMELOCK_SLOTS).await;
+    let execute = instruction::execute_change(&program_id, &mint, 0);
+    assert_booth_error(
+        booth.process(&[execute], &[]).await,
+        TokenTracingError::TimelockNotExpired,
+    );
+
+    booth
+        .process(&[instruction::cancel_change(&program_id, &payer, &mint, 0)], &[])
+        .await
+        .unwrap();
+    let account = booth.banks_client.get_account(vault).await.unwrap().unwrap();
+    let exchange = ExchangeAccount::unpack(&account.data).unwrap();
+    assert_eq!(exchange.rate, 10);
+    assert!(exchange.pending_change().is_none());
+}
+
+#[tokio::test]
+async fn test_multiple_booths_per_mint() {
+    let mut booth = setup_booth().await;
+    let (program_id, payer, mint) = (booth.program_id, booth.payer.pubkey(), booth.mint.pubkey());
+    let (partner_vault, _) = find_booth_address(&program_id, &mint, 7);
+    assert_ne!(partner_vault, booth.vault);
+
+    booth
+        .process(
+            &[
+                instruction::initialize(&program_id, &payer, &mint, 7),
+                create_associated_token_account(&payer, &partner_vault, &mint, &spl_token::id()),
+                instruction::deposit(&program_id, &payer, &mint, 7, 0, 10 * 10_u64.pow(MINT_DECIMALS as u32)),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+    booth
+        .apply_change(instruction::set_rate(&program_id, &payer, &mint, 7, 20), 7)
+        .await;
+
+    // each booth prices with its own rate out of its own liquidity
+    let payer_ata = booth.payer_ata();
+    let tokens_before = booth.token_balance(&payer_ata).await;
+    booth
+        .process(
+            &[
+                instruction::exchange_sol_to_token(&program_id, &payer, &mint, 0, 1000),
+                instruction::exchange_sol_to_token(&program_id, &payer, &mint, 7, 1000),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+    assert_eq!(booth.token_balance(&payer_ata).await, tokens_before + 10_000 + 20_000);
+    let partner_ata = get_associated_token_address(&partner_vault, &mint);
+    assert_eq!(
+        booth.token_balance(&partner_ata).await,
+        10 * 10_u64.pow(MINT_DECIMALS as u32) - 20_000
+    );
+
+    let account = booth.banks_client.get_account(partner_vault).await.unwrap().unwrap();
+    assert_eq!(ExchangeAccount::unpack(&account.data).unwrap().booth_id, 7);
+}
+
+#[tokio::test]
+async fn test_registry_lists_booths() {
+    let mut booth = setup_booth().await;
+    let (program_id, payer, mint) = (booth.program_id, booth.payer.pubkey(), booth.mint.pubkey());
+    booth
+        .process(&[instruction::initialize(&program_id, &payer, &mint, 3)], &[])
+        .await
+        .unwrap();
+
+    let (registry, _) = find_registry_address(&program_id);
+    let data = booth.banks_client.get_account(registry).await.unwrap().unwrap().data;
+    assert_eq!(BoothRegistry::unpack(&data).unwrap().count, 2);
+    assert_eq!(data.len(), BoothRegistry::space(2));
+    let entries = BoothRegistry::entries(&data, 0, 10).unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].vault, booth.vault);
+    assert_eq!(entries[0].admin, payer);
+    assert_eq!(entries[1].mint, mint);
+    assert_eq!(entries[1].booth_id, 3);
+    assert_eq!(entries[1].vault, find_booth_address(&program_id, &mint, 3).0);
+
+    // pages past the end are empty rather than an error
+    assert_eq!(BoothRegistry::entries(&data, 1, 1).unwrap(), entries[1..]);
+    assert!(BoothRegistry::entries(&data, 2, 10).unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_booth_metadata() {
+    let mut booth = setup_booth().await;
+    let (program_id, payer, mint) = (booth.program_id, booth.payer.pubkey(), booth.mint.pubkey());
+    let set_metadata = |admin: &Pubkey, name: &str| {
+        instruction::set_metadata(
+            &program_id,
+            &payer,
+            admin,
+            &mint,
+            0,
+            name,
+            "BTH",
+            "A booth",
+            "https://example.com/booth.json",
+        )
+    };
+
+    let stranger = Keypair::new();
+    let not_admin = set_metadata(&stranger.pubkey(), "Mine");
+    assert_booth_error(
+        booth.process(&[not_admin], &[&stranger]).await,
+        TokenTracingError::InvalidOwner,
+    );
+    let too_long = set_metadata(&payer, &"x".repeat(MAX_NAME_LEN + 1));
+    assert_booth_error(
+        booth.process(&[too_long], &[]).await,
+        TokenTracingError::MetadataTooLong,
+    );
+
+    booth.process(&[set_metadata(&payer, "First")], &[]).await.unwrap();
+    booth.process(&[set_metadata(&payer, "Booth")], &[]).await.unwrap();
+
+    let (metadata, _) = find_metadata_address(&program_id, &booth.vault);
+    let account = booth.banks_client.get_account(metadata).await.unwrap().unwrap();
+    let stored = BoothMetadata::unpack(&account.data).unwrap();
+    assert_eq!(stored.vault, booth.vault);
+    assert_eq!(stored.name, "Booth");
+    assert_eq!(stored.symbol, "BTH");
+    assert_eq!(stored.uri, "https://example.com/booth.json");
+
+    // the registry entry points at it
+    let (registry, _) = find_registry_address(&program_id);
+    let data = booth.banks_client.get_account(registry).await.unwrap().unwrap().data;
+    assert_eq!(BoothRegistry::entries(&data, 0, 1).unwrap()[0].metadata, metadata);
+}
+
+#[tokio::test]
+async fn test_close_exchange_unregisters_booth() {
+    let mut booth = setup_booth().await;
+    let (program_id, payer, mint, vault) = (
+        booth.program_id,
+        booth.payer.pubkey(),
+        booth.mint.pubkey(),
+        booth.vault,
+    );
+    let set_metadata = instruction::set_metadata(
+        &program_id,
+        &payer,
+        &payer,
+        &mint,
+        0,
+        "Closing",
+        "BTH",
+        "A booth",
+        "https://example.com/booth.json",
+    );
+    booth
+        .process(
+            &[set_metadata, instruction::initialize(&program_id, &payer, &mint, 3)],
+            &[],
+        )
+        .await
+        .unwrap();
+
+    let remove = instruction::remove_liquidity(&program_id, &payer, &mint, 0, LAMPORTS_PER_SOL);
+    let close = instruction::close_exchange(&program_id, &payer, &payer, &mint, 0);
+    booth.process(&[remove, close], &[]).await.unwrap();
+    assert_eq!(booth.banks_client.get_account(vault).await.unwrap(), None);
+    let (metadata, _) = find_metadata_address(&program_id, &vault);
+    assert_eq!(booth.banks_client.get_account(metadata).await.unwrap(), None);
+
+    // the last booth takes the closed booth's place, and the entry's rent is refunded
+    let (registry, _) = find_registry_address(&program_id);
+    let account = booth.banks_client.get_account(registry).await.unwrap().unwrap();
+    assert_eq!(account.data.len(), BoothRegistry::space(1));
+    let rent = booth.banks_client.get_rent().await.unwrap();
+    assert_eq!(account.lamports, rent.minimum_balance(BoothRegistry::space(1)));
+    let entries = BoothRegistry::entries(&account.data, 0, 10).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].booth_id, 3);
+}
+
+#[tokio::test]
+async fn test_flash_loans() {
+    let (receiver, stingy_receiver) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut booth = setup_booth_with(|program_test| {
+        program_test.add_program("flash_receiver", receiver, processor!(repay_flash_loan));
+        program_test.add_program(
+            "stingy_flash_receiver",
+            stingy_receiver,
+            processor!(repay_flash_loan_without_fee),
+        );
+    })
+    .await;
+    let (program_id, payer, mint) = (booth.program_id, booth.payer.pubkey(), booth.mint.pubkey());
+    let (vault, payer_ata, vault_ata) = (booth.vault, booth.payer_ata(), booth.vault_ata());
+    let flash_loan = |receiver: &Pubkey, amount, side| {
+        let destination = match side {
+            LoanSide::Sol => payer,
+            LoanSide::Token => payer_ata,
+        };
+        instruction::flash_loan(
+            &program_id,
+            &payer,
+            &mint,
+            0,
+            &destination,
+            receiver,
+            vec![
+                AccountMeta::new(payer, true),
+                AccountMeta::new(payer_ata, false),
+                AccountMeta::new(vault, false),
+                AccountMeta::new(vault_ata, false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new_readonly(solana_program::system_program::id(), false),
+            ],
+            amount,
+            side,
+        )
+    };
+
+    let lamports = LAMPORTS_PER_SOL / 2;
+    let vault_lamports = booth.lamports(&vault).await;
+    booth
+        .process(&[flash_loan(&receiver, lamports, LoanSide::Sol)], &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        booth.lamports(&vault).await,
+        vault_lamports + flash_loan_fee(lamports).unwrap()
+    );
+
+    let tokens = 50 * 10_u64.pow(MINT_DECIMALS as u32);
+    let vault_tokens = booth.token_balance(&vault_ata).await;
+    booth
+        .process(&[flash_loan(&receiver, tokens, LoanSide::Token)], &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        booth.token_balance(&vault_ata).await,
+        vault_tokens + flash_loan_fee(tokens).unwrap()
+    );
+
+    // returning the principal alone fails the whole loan
+    for side in [LoanSide::Sol, LoanSide::Token] {
+        let short = flash_loan(&stingy_receiver, 1000, side);
+        assert_booth_error(
+            booth.process(&[short], &[]).await,
+            TokenTracingError::FlashLoanNotRepaid,
+        );
+    }
+    // the vault keeps its rent-exempt minimum
+    assert_booth_error(
+        booth
+            .process(&[flash_loan(&receiver, vault_lamports, LoanSide::Sol)], &[])
+            .await,
+        TokenTracingError::InsufficientFunds,
+    );
+    // nor lend to itself, which could swap against the lent reserves
+    assert_booth_error(
+        booth
+            .process(&[flash_loan(&program_id, 1000, LoanSide::Token)], &[])
+            .await,
+        TokenTracingError::InvalidAccountAddress,
+    );
+}
+
+#[tokio::test]
+async fn test_limit_orders() {
+    let mut booth = setup_booth().await;
+    let (program_id, payer, mint) = (booth.program_id, booth.payer.pubkey(), booth.mint.pubkey());
+    let (payer_ata, vault_ata) = (booth.payer_ata(), booth.vault_ata());
+    let cranker = Keypair::new();
+    let fund_cranker = system_instruction::transfer(&payer, &cranker.pubkey(), LAMPORTS_PER_SOL);
+    booth.process(&[fund_cranker], &[]).await.unwrap();
+
+    // buying tokens at 15 per lamport, and selling them at 20 per lamport
+    let buy = instruction::place_limit_order(
+        &program_id,
+        &payer,
+        &mint,
+        0,
+        1,
+        OrderSide::SolToToken,
+        1000,
+        15_000,
+    );
+    let sell = instruction::place_limit_order(
+        &program_id,
+        &payer,
+        &mint,
+        0,
+        2,
+        OrderSide::TokenToSol,
+        10_000,
+        500,
+    );
+    let payer_tokens = booth.token_balance(&payer_ata).await;
+    booth.process(&[buy, sell], &[]).await.unwrap();
+    assert_eq!(booth.token_balance(&payer_ata).await, payer_tokens - 10_000);
+    let (buy_order, _) = find_limit_order_address(&program_id, &booth.vault, &payer, 1);
+    let account = booth.banks_client.get_account(buy_order).await.unwrap().unwrap();
+    let order = LimitOrder::unpack(&account.data).unwrap();
+    assert_eq!((order.amount, order.min_out), (1000, 15_000));
+
+    // at rate 10 only the sell order fills
+    let fill = |cranker: &Pubkey| {
+        instruction::fill_limit_orders(&program_id, cranker, &mint, 0, &[(payer, 1), (payer, 2)], None)
+    };
+    let vault_tokens = booth.token_balance(&vault_ata).await;
+    let cranker_lamports = booth.lamports(&cranker.pubkey()).await;
+    booth.process(&[fill(&cranker.pubkey())], &[&cranker]).await.unwrap();
+    assert_eq!(booth.token_balance(&vault_ata).await, vault_tokens + 10_000);
+    assert_eq!(
+        booth.lamports(&cranker.pubkey()).await,
+        cranker_lamports + LIMIT_ORDER_CRANK_REWARD
+    );
+    let (sell_order, _) = find_limit_order_address(&program_id, &booth.vault, &payer, 2);
+    assert_eq!(booth.banks_client.get_account(sell_order).await.unwrap(), None);
+
+    let set_rate = instruction::set_rate(&program_id, &payer, &mint, 0, 20);
+    booth.apply_change(set_rate, 0).await;
+    let payer_tokens = booth.token_balance(&payer_ata).await;
+    let fill = instruction::fill_limit_orders(&program_id, &cranker.pubkey(), &mint, 0, &[(payer, 1)], None);
+    booth.process(&[fill], &[&cranker]).await.unwrap();
+    assert_eq!(booth.token_balance(&payer_ata).await, payer_tokens + 20_000);
+    assert_eq!(booth.banks_client.get_account(buy_order).await.unwrap(), None);
+
+    // cancelling hands the escrowed tokens back, from an escrow someone
+    // else created ahead of the order
+    let (cancelled, _) = find_limit_order_address(&program_id, &booth.vault, &payer, 3);
+    let escrow = create_associated_token_account(&payer, &cancelled, &mint, &spl_token::id());
+    booth.process(&[escrow], &[]).await.unwrap();
+    let sell = instruction::place_limit_order(
+        &program_id,
+        &payer,
+        &mint,
+        0,
+        3,
+        OrderSide::TokenToSol,
+        5000,
+        LAMPORTS_PER_SOL,
+    );
+    booth.process(&[sell], &[]).await.unwrap();
+    let stranger = Keypair::new();
+    let mut not_owner = instruction::cancel_limit_order(&program_id, &payer, &mint, 0, 3);
+    not_owner.accounts[0] = AccountMeta::new(stranger.pubkey(), true);
+    assert_booth_error(
+        booth.process(&[not_owner], &[&stranger]).await,
+        TokenTracingError::InvalidOwner,
+    );
+    let cancel = instruction::cancel_limit_order(&program_id, &payer, &mint, 0, 3);
+    booth.process(&[cancel], &[]).await.unwrap();
+    assert_eq!(booth.token_balance(&payer_ata).await, payer_tokens + 20_000);
+    assert_eq!(booth.banks_client.get_account(cancelled).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn test_limit_order_fills_check_owner() {
+    let mut booth = setup_booth().await;
+    let (program_id, payer, mint, vault) = (
+        booth.program_id,
+        booth.payer.pubkey(),
+        booth.mint.pubkey(),
+        booth.vault,
+    );
+    let payer_ata = booth.payer_ata();
+    let crankers = [Keypair::new(), Keypair::new()];
+    booth
+        .process(
+            &[
+                system_instruction::transfer(&payer, &crankers[0].pubkey(), LAMPORTS_PER_SOL),
+                system_instruction::transfer(&payer, &crankers[1].pubkey(), LAMPORTS_PER_SOL),
+                instruction::set_wallet_limit(&program_id, &payer, &mint, 0, LAMPORTS_PER_SOL, 86_400),
+                instruction::set_access_mode(&program_id, &payer, &mint, 0, AccessMode::Allowlist),
+                instruction::add_access_entry(&program_id, &payer, &payer, &mint, 0, &payer),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+    let fill = |cranker: &Pubkey| {
+        instruction::fill_limit_orders(&program_id, cranker, &mint, 0, &[(payer, 1)], None)
+    };
+    let (order, _) = find_limit_order_address(&program_id, &vault, &payer, 1);
+
+    // placing the order does not count toward the wallet limit, so a swap still fits
+    let place = instruction::place_limit_order(
+        &program_id,
+        &payer,
+        &mint,
+        0,
+        1,
+        OrderSide::SolToToken,
+        6 * LAMPORTS_PER_SOL / 10,
+        0,
+    );
+    let swap = instruction::exchange_sol_to_token(&program_id, &payer, &mint, 0, LAMPORTS_PER_SOL / 2);
+    booth.process(&[place, swap], &[]).await.unwrap();
+
+    // filling it would, so the order waits
+    booth
+        .process(&[fill(&crankers[0].pubkey())], &[&crankers[0]])
+        .await
+        .unwrap();
+    assert!(booth.banks_client.get_account(order).await.unwrap().is_some());
+
+    // a window later the limit has room, but the owner is off the allowlist
+    booth.advance_clock(2 * 86_400).await;
+    let disallow = instruction::remove_access_entry(&program_id, &payer, &mint, 0, &payer, &payer);
+    booth.process(&[disallow], &[]).await.unwrap();
+    booth.process(&[fill(&payer)], &[]).await.unwrap();
+    assert!(booth.banks_client.get_account(order).await.unwrap().is_some());
+
+    let allow = instruction::add_access_entry(&program_id, &payer, &payer, &mint, 0, &payer);
+    booth.process(&[allow], &[]).await.unwrap();
+    let payer_tokens = booth.token_balance(&payer_ata).await;
+    booth
+        .process(&[fill(&crankers[1].pubkey())], &[&crankers[1]])
+        .await
+        .unwrap();
+    assert_eq!(booth.banks_client.get_account(order).await.unwrap(), None);
+    assert_eq!(
+        booth.token_balance(&payer_ata).await,
+        payer_tokens + 6 * 10_u64.pow(MINT_DECIMALS as u32)
+    );
+    let (wallet_stats, _) = find_wallet_stats_address(&program_id, &vault, &payer);
+    let account = booth.banks_client.get_account(wallet_stats).await.unwrap().unwrap();
+    let stats = WalletStats::unpack(&account.data).unwrap();
+    assert_eq!(stats.window_volume, 6 * LAMPORTS_PER_SOL / 10);
+    assert_eq!(stats.total_volume, 11 * LAMPORTS_PER_SOL / 10);
+}
+
+#[tokio::test]
+async fn test_cancelled_orders_add_no_volume() {
+    let mut booth = setup_booth().await;
+    let (program_id, payer, mint, vault) = (
+        booth.program_id,
+        booth.payer.pubkey(),
+        booth.mint.pubkey(),
+        booth.vault,
+    );
+    let limit = instruction::set_wallet_limit(&program_id, &payer, &mint, 0, LAMPORTS_PER_SOL, 86_400);
+    let swap = instruction::exchange_sol_to_token(&program_id, &payer, &mint, 0, 1000);
+    booth.process(&[limit, swap], &[]).await.unwrap();
+    let (wallet_stats, _) = find_wallet_stats_address(&program_id, &vault, &payer);
+
+    // orders count once they swap, so closing them unfilled leaves the volume alone
+    let place = instruction::place_limit_order(
+        &program_id,
+        &payer,
+        &mint,
+        0,
+        1,
+        OrderSide::SolToToken,
+        LAMPORTS_PER_SOL / 2,
+        0,
+    );
+    let cancel = instruction::cancel_limit_order(&program_id, &payer, &mint, 0, 1);
+    let create = instruction::create_dca(&program_id, &payer, &mint, 0, 1, 1000, 3600, LAMPORTS_PER_SOL / 2);
+    let close = instruction::close_dca(&program_id, &payer, &mint, 0, 1);
+    booth.process(&[place, cancel, create, close], &[]).await.unwrap();
+    let account = booth.banks_client.get_account(wallet_stats).await.unwrap().unwrap();
+    let stats = WalletStats::unpack(&account.data).unwrap();
+    assert_eq!((stats.window_volume, stats.total_volume), (1000, 1000));
+}
+
+#[tokio::test]
+async fn test_dca_orders() {
+    let mut booth = setup_booth().await;
+    let (program_id, payer, mint) = (booth.program_id, booth.payer.pubkey(), booth.mint.pubkey());
+    let payer_ata = booth.payer_ata();
+    let create = instruction::create_dca(&program_id, &payer, &mint, 0, 1, 1000, 3600, 2500);
+    booth.process(&[create], &[]).await.unwrap();
+
+    // the first period is due straight away
+    let execute = instruction::execute_dca(&program_id, &payer, &payer, &mint, 0, 1, None);
+    let payer_tokens = booth.token_balance(&payer_ata).await;
+    booth.process(&[execute.clone()], &[]).await.unwrap();
+    assert_eq!(booth.token_balance(&payer_ata).await, payer_tokens + 10_000);
+    let (dca, _) = find_dca_address(&program_id, &booth.vault, &payer, 1);
+    let account = booth.banks_client.get_account(dca).await.unwrap().unwrap();
+    let dca_order = DcaOrder::unpack(&account.data).unwrap();
+    assert_eq!(dca_order.amount_per_period, 1000);
+    let rent = solana_program::rent::Rent::default().minimum_balance(account.data.len());
+    assert_eq!(account.lamports, rent + 1500);
+
+    // the next one waits an interval
+    assert_booth_error(
+        booth.process(&[execute.clone(), execute], &[]).await,
+        TokenTracingError::DcaNotDue,
+    );
+
+    let close = instruction::close_dca(&program_id, &payer, &mint, 0, 1);
+    booth.process(&[close], &[]).await.unwrap();
+    assert_eq!(booth.banks_client.get_account(dca).await.unwrap(), None);
+
+    // an order closes itself once its escrow runs out
+    let create = instruction::create_dca(&program_id, &payer, &mint, 0, 2, 1000, 60, 600);
+    booth.process(&[create], &[]).await.unwrap();
+    let payer_tokens = booth.token_balance(&payer_ata).await;
+    let execute = instruction::execute_dca(&program_id, &payer, &payer, &mint, 0, 2, None);
+    booth.process(&[execute], &[]).await.unwrap();
+    assert_eq!(booth.token_balance(&payer_ata).await, payer_tokens + 6000);
+    let (dca, _) = find_dca_address(&program_id, &booth.vault, &payer, 2);
+    assert_eq!(booth.banks_client.get_account(dca).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn test_dca_executions_check_owner() {
+    let mut booth = setup_booth().await;
+    let (program_id, payer, mint, vault) = (
+        booth.program_id,
+        booth.payer.pubkey(),
+        booth.mint.pubkey(),
+        booth.vault,
+    );
+    let crankers = [Keypair::new(), Keypair::new(), Keypair::new()];
+    booth
+        .process(
+            &[
+                system_instruction::transfer(&payer, &crankers[0].pubkey(), LAMPORTS_PER_SOL),
+                system_instruction::transfer(&payer, &crankers[1].pubkey(), LAMPORTS_PER_SOL),
+                system_instruction::transfer(&payer, &crankers[2].pubkey(), LAMPORTS_PER_SOL),
+                instruction::set_wallet_limit(&program_id, &payer, &mint, 0, LAMPORTS_PER_SOL, 86_400),
+                instruction::set_access_mode(&program_id, &payer, &mint, 0, AccessMode::Allowlist),
+                instruction::add_access_entry(&program_id, &payer, &payer, &mint, 0, &payer),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+    let period = 6 * LAMPORTS_PER_SOL / 10;
+    let create = instruction::create_dca(&program_id, &payer, &mint, 0, 1, period, 60, 2 * period);
+    booth.process(&[create], &[]).await.unwrap();
+    let (wallet_stats, _) = find_wallet_stats_address(&program_id, &vault, &payer);
+    let window_volume = |account: Option<solana_sdk::account::Account>| {
+        WalletStats::unpack(&account.unwrap().data).unwrap().window_volume
+    };
+    let execute =
+        |cranker: &Pubkey| instruction::execute_dca(&program_id, cranker, &payer, &mint, 0, 1, None);
+
+    // the deposit is not counted, only what each period swaps
+    let account = booth.banks_client.get_account(wallet_stats).await.unwrap();
+    assert_eq!(window_volume(account), 0);
+    booth
+        .process(&[execute(&crankers[0].pubkey())], &[&crankers[0]])
+        .await
+        .unwrap();
+    let account = booth.banks_client.get_account(wallet_stats).await.unwrap();
+    assert_eq!(window_volume(account), period);
+
+    // the second period would go over the limit
+    booth.advance_clock(60).await;
+    assert_booth_error(
+        booth.process(&[execute(&payer)], &[]).await,
+        TokenTracingError::LimitExceeded,
+    );
+
+    // a window later it fits, but the owner is off the allowlist
+    booth.advance_clock(86_400).await;
+    let disallow = instruction::remove_access_entry(&program_id, &payer, &mint, 0, &payer, &payer);
+    booth.process(&[disallow], &[]).await.unwrap();
+    assert_booth_error(
+        booth
+            .process(&[execute(&crankers[1].pubkey())], &[&crankers[1]])
+            .await,
+        TokenTracingError::WalletNotAllowed,
+    );
+
+    let allow = instruction::add_access_entry(&program_id, &payer, &payer, &mint, 0, &payer);
+    booth.process(&[allow], &[]).await.unwrap();
+    booth
+        .process(&[execute(&crankers[2].pubkey())], &[&crankers[2]])
+        .await
+        .unwrap();
+    let (dca, _) = find_dca_address(&program_id, &vault, &payer, 1);
+    assert_eq!(booth.banks_client.get_account(dca).await.unwrap(), None);
+    let account = booth.banks_client.get_account(wallet_stats).await.unwrap();
+    assert_eq!(window_volume(account), period);
+}
+
+#[tokio::test]
+async fn test_batch_SOL_to_token() {
+    let mut booth = setup_booth().await;
+    let (program_id, payer, mint) = (booth.program_id, booth.payer.pubkey(), booth.mint.pubkey());
+    let (payer_ata, vault) = (booth.payer_ata(), booth.vault);
+    let employee = Keypair::new();
+    let employee_ata = get_associated_token_address(&employee.pubkey(), &mint);
+    let create_ata = create_associated_token_account(&payer, &employee.pubkey(), &mint, &spl_token::id());
+    booth.process(&[create_ata], &[]).await.unwrap();
+    let vault_before = booth.lamports(&vault).await;
+    let tokens_before = booth.token_balance(&payer_ata).await;
+
+    let batch = instruction::batch_exchange_sol_to_token(
+        &program_id,
+        &payer,
+        &mint,
+        0,
+        &[(employee_ata, 1000), (payer_ata, 500)],
+        None,
+    );
+    booth.process(&[batch], &[]).await.unwrap();
+    assert_eq!(booth.lamports(&vault).await, vault_before + 1500);
+    assert_eq!(booth.token_balance(&employee_ata).await, 10_000);
+    assert_eq!(booth.token_balance(&payer_ata).await, tokens_before + 5000);
+
+    // one bad recipient fails the whole batch
+    let batch = instruction::batch_exchange_sol_to_token(
+        &program_id,
+        &payer,
+        &mint,
+        0,
+        &[(employee_ata, 1000), (Pubkey::new_unique(), 1000)],
+        None,
+    );
+    assert_booth_error(
+        booth.process(&[batch], &[]).await,
+        TokenTracingError::InvalidSPLTokenAccount,
+    );
+    assert_eq!(booth.lamports(&vault).await, vault_before + 1500);
+    assert_eq!(booth.token_balance(&employee_ata).await, 10_000);
+}
+
+#[tokio::test]
+async fn test_referral_fees() {
+    let mut booth = setup_booth().await;
+    let (program_id, payer, mint, vault) = (
+        booth.program_id,
+        booth.payer.pubkey(),
+        booth.mint.pubkey(),
+        booth.vault,
+    );
+    let fee = instruction::propose_change(&program_id, &payer, &mint, 0, 10, 30, CurveType::ConstantPrice, 0);
+    let share = instruction::set_referral_share(&program_id, &payer, &mint, 0, 5000);
+    booth.apply_change(fee, 0).await;
+    booth.process(&[share], &[]).await.unwrap();
+
+    let referrer = Keypair::new().pubkey();
+    let referrer_ata = get_associated_token_address(&referrer, &mint);
+    booth
+        .process(
+            &[
+                system_instruction::transfer(&payer, &referrer, LAMPORTS_PER_SOL),
+                create_associated_token_account(&payer, &referrer, &mint, &spl_token::id()),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+
+    // half of the 30 bps fee on 10_000 tokens goes to the referrer
+    let payer_ata = booth.payer_ata();
+    let tokens_before = booth.token_balance(&payer_ata).await;
+    let swap = instruction::exchange_sol_to_token(&program_id, &payer, &mint, 0, 1000);
+    booth
+        .process(&[instruction::with_referrer(&program_id, swap, &referrer)], &[])
+        .await
+        .unwrap();
+    assert_eq!(booth.token_balance(&payer_ata).await, tokens_before + 9970);
+    assert_eq!(booth.token_balance(&referrer_ata).await, 15);
+
+    let swap = instruction::exchange_token_to_sol(&program_id, &payer, &mint, 0, 100_000);
+    booth
+        .process(&[instruction::with_referrer(&program_id, swap, &referrer)], &[])
+        .await
+        .unwrap();
+    assert_eq!(booth.lamports(&referrer).await, LAMPORTS_PER_SOL + 15);
+
+    let (referrer_stats, _) = find_referrer_stats_address(&program_id, &vault, &referrer);
+    let account = booth.banks_client.get_account(referrer_stats).await.unwrap().unwrap();
+    let stats = ReferrerStats::unpack(&account.data).unwrap();
+    assert_eq!((stats.swaps, stats.lamports_earned, stats.tokens_earned), (2, 15, 15));
+
+    // a payer cannot refer itself
+    let swap = instruction::exchange_sol_to_token(&program_id, &payer, &mint, 0, 1000);
+    let self_referred = instruction::with_referrer(&program_id, swap, &payer);
+    assert_booth_error(
+        booth.process(&[self_referred], &[]).await,
+        TokenTracingError::InvalidAccountAddress,
+    );
+
+    // nor does a batch pay one
+    let recipients = [(payer_ata, 1000)];
+    let mut batch = instruction::batch_exchange_sol_to_token(&program_id, &payer, &mint, 0, &recipients, None);
+    let recipient = batch.accounts.pop().unwrap();
+    let mut referred = instruction::with_referrer(&program_id, batch, &referrer);
+    referred.accounts.push(recipient);
+    assert_booth_error(
+        booth.process(&[referred], &[]).await,
+        TokenTracingError::InvalidAccountAddress,
+    );
+}
+
+#[tokio::test]
+async fn test_fee_tiers() {
+    let mut booth = setup_booth().await;
+    let (program_id, payer, mint, vault) = (
+        booth.program_id,
+        booth.payer.pubkey(),
+        booth.mint.pubkey(),
+        booth.vault,
+    );
+    let fee = instruction::propose_change(&program_id, &payer, &mint, 0, 10, 30, CurveType::ConstantPrice, 0);
+    let tiers = [
+        FeeTier { min_volume: 1000, fee_bps: 10 },
+        FeeTier { min_volume: 1_000_000, fee_bps: 0 },
+    ];
+    let set_tiers = instruction::set_fee_tiers(&program_id, &payer, &mint, 0, &tiers);
+    booth.apply_change(fee, 0).await;
+    booth.process(&[set_tiers], &[]).await.unwrap();
+
+    // the first swap pays the booth fee, the second the first tier's
+    let payer_ata = booth.payer_ata();
+    let tokens_before = booth.token_balance(&payer_ata).await;
+    let swap = instruction::exchange_sol_to_token(&program_id, &payer, &mint, 0, 1000);
+    booth.process(&[swap], &[]).await.unwrap();
+    assert_eq!(booth.token_balance(&payer_ata).await, tokens_before + 9970);
+    let swap = instruction::exchange_sol_to_token(&program_id, &payer, &mint, 0, 2000);
+    booth.process(&[swap], &[]).await.unwrap();
+    assert_eq!(booth.token_balance(&payer_ata).await, tokens_before + 9970 + 19_980);
+
+    let (wallet_stats, _) = find_wallet_stats_address(&program_id, &vault, &payer);
+    let account = booth.banks_client.get_account(wallet_stats).await.unwrap().unwrap();
+    assert_eq!(WalletStats::unpack(&account.data).unwrap().total_volume, 3000);
+
+    // limit order fills and DCA periods price at the owner's tier too
+    let place = instruction::place_limit_order(
+        &program_id,
+        &payer,
+        &mint,
+        0,
+        1,
+        OrderSide::SolToToken,
+        1000,
+        0,
+    );
+    let fill = instruction::fill_limit_orders(&program_id, &payer, &mint, 0, &[(payer, 1)], None);
+    let create = instruction::create_dca(&program_id, &payer, &mint, 0, 1, 1000, 3600, 1000);
+    let execute = instruction::execute_dca(&program_id, &payer, &payer, &mint, 0, 1, None);
+    let tokens_before = booth.token_balance(&payer_ata).await;
+    booth.process(&[place, fill], &[]).await.unwrap();
+    assert_eq!(booth.token_balance(&payer_ata).await, tokens_before + 9990);
+    booth.process(&[create, execute], &[]).await.unwrap();
+    assert_eq!(booth.token_balance(&payer_ata).await, tokens_before + 2 * 9990);
+    let account = booth.banks_client.get_account(wallet_stats).await.unwrap().unwrap();
+    assert_eq!(WalletStats::unpack(&account.data).unwrap().total_volume, 5000);
+
+    // tiers may only get cheaper as volume grows
+    let pricier = [
+        FeeTier { min_volume: 1000, fee_bps: 10 },
+        FeeTier { min_volume: 2000, fee_bps: 20 },
+    ];
+    let set_tiers = instruction::set_fee_tiers(&program_id, &payer, &mint, 0, &pricier);
+    assert_booth_error(booth.process(&[set_tiers], &[]).await, TokenTracingError::InvalidFee);
+}
+
+/// Create a reward mint and mint the payer one whole token of it.
+async fn setup_reward_mint(booth: &mut Booth) -> Pubkey {
+    let payer = booth.payer.pubkey();
+    let reward_mint = Keypair::new();
+    let recent_blockhash = booth.banks_client.get_latest_blockhash().await.unwrap();
+    create_and_initialize_mint(
+        &mut booth.banks_client,
+        recent_blockhash,
+        &booth.payer,
+        &booth.payer,
+        &reward_mint,
+        &spl_token::id(),
+        &MINT_DECIMALS,
+    )
+    .await
+    .unwrap();
+    let reward_mint = reward_mint.pubkey();
+    let payer_reward_ata = get_associated_token_address(&payer, &reward_mint);
+    let create_ata = create_associated_token_account(&payer, &payer, &reward_mint, &spl_token::id());
+    booth.process(&[create_ata], &[]).await.unwrap();
+    let recent_blockhash = booth.banks_client.get_latest_blockhash().await.unwrap();
+    mint_amount(
+        &mut booth.banks_client,
+        recent_blockhash,
+        &spl_token::id(),
+        &payer_reward_ata,
+        &reward_mint,
+        &booth.payer,
+        &booth.payer,
+        1.0,
+        MINT_DECIMALS,
+    )
+    .await
+    .unwrap();
+    reward_mint
+}
+
+#[tokio::test]
+async fn test_liquidity_mining() {
+    let mut booth = setup_booth().await;
+    let (program_id, payer, mint, vault) = (
+        booth.program_id,
+        booth.payer.pubkey(),
+        booth.mint.pubkey(),
+        booth.vault,
+    );
+    let reward_mint = setup_reward_mint(&mut booth).await;
+
+    // the setup deposit of 1 SOL minted as many LP tokens as lamports
+    let (lp_mint, _) = find_lp_mint_address(&program_id, &vault);
+    let payer_lp_ata = get_associated_token_address(&payer, &lp_mint);
+    assert_eq!(booth.token_balance(&payer_lp_ata).await, LAMPORTS_PER_SOL);
+    // later ones mint their share of the 1 SOL and 100 tokens (worth 10 SOL)
+    // in the vault, the tokens valued at the booth rate
+    let deposit = instruction::deposit(&program_id, &payer, &mint, 0, 1000, 5000);
+    booth.process(&[deposit], &[]).await.unwrap();
+    let reserves = LAMPORTS_PER_SOL + 100 * 10_u64.pow(MINT_DECIMALS as u32) / DEFAULT_RATE;
+    assert_eq!(
+        booth.token_balance(&payer_lp_ata).await,
+        LAMPORTS_PER_SOL + (1000 + 5000 / DEFAULT_RATE) * LAMPORTS_PER_SOL / reserves
+    );
+
+    // the pool must pay out in a different mint than it stakes
+    let same_mints = instruction::initialize_rewards(&program_id, &payer, &payer, &mint, 0, &lp_mint);
+    assert_booth_error(
+        booth.process(&[same_mints], &[]).await,
+        TokenTracingError::UniqueMintAccounts,
+    );
+
+    // anyone may create the pool token accounts before the pool exists
+    let (rewards, _) = find_rewards_address(&program_id, &vault);
+    let squat = create_associated_token_account(&payer, &rewards, &reward_mint, &spl_token::id());
+    booth.process(&[squat], &[]).await.unwrap();
+    let init = instruction::initialize_rewards(&program_id, &payer, &payer, &mint, 0, &reward_mint);
+    let fund = instruction::fund_rewards(&program_id, &payer, &payer, &mint, 0, &reward_mint, 1000, 10);
+    booth.process(&[init, fund], &[]).await.unwrap();
+    let pool_reward_ata = get_associated_token_address(&rewards, &reward_mint);
+    let pool_stake_ata = get_associated_token_address(&rewards, &lp_mint);
+    assert_eq!(booth.token_balance(&pool_reward_ata).await, 1000);
+    let account = booth.banks_client.get_account(rewards).await.unwrap().unwrap();
+    let pool = RewardPool::unpack(&account.data).unwrap();
+    assert_eq!(pool.stake_mint, lp_mint);
+    assert_eq!((pool.reward_rate, pool.undistributed), (10, 1000));
+
+    // a booth has one reward pool
+    let init = instruction::initialize_rewards(&program_id, &payer, &payer, &mint, 0, &reward_mint);
+    assert_booth_error(
+        booth.process(&[init], &[]).await,
+        TokenTracingError::InvalidAccountAddress,
+    );
+
+    let lp_before = booth.token_balance(&payer_lp_ata).await;
+    let stake = instruction::stake(&program_id, &payer, &mint, 0, 5000);
+    booth.process(&[stake], &[]).await.unwrap();
+    assert_eq!(booth.token_balance(&pool_stake_ata).await, 5000);
+    let (stake, _) = find_stake_address(&program_id, &rewards, &payer);
+    let account = booth.banks_client.get_account(stake).await.unwrap().unwrap();
+    assert_eq!(StakeAccount::unpack(&account.data).unwrap().amount, 5000);
+
+    // rewards earned so far leave the pool and nothing more
+    let payer_reward_ata = get_associated_token_address(&payer, &reward_mint);
+    let rewards_before = booth.token_balance(&payer_reward_ata).await;
+    let claim = instruction::claim_rewards(&program_id, &payer, &mint, 0, &reward_mint);
+    booth.process(&[claim], &[]).await.unwrap();
+    let claimed = booth.token_balance(&payer_reward_ata).await - rewards_before;
+    assert_eq!(booth.token_balance(&pool_reward_ata).await, 1000 - claimed);
+
+    let unstake = instruction::unstake(&program_id, &payer, &mint, 0, 5001);
+    assert_booth_error(
+        booth.process(&[unstake], &[]).await,
+        TokenTracingError::InsufficientFunds,
+    );
+    let unstake = instruction::unstake(&program_id, &payer, &mint, 0, 5000);
+    booth.process(&[unstake], &[]).await.unwrap();
+    assert_eq!(booth.token_balance(&payer_lp_ata).await, lp_before);
+    assert_eq!(booth.token_balance(&pool_stake_ata).await, 0);
+}
+
+#[tokio::test]
+async fn test_liquidity_mining_rewards_by_stake() {
+    let mut booth = setup_booth().await;
+    let (program_id, payer, mint, vault) = (
+        booth.program_id,
+        booth.payer.pubkey(),
+        booth.mint.pubkey(),
+        booth.vault,
+    );
+    let reward_mint = setup_reward_mint(&mut booth).await;
+    let staker = Keypair::new();
+    let staker_reward_ata = get_associated_token_address(&staker.pubkey(), &reward_mint);
+    // 1000 LP tokens' worth of the 11 SOL the vault's lamports and tokens are worth
+    let staker_deposit = instruction::deposit(&program_id, &staker.pubkey(), &mint, 0, 11_000, 0);
+    booth
+        .process(
+            &[
+                system_instruction::transfer(&payer, &staker.pubkey(), LAMPORTS_PER_SOL),
+                create_associated_token_account(&payer, &staker.pubkey(), &reward_mint, &spl_token::id()),
+                staker_deposit,
+                instruction::initialize_rewards(&program_id, &payer, &payer, &mint, 0, &reward_mint),
+                instruction::fund_rewards(&program_id, &payer, &payer, &mint, 0, &reward_mint, 1000, 10),
+                instruction::stake(&program_id, &payer, &mint, 0, 3000),
+                instruction::stake(&program_id, &staker.pubkey(), &mint, 0, 1000),
+            ],
+            &[&staker],
+        )
+        .await
+        .unwrap();
+    let (rewards, _) = find_rewards_address(&program_id, &vault);
+    let pending = |account: Option<solana_sdk::account::Account>| {
+        StakeAccount::unpack(&account.unwrap().data).unwrap().pending
+    };
+    let (payer_stake, _) = find_stake_address(&program_id, &rewards, &payer);
+    let (staker_stake, _) = find_stake_address(&program_id, &rewards, &staker.pubkey());
+
+    // 200 rewards over 20s split 3 to 1, settled as the staker leaves
+    booth.advance_clock(20).await;
+    let unstake = instruction::unstake(&program_id, &staker.pubkey(), &mint, 0, 1000);
+    booth.process(&[unstake], &[&staker]).await.unwrap();
+    let account = booth.banks_client.get_account(staker_stake).await.unwrap();
+    assert_eq!(pending(account), 50);
+
+    // the next 300 all go to the payer
+    booth.advance_clock(30).await;
+    let payer_reward_ata = get_associated_token_address(&payer, &reward_mint);
+    let rewards_before = booth.token_balance(&payer_reward_ata).await;
+    let claim = instruction::claim_rewards(&program_id, &payer, &mint, 0, &reward_mint);
+    booth.process(&[claim.clone()], &[]).await.unwrap();
+    assert_eq!(booth.token_balance(&payer_reward_ata).await, rewards_before + 450);
+
+    // the last 500 run the pool dry, short of the truncation dust it keeps
+    booth.advance_clock(100).await;
+    let unstake = instruction::unstake(&program_id, &payer, &mint, 0, 3000);
+    booth.process(&[unstake, claim], &[]).await.unwrap();
+    assert_eq!(booth.token_balance(&payer_reward_ata).await, rewards_before + 949);
+    let account = booth.banks_client.get_account(rewards).await.unwrap().unwrap();
+    assert_eq!(RewardPool::unpack(&account.data).unwrap().undistributed, 1);
+    let account = booth.banks_client.get_account(payer_stake).await.unwrap();
+    assert_eq!(pending(account), 0);
+
+    let claim = instruction::claim_rewards(&program_id, &staker.pubkey(), &mint, 0, &reward_mint);
+    booth.process(&[claim], &[&staker]).await.unwrap();
+    assert_eq!(booth.token_balance(&staker_reward_ata).await, 50);
+    let pool_reward_ata = get_associated_token_address(&rewards, &reward_mint);
+    assert_eq!(booth.token_balance(&pool_reward_ata).await, 1);
+}
+
+#[tokio::test]
+async fn test_remove_liquidity() {
+    let mut booth = setup_booth().await;
+    let (program_id, payer, mint, vault) = (
+        booth.program_id,
+        booth.payer.pubkey(),
+        booth.mint.pubkey(),
+        booth.vault,
+    );
+    let (lp_mint, _) = find_lp_mint_address(&program_id, &vault);
+    let lp_supply = |account: Option<solana_sdk::account::Account>| {
+        Mint::unpack(&account.unwrap().data).unwrap().supply
+    };
+    let depositor = Keypair::new();
+    let depositor_lp_ata = get_associated_token_address(&depositor.pubkey(), &lp_mint);
+    let depositor_ata = get_associated_token_address(&depositor.pubkey(), &mint);
+    let vault_ata = booth.vault_ata();
+
+    // 1.1 SOL is a tenth of the 11 SOL the vault's lamports and tokens are worth
+    let deposit = instruction::deposit(&program_id, &depositor.pubkey(), &mint, 0, 1_100_000_000, 0);
+    booth
+        .process(
+            &[
+                system_instruction::transfer(&payer, &depositor.pubkey(), 2 * LAMPORTS_PER_SOL),
+                create_associated_token_account(&payer, &depositor.pubkey(), &mint, &spl_token::id()),
+                deposit.clone(),
+            ],
+            &[&depositor],
+        )
+        .await
+        .unwrap();
+    let held = booth.token_balance(&depositor_lp_ata).await;
+    let supply = lp_supply(booth.banks_client.get_account(lp_mint).await.unwrap());
+    assert_eq!((held, supply), (LAMPORTS_PER_SOL / 10, LAMPORTS_PER_SOL * 11 / 10));
+
+    let remove = |lp_amount| {
+        instruction::remove_liquidity(&program_id, &depositor.pubkey(), &mint, 0, lp_amount)
+    };
+    assert_booth_error(
+        booth.process(&[remove(0)], &[&depositor]).await,
+        TokenTracingError::InvalidInstructionData,
+    );
+    assert_booth_error(
+        booth.process(&[remove(held + 1)], &[&depositor]).await,
+        TokenTracingError::InsufficientFunds,
+    );
+
+    // burning them pays a share of both legs, rounded down, even while paused
+    let (vault_lamports, vault_tokens) = (booth.lamports(&vault).await, booth.token_balance(&vault_ata).await);
+    let lamports_before = booth.lamports(&depositor.pubkey()).await;
+    let pause = instruction::set_paused(&program_id, &payer, &mint, 0, true);
+    booth.process(&[pause, remove(held)], &[&depositor]).await.unwrap();
+    assert_eq!(booth.token_balance(&depositor_lp_ata).await, 0);
+    assert_eq!(booth.lamports(&depositor.pubkey()).await, lamports_before + 190_909_090);
+    assert_eq!(booth.lamports(&vault).await, vault_lamports - 190_909_090);
+    assert_eq!(booth.token_balance(&depositor_ata).await, 9_090_909_090);
+    assert_eq!(booth.token_balance(&vault_ata).await, vault_tokens - 9_090_909_090);
+    let account = booth.banks_client.get_account(lp_mint).await.unwrap();
+    assert_eq!(lp_supply(account), LAMPORTS_PER_SOL);
+
+    // depositing the same again never buys a larger share of the supply
+    let resume = instruction::set_paused(&program_id, &payer, &mint, 0, false);
+    booth.process(&[resume, deposit], &[&depositor]).await.unwrap();
+    let held_again = booth.token_balance(&depositor_lp_ata).await;
+    let supply_again = lp_supply(booth.banks_client.get_account(lp_mint).await.unwrap());
+    ma::assert_le!(held_again as u128 * supply as u128, held as u128 * supply_again as u128);
+    assert_eq!(held_again, held - 1);
+}
+
+#[test]
+fn test_unpack_instruction_layouts() {
+    // Borsh layout: tag followed by a u64 amount
+    let amount = 5 * 1_000_000_000_u64;
+    let data = TokenTracingInstruction::ExchangeSOLToToken { amount }.pack();
+    assert_eq!(data.len(), 9);
+    assert_eq!(
+        TokenTracingInstruction::unpack(&data).unwrap(),
+        TokenTracingInstruction::ExchangeSOLToToken { amount }
+    );
+
+    // legacy layout from the TypeScript client: tag followed by a u32 amount
+    let mut legacy = vec![2_u8];
+    legacy.extend_from_slice(&1_000_000_000_u32.to_le_bytes());
+    assert_eq!(
+        TokenTracingInstruction::unpack(&legacy).unwrap(),
+        TokenTracingInstruction::ExchangeTokenToSOL { amount: 1_000_000_000 }
+    );
+    assert_eq!(
+        TokenTracingInstruction::unpack(&[0]).unwrap(),
+        TokenTracingInstruction::Initialize
+    );
+
+    // short or unknown data is rejected instead of panicking
+    assert!(TokenTracingInstruction::unpack(&[]).is_err());
+    assert!(TokenTracingInstruction::unpack(&[1, 0, 0]).is_err());
+    assert!(TokenTracingInstruction::unpack(&[7, 0, 0, 0, 0]).is_err());
+}
+
+#[test]
+fn test_exchange_account_layout() {
+    let exchange = ExchangeAccount {
+        admin: Pubkey::new_unique(),
+        vault: Pubkey::new_unique(),
+        mint: Pubkey::new_unique(),
+        rate: 10,
+        ..ExchangeAccount::default()
+    };
+    let mut data = vec![0; EXCHANGE_ACCOUNT_LEN];
+    exchange.pack(&mut data).unwrap();
+    assert_eq!(&data[..8], &ExchangeAccount::DISCRIMINATOR);
+    assert_eq!(ExchangeAccount::unpack(&data).unwrap().admin, exchange.admin);
+
+    // a raw 64-byte account with two pubkeys is not a booth
+    let mut raw = vec![0; 64];
+    raw[..32].copy_from_slice(exchange.admin.as_ref());
+    assert!(ExchangeAccount::unpack(&raw).is_err());
+
+    let mut wrong_version = data.clone();
+    wrong_version[8] += 1;
+    assert!(ExchangeAccount::unpack(&wrong_version).is_err());
+}
+
+#[test]
+fn test_quotes_use_checked_math() {
+    let exchange = ExchangeAccount {
+        rate: 10,
+        ..ExchangeAccount::default()
+    };
+    assert_eq!(exchange.quote_sol_to_token(1000).unwrap(), 10_000);
+    assert_eq!(exchange.quote_token_to_sol(1000).unwrap(), 100);
+    assert!(exchange.quote_sol_to_token(u64::MAX).is_err());
+    let with_fee = ExchangeAccount {
+        fee_bps: 30,
+        ..exchange
+    };
+    assert_eq!(with_fee.quote_sol_to_token(1000).unwrap(), 9_970);
+    assert_eq!(with_fee.quote_token_to_sol(10_000).unwrap(), 997);
+    assert_eq!(math::checked_mul_div(u64::MAX, 2, 4).unwrap(), u64::MAX / 2);
+    assert!(math::checked_sub(1, 2).is_err());
+}
+
+#[cfg(feature = "cpi")]
+#[test]
+fn test_cpi_accounts_match_builders() {
+    use tokentracing::cpi::{Exchange, Referral};
+
+    let (program_id, payer, mint) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let (attestor, referrer) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let built = instruction::with_referrer(
+        &program_id,
+        instruction::with_attestation(
+            &program_id,
+            instruction::exchange_sol_to_token(&program_id, &payer, &mint, 0, 1000),
+            &attestor,
+        ),
+        &referrer,
+    );
+    assert_eq!(built.accounts.len(), 14);
+
+    let keys: Vec<Pubkey> = built.accounts.iter().map(|account| account.pubkey).collect();
+    let mut lamports = vec![0; keys.len()];
+    let mut data = vec![Vec::new(); keys.len()];
+    let infos: Vec<AccountInfo> = keys
+        .iter()
+        .zip(lamports.iter_mut())
+        .zip(data.iter_mut())
+        .map(|((key, lamports), data)| {
+            AccountInfo::new(key, false, false, lamports, data, &program_id, false, 0)
+        })
+        .collect();
+    let mut exchange = Exchange {
+        program: infos[0].clone(),
+        payer: infos[1].clone(),
+        payer_token_account: infos[2].clone(),
+        mint: infos[3].clone(),
+        vault: infos[4].clone(),
+        vault_token_account: infos[5].clone(),
+        token_program: infos[6].clone(),
+        system_program: infos[7].clone(),
+        wallet_stats: Some(infos[8].clone()),
+        access_entry: Some(infos[9].clone()),
+        attestation: Some(infos[10].clone()),
+        referral: Some(Referral {
+            referrer: infos[11].clone(),
+            referrer_token_account: infos[12].clone(),
+            referrer_stats: infos[13].clone(),
+        }),
+    };
+    let amount = 1000;
+    assert_eq!(
+        exchange.instruction(TokenTracingInstruction::ExchangeSOLToToken { amount }),
+        built
+    );
+
+    // a skipped attestation is padded so the referrer keeps its place
+    exchange.attestation = None;
+    let built = instruction::with_referrer(
+        &program_id,
+        instruction::exchange_sol_to_token(&program_id, &payer, &mint, 0, amount),
+        &referrer,
+    );
+    assert_eq!(built.accounts[10].pubkey, program_id);
+    assert_eq!(
+        exchange.instruction(TokenTracingInstruction::ExchangeSOLToToken { amount }),
+        built
+    );
+
+    // without the optional accounts only the fixed swap accounts remain
+    exchange.wallet_stats = None;
+    exchange.access_entry = None;
+    exchange.attestation = None;
+    exchange.referral = None;
+    let mut built = instruction::exchange_token_to_sol(&program_id, &payer, &mint, 0, amount);
+    built.accounts.truncate(8);
+    assert_eq!(
+        exchange.instruction(TokenTracingInstruction::ExchangeTokenToSOL { amount }),
+        built
+    );
+}
+
+#[cfg(feature = "cpi")]
+#[tokio::test]
+async fn test_cpi_swap_pays_referrer() {
+    let router = Pubkey::new_unique();
+    let mut booth = setup_booth_with(|program_test| {
+        program_test.add_program("referral_router", router, processor!(route_referred_swap));
+    })
+    .await;
+    let (program_id, payer, mint) = (booth.program_id, booth.payer.pubkey(), booth.mint.pubkey());
+    let fee = instruction::propose_change(&program_id, &payer, &mint, 0, 10, 30, CurveType::ConstantPrice, 0);
+    let share = instruction::set_referral_share(&program_id, &payer, &mint, 0, 5000);
+    booth.apply_change(fee, 0).await;
+    let referrer = Keypair::new().pubkey();
+    let referrer_ata = get_associated_token_address(&referrer, &mint);
+    booth
+        .process(
+            &[
+                share,
+                system_instruction::transfer(&payer, &referrer, LAMPORTS_PER_SOL),
+                create_associated_token_account(&payer, &referrer, &mint, &spl_token::id()),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+
+    // the booth names no attestor, so the router leaves the attestation out
+    let mut accounts = instruction::with_referrer(
+        &program_id,
+        instruction::exchange_sol_to_token(&program_id, &payer, &mint, 0, 1000),
+        &referrer,
+    )
+    .accounts;
+    accounts.remove(10);
+    let routed = Instruction {
+        program_id: router,
+        accounts,
+        data: 1000_u64.to_le_bytes().to_vec(),
+    };
+    let payer_ata = booth.payer_ata();
+    let tokens_before = booth.token_balance(&payer_ata).await;
+    booth.process(&[routed], &[]).await.unwrap();
+    assert_eq!(booth.token_balance(&payer_ata).await, tokens_before + 9970);
+    assert_eq!(booth.token_balance(&referrer_ata).await, 15);
+}